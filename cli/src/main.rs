@@ -0,0 +1,291 @@
+/*!
+
+Command line tool for Rigol waveform files
+
+*/
+use clap::{builder::RangedU64ValueParser, Parser, Subcommand};
+use rigol_wfm::{
+    analysis::{clipping, persistence::Persistence, Mask},
+    batch::{parse_file, Watcher},
+    decode::sigrok::Sigrok,
+    export::{self, Format},
+    Channel, Threshold,
+};
+use std::{
+    error::Error,
+    fs::File,
+    io::{self, BufWriter},
+    path::PathBuf,
+    process::exit,
+    time::Duration,
+};
+
+#[cfg(feature = "serve")]
+mod serve;
+mod view;
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print summary of waveform
+    Info { file: PathBuf },
+    /// Browse waveform in terminal
+    View { file: PathBuf },
+    /// Decode protocols with sigrok decoders
+    Sigrok {
+        file: PathBuf,
+        /// Decoder with options, like uart:baudrate=9600:rx=0
+        #[arg(short = 'P', long = "decoder", required = true)]
+        decoders: Vec<String>,
+        /// Print only given annotations, like uart=rx-data
+        #[arg(short = 'A', long = "annotation")]
+        annotations: Vec<String>,
+        /// Decode analog channels on lines 0 and 1 instead of logic analyzer
+        #[arg(long)]
+        analog: bool,
+        /// Path to sigrok-cli
+        #[arg(long, default_value = "sigrok-cli")]
+        program: PathBuf,
+        /// Print Audacity label track with times from record start
+        #[arg(long)]
+        labels: bool,
+    },
+    /// Accumulate captures into persistence map
+    Persistence {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Channel number
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u8).range(1..=2))]
+        channel: u8,
+        /// Output file, PNG image or CSV counts by extension
+        #[arg(long, short)]
+        output: PathBuf,
+        /// Map width in cells
+        #[arg(
+            long,
+            default_value_t = 600,
+            value_parser = RangedU64ValueParser::<usize>::new().range(1..)
+        )]
+        width: usize,
+        /// Map height in cells
+        #[arg(
+            long,
+            default_value_t = 400,
+            value_parser = RangedU64ValueParser::<usize>::new().range(1..)
+        )]
+        height: usize,
+    },
+    /// Test captures against mask around golden capture
+    Mask {
+        golden: PathBuf,
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Channel number
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u8).range(1..=2))]
+        channel: u8,
+        /// Voltage margin in units
+        #[arg(long, default_value_t = 0.1)]
+        volts: f32,
+        /// Time margin in seconds
+        #[arg(long, default_value_t = 0.0)]
+        time: f32,
+    },
+    /// Write captures to TDMS file as groups named by files
+    Tdms {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Convert new waveforms appearing in directory
+    WatchDir {
+        dir: PathBuf,
+        /// Directory for converted files and index, same as watched by default
+        #[arg(long)]
+        output: Option<PathBuf>,
+        /// Output format: csv, json or raw (LTspice)
+        #[arg(long, default_value = "csv")]
+        format: Format,
+        /// Scan interval in seconds
+        #[arg(long, default_value_t = 2)]
+        interval: u64,
+    },
+    /// Serve conversions of uploaded waveforms over HTTP
+    #[cfg(feature = "serve")]
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        address: String,
+        /// Limit of waveform data in bytes
+        #[arg(long, default_value_t = serve::MAX_BYTES)]
+        max_bytes: usize,
+    },
+}
+
+fn run(command: Command) -> Result<(), Box<dyn Error>> {
+    match command {
+        Command::Info { file } => {
+            let data = parse_file(file)?;
+            print!("{}", data.summary());
+            for channel in clipping(&data)
+                .iter()
+                .filter(|channel| channel.is_clipped())
+            {
+                println!(
+                    "{:?}: {:.2}% of samples clipped in {} runs",
+                    channel.channel,
+                    channel.percent(),
+                    channel.runs.len()
+                );
+            }
+        }
+        Command::View { file } => view::run(&parse_file(file)?)?,
+        Command::Sigrok {
+            file,
+            decoders,
+            annotations,
+            analog,
+            program,
+            labels,
+        } => {
+            let data = parse_file(file)?;
+            let logic = if analog {
+                data.analog_logic(Threshold::default())
+            } else {
+                data.logic()
+            }
+            .ok_or("No samples to decode")?;
+
+            let sigrok = Sigrok {
+                program,
+                decoders,
+                annotations,
+            };
+            let events = sigrok.decode(&logic)?;
+            if labels {
+                export::write_labels(&events, logic.time_offset as f64, io::stdout().lock())?;
+            } else {
+                for event in events {
+                    println!(
+                        "{:e}\t{:e}\t{}\t{}",
+                        event.start, event.end, event.decoder, event.text
+                    );
+                }
+            }
+        }
+        Command::Persistence {
+            files,
+            channel,
+            output,
+            width,
+            height,
+        } => {
+            let channel = if channel == 1 {
+                Channel::Ch1
+            } else {
+                Channel::Ch2
+            };
+            let mut persistence = None;
+            for file in &files {
+                let data = parse_file(file)?;
+                if persistence.is_none() {
+                    persistence = Persistence::screen(&data, channel, width, height);
+                }
+                if let (Some(persistence), Some(trace)) = (&mut persistence, data.trace(channel)) {
+                    persistence.add(&trace);
+                }
+            }
+            let persistence = persistence.ok_or("Channel is disabled")?;
+
+            let writer = BufWriter::new(File::create(&output)?);
+            match output.extension().and_then(|extension| extension.to_str()) {
+                Some("csv") => export::write_density_csv(&persistence.density, writer)?,
+                _ => export::write_density_png(&persistence.density, writer)?,
+            }
+            eprintln!(
+                "{} of {} captures accumulated",
+                persistence.captures,
+                files.len()
+            );
+        }
+        Command::Mask {
+            golden,
+            files,
+            channel,
+            volts,
+            time,
+        } => {
+            let channel = if channel == 1 {
+                Channel::Ch1
+            } else {
+                Channel::Ch2
+            };
+            let mask = Mask::golden(&parse_file(golden)?, channel, volts, time)?;
+            let mut failed = 0;
+            for file in &files {
+                let report = mask.test(&parse_file(file)?)?;
+                match report.violations.first() {
+                    None => println!("{}: pass", file.display()),
+                    Some(first) => {
+                        failed += 1;
+                        println!(
+                            "{}: fail, {} of {} samples outside of mask, first at {:e} s",
+                            file.display(),
+                            report.violations.len(),
+                            report.tested,
+                            first.time
+                        );
+                    }
+                }
+            }
+            if failed > 0 {
+                return Err(format!("{} of {} captures failed", failed, files.len()).into());
+            }
+        }
+        Command::Tdms { files, output } => {
+            let captures = files
+                .iter()
+                .map(|file| {
+                    let name = file
+                        .file_stem()
+                        .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+                    Ok((name, parse_file(file)?))
+                })
+                .collect::<Result<Vec<_>, rigol_wfm::Error>>()?;
+            export::write_tdms(
+                captures.iter().map(|(name, data)| (name.as_str(), data)),
+                BufWriter::new(File::create(output)?),
+            )?;
+        }
+        Command::WatchDir {
+            dir,
+            output,
+            format,
+            interval,
+        } => {
+            let output = output.unwrap_or_else(|| dir.clone());
+            Watcher::new(dir, output, format)?.watch(Duration::from_secs(interval), |entry| {
+                match &entry.result {
+                    Ok(output) => println!("{} -> {}", entry.name, output),
+                    Err(error) => eprintln!("{}: {}", entry.name, error),
+                }
+            })?
+        }
+        #[cfg(feature = "serve")]
+        Command::Serve { address, max_bytes } => serve::run(&address, max_bytes)?,
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(error) = run(Args::parse().command) {
+        eprintln!("Error: {}", error);
+        exit(1);
+    }
+}
@@ -0,0 +1,181 @@
+/*!
+
+HTTP conversion service
+
+Waveform uploaded with `POST /csv` or `POST /json` is answered with its
+conversion, `POST /png` answers with image of channel on screen, first
+enabled one or given like `/png?channel=2`. Uploads are parsed while
+reading with size limit, so data which header claims beyond the limit is
+rejected before it is received.
+
+*/
+use rigol_wfm::{
+    analysis::persistence::Persistence, export, parse_reader_with, Channel, Error, ParseOptions,
+    WaveformData,
+};
+use std::io::Read;
+use tiny_http::{Header, Method, Request, Server};
+
+/// Default limit of waveform data in bytes
+pub const MAX_BYTES: usize = 64 << 20;
+
+/// Size of image in pixels
+const IMAGE_SIZE: (usize, usize) = (600, 400);
+
+/// Conversion result
+#[derive(Debug, Clone, PartialEq)]
+struct Reply {
+    status: u16,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Reply {
+    fn text(status: u16, text: impl Into<String>) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: text.into().into_bytes(),
+        }
+    }
+}
+
+/// Write image of channel on screen
+fn write_png(
+    data: &WaveformData,
+    channel: Option<Channel>,
+    body: &mut Vec<u8>,
+) -> Result<(), String> {
+    let channel = channel
+        .or_else(|| {
+            [Channel::Ch1, Channel::Ch2]
+                .iter()
+                .copied()
+                .find(|&channel| data.trace(channel).is_some())
+        })
+        .ok_or("No enabled channels")?;
+    let trace = data.trace(channel).ok_or("Channel is disabled")?;
+    let (width, height) = IMAGE_SIZE;
+    let mut screen =
+        Persistence::screen(data, channel, width, height).ok_or("Channel is disabled")?;
+    screen.add(&trace);
+    export::write_density_png(&screen.density, body).map_err(|error| error.to_string())
+}
+
+/// Convert uploaded waveform according to request
+fn convert(method: &Method, url: &str, body: impl Read, max_bytes: usize) -> Reply {
+    let mut url = url.splitn(2, '?');
+    let path = url.next().unwrap_or_default();
+    let channel = match url
+        .next()
+        .unwrap_or_default()
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("channel="))
+    {
+        None => None,
+        Some("1") => Some(Channel::Ch1),
+        Some("2") => Some(Channel::Ch2),
+        Some(_) => return Reply::text(400, "Channel should be 1 or 2\n"),
+    };
+
+    let content_type = match path {
+        "/" => {
+            return Reply::text(
+                200,
+                "POST waveform file to /csv, /json or /png to get it converted\n",
+            )
+        }
+        "/csv" => "text/csv",
+        "/json" => "application/json",
+        "/png" => "image/png",
+        _ => return Reply::text(404, "Not found\n"),
+    };
+    if *method != Method::Post {
+        return Reply::text(405, "Method not allowed\n");
+    }
+
+    let data = match parse_reader_with(body, ParseOptions::new().max_bytes(max_bytes)) {
+        Ok(data) => data,
+        Err(error @ Error::TooLarge { .. }) => return Reply::text(413, format!("{}\n", error)),
+        Err(error) => return Reply::text(400, format!("{}\n", error)),
+    };
+
+    let mut body = Vec::new();
+    let result = match path {
+        "/csv" => export::write_csv(&data, &mut body).map_err(|error| error.to_string()),
+        "/png" => write_png(&data, channel, &mut body),
+        _ => export::write_json(&data, &mut body).map_err(|error| error.to_string()),
+    };
+    match result {
+        Ok(()) => Reply {
+            status: 200,
+            content_type,
+            body,
+        },
+        Err(error) => Reply::text(500, format!("{}\n", error)),
+    }
+}
+
+fn handle(mut request: Request, max_bytes: usize) -> std::io::Result<()> {
+    let url = request.url().to_string();
+    let method = request.method().clone();
+    let reply = convert(&method, &url, request.as_reader(), max_bytes);
+
+    let header = Header::from_bytes(&b"Content-Type"[..], reply.content_type)
+        .expect("Content type is valid header");
+    request.respond(
+        tiny_http::Response::from_data(reply.body)
+            .with_status_code(reply.status)
+            .with_header(header),
+    )
+}
+
+/// Serve conversions on address until failure
+pub fn run(address: &str, max_bytes: usize) -> Result<(), Box<dyn std::error::Error>> {
+    let server = Server::http(address).map_err(|error| error.to_string())?;
+    eprintln!("Listening on http://{}", server.server_addr());
+
+    for request in server.incoming_requests() {
+        // Client gone away, nothing to report
+        let _ = handle(request, max_bytes);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ds1052e_2ch() {
+        let input = std::fs::read("../wfm/test/ds1052e_2ch.wfm").unwrap();
+
+        let reply = convert(&Method::Post, "/csv", &input[..], MAX_BYTES);
+        assert_eq!(reply.status, 200);
+        assert_eq!(reply.content_type, "text/csv");
+        assert!(reply.body.starts_with(b"Time"));
+
+        let reply = convert(&Method::Post, "/json", &input[..], MAX_BYTES);
+        assert_eq!(reply.status, 200);
+        assert_eq!(reply.body[0], b'{');
+
+        assert_eq!(convert(&Method::Post, "/csv", &input[..], 1024).status, 413);
+        assert_eq!(
+            convert(&Method::Post, "/csv", &input[..100], MAX_BYTES).status,
+            400
+        );
+        assert_eq!(
+            convert(&Method::Get, "/csv", &input[..], MAX_BYTES).status,
+            405
+        );
+
+        let reply = convert(&Method::Post, "/png?channel=2", &input[..], MAX_BYTES);
+        assert_eq!(reply.status, 200);
+        assert_eq!(reply.content_type, "image/png");
+        assert_eq!(reply.body[1..4], *b"PNG");
+        assert_eq!(
+            convert(&Method::Post, "/png?channel=3", &input[..], MAX_BYTES).status,
+            400
+        );
+    }
+}
@@ -0,0 +1,299 @@
+/*!
+
+Terminal waveform browser
+
+Traces are drawn with braille characters, header summary and measurements
+of visible part are shown in sidebar. Keys: left/right scroll, up/down
+zoom, `Home` shows whole record, `1`/`2` toggle channels, `q` quits.
+
+*/
+use ratatui::{
+    crossterm::event::{self, KeyCode},
+    layout::{Constraint, Layout},
+    style::{Color, Style},
+    symbols::Marker,
+    text::Line,
+    widgets::{canvas, Block, Paragraph},
+    DefaultTerminal, Frame,
+};
+use rigol_wfm::{
+    analysis::{envelope::Envelope, measure::Measurements, Gate},
+    Channel, Summary, Trace, Viewport, WaveformData,
+};
+use std::io;
+
+/// Number of vertical divisions
+const ROWS: f64 = 8.0;
+
+/// Width of sidebar in cells
+const SIDEBAR_WIDTH: u16 = 44;
+
+/// Fraction of window moved by single scroll
+const SCROLL: f32 = 0.1;
+
+/// Zoom factor of single step
+const ZOOM: f32 = 2.0;
+
+/// Scaled channel with display settings
+struct ChannelView {
+    channel: Channel,
+    trace: Trace,
+    /// Vertical scale in units per division
+    scale: f32,
+    /// Level of screen center in units
+    center: f32,
+    visible: bool,
+    /// Measurements of visible part
+    measurements: Measurements,
+}
+
+impl ChannelView {
+    /// Color like on instrument screen
+    fn color(&self) -> Color {
+        match self.channel {
+            Channel::Ch1 => Color::Yellow,
+            Channel::Ch2 => Color::Cyan,
+        }
+    }
+
+    /// Position in divisions from screen center
+    fn divisions(&self, value: f32) -> f64 {
+        ((value - self.center) / self.scale) as f64
+    }
+}
+
+struct Browser {
+    summary: Summary,
+    channels: Vec<ChannelView>,
+    window: Viewport,
+}
+
+impl Browser {
+    fn new(data: &WaveformData) -> Self {
+        let channels = data
+            .screen_traces()
+            .into_iter()
+            .map(|screen| ChannelView {
+                channel: screen.channel,
+                scale: screen.scale,
+                center: screen.center,
+                visible: true,
+                measurements: Measurements::of(&screen.trace),
+                trace: screen.trace,
+            })
+            .collect::<Vec<_>>();
+
+        Self {
+            summary: data.summary(),
+            window: Viewport::of(channels.iter().map(|view| &view.trace)),
+            channels,
+        }
+    }
+
+    /// Measure visible part of traces
+    fn measure(&mut self) {
+        let gate = Gate::time(self.window.start, self.window.end);
+        for view in &mut self.channels {
+            view.measurements = Measurements::gated(&view.trace, &gate);
+        }
+    }
+
+    /// Handle key, returns `false` to quit
+    fn key(&mut self, code: KeyCode) -> bool {
+        let window = self.window;
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Left => self.window.pan(-SCROLL * window.width()),
+            KeyCode::Right => self.window.pan(SCROLL * window.width()),
+            KeyCode::Up | KeyCode::Char('+') => self.window.zoom(window.center(), ZOOM),
+            KeyCode::Down | KeyCode::Char('-') => self.window.zoom(window.center(), 1.0 / ZOOM),
+            KeyCode::Home => self.window.reset(),
+            KeyCode::Char(key @ '1'..='2') => {
+                let channel = if key == '1' {
+                    Channel::Ch1
+                } else {
+                    Channel::Ch2
+                };
+                for view in &mut self.channels {
+                    if view.channel == channel {
+                        view.visible = !view.visible;
+                    }
+                }
+            }
+            _ => (),
+        }
+        if self.window != window {
+            self.measure();
+        }
+        true
+    }
+
+    fn sidebar(&self) -> Vec<Line<'_>> {
+        let mut lines = self
+            .summary
+            .to_string()
+            .lines()
+            .map(|line| Line::from(line.to_string()))
+            .collect::<Vec<_>>();
+
+        for view in self.channels.iter().filter(|view| view.visible) {
+            let m = &view.measurements;
+            let unit = format!("{:?}", view.trace.unit);
+            lines.push(Line::default());
+            lines.push(Line::styled(
+                format!("{:?}", view.channel),
+                Style::default().fg(view.color()),
+            ));
+            for (name, value, unit) in [
+                ("Vpp", m.vpp, unit.as_str()),
+                ("Mean", m.mean, &unit),
+                ("RMS", m.rms, &unit),
+                ("Frequency", m.frequency, "Hz"),
+                ("Rise time", m.rise_time, "s"),
+            ]
+            .iter()
+            {
+                lines.push(Line::from(match value {
+                    Some(value) => format!("  {:<10} {:.4e} {}", name, value, unit),
+                    None => format!("  {:<10} -", name),
+                }));
+            }
+        }
+        lines
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let [sidebar, plot] =
+            Layout::horizontal([Constraint::Length(SIDEBAR_WIDTH), Constraint::Min(0)])
+                .areas(frame.area());
+
+        frame.render_widget(
+            Paragraph::new(self.sidebar()).block(Block::bordered().title("Header")),
+            sidebar,
+        );
+
+        let window = self.window;
+        // Braille character has two dot columns
+        let columns = (plot.width.saturating_sub(2) as usize * 2).max(1);
+        let title = format!(
+            "{:.4e} s .. {:.4e} s, {:.3e} s/div",
+            window.start,
+            window.end,
+            window.width() / 12.0
+        );
+
+        let canvas = canvas::Canvas::default()
+            .block(Block::bordered().title(title))
+            .marker(Marker::Braille)
+            .x_bounds([window.start as f64, window.end as f64])
+            .y_bounds([-ROWS / 2.0, ROWS / 2.0])
+            .paint(|ctx| {
+                ctx.draw(&canvas::Line::new(
+                    window.start as f64,
+                    0.0,
+                    window.end as f64,
+                    0.0,
+                    Color::DarkGray,
+                ));
+                ctx.draw(&canvas::Line::new(
+                    0.0,
+                    -ROWS / 2.0,
+                    0.0,
+                    ROWS / 2.0,
+                    Color::DarkGray,
+                ));
+                ctx.layer();
+
+                for view in self.channels.iter().filter(|view| view.visible) {
+                    draw(ctx, &window, view, columns);
+                }
+            });
+        frame.render_widget(canvas, plot);
+    }
+
+    fn main_loop(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.render(frame))?;
+            if let Some(key) = event::read()?.as_key_press_event() {
+                if !self.key(key.code) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Draw visible part of trace decimated to columns
+fn draw(ctx: &mut canvas::Context, window: &Viewport, view: &ChannelView, columns: usize) {
+    let trace = &view.trace;
+    let step = trace.time_step();
+    let from = ((window.start - trace.time_offset) / step).floor().max(0.0) as usize;
+    let to = ((window.end - trace.time_offset) / step).ceil().max(0.0) as usize + 1;
+    if from >= trace.len() || from >= to {
+        return;
+    }
+
+    let buckets = columns.clamp(1, to.min(trace.len()) - from);
+    let envelope = Envelope::of_range(trace, from..to, buckets);
+    let color = view.color();
+
+    let mut previous: Option<(f64, f64)> = None;
+    for (index, bucket) in envelope.buckets.iter().enumerate() {
+        let time = envelope.time(index) as f64;
+        let (low, high) = (view.divisions(bucket.min), view.divisions(bucket.max));
+
+        // Connect to previous bucket to keep trace continuous
+        if let Some((x, y)) = previous {
+            ctx.draw(&canvas::Line::new(x, y, time, low, color));
+        }
+        ctx.draw(&canvas::Line::new(time, low, time, high, color));
+        previous = Some((time, high));
+    }
+}
+
+/// Browse waveform until quit
+pub fn run(data: &WaveformData) -> io::Result<()> {
+    let mut browser = Browser::new(data);
+    ratatui::run(|terminal| browser.main_loop(terminal))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ratatui::{backend::TestBackend, Terminal};
+    use rigol_wfm::batch::parse_file;
+
+    #[test]
+    fn ds1052e_2ch() {
+        let data = parse_file("../wfm/test/ds1052e_2ch.wfm").unwrap();
+        let mut browser = Browser::new(&data);
+        let full = browser.window;
+
+        assert!(browser.key(KeyCode::Up));
+        assert!((browser.window.width() - full.width() / 2.0).abs() < 1.0e-6);
+        for _ in 0..10 {
+            browser.key(KeyCode::Left);
+        }
+        assert_eq!(browser.window.start, full.start);
+        browser.key(KeyCode::Home);
+        assert_eq!(browser.window, full);
+        // Whole record is measured again
+        let view = &browser.channels[0];
+        assert_eq!(view.measurements, Measurements::of(&view.trace));
+        browser.key(KeyCode::Char('2'));
+        assert!(!browser.channels[1].visible);
+        assert!(!browser.key(KeyCode::Char('q')));
+
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+        terminal.draw(|frame| browser.render(frame)).unwrap();
+        let text = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect::<String>();
+        assert!(text.contains("Model: DS1000E"));
+        assert!(text.contains("s/div"));
+    }
+}
@@ -0,0 +1,176 @@
+/*!
+
+Desktop waveform viewer
+
+Drop .wfm file onto the window or pass its path as argument. Traces are
+zoomed with mouse wheel, panned by dragging and reset by double click.
+
+*/
+use eframe::egui;
+use rigol_wfm::{
+    analysis::measure::Measurements, parse, Channel, Result, Summary, Trace, Viewport,
+};
+
+mod plot;
+
+/// Scaled channel with display settings
+pub struct ChannelView {
+    pub channel: Channel,
+    pub trace: Trace,
+    /// Vertical scale in units per division
+    pub scale: f32,
+    /// Level of screen center in units
+    pub center: f32,
+    pub visible: bool,
+    measurements: Measurements,
+}
+
+impl ChannelView {
+    /// Color like on instrument screen
+    pub fn color(&self) -> egui::Color32 {
+        match self.channel {
+            Channel::Ch1 => egui::Color32::YELLOW,
+            Channel::Ch2 => egui::Color32::from_rgb(0x00, 0xbf, 0xff),
+        }
+    }
+}
+
+/// Loaded waveform
+struct Capture {
+    name: String,
+    summary: Summary,
+    channels: Vec<ChannelView>,
+    view: Viewport,
+}
+
+impl Capture {
+    fn load(name: String, input: &[u8]) -> Result<Self> {
+        let data = parse(input)?;
+
+        let channels = data
+            .screen_traces()
+            .into_iter()
+            .map(|screen| ChannelView {
+                channel: screen.channel,
+                scale: screen.scale,
+                center: screen.center,
+                visible: true,
+                measurements: Measurements::of(&screen.trace),
+                trace: screen.trace,
+            })
+            .collect::<Vec<_>>();
+
+        Ok(Self {
+            name,
+            summary: data.summary(),
+            view: Viewport::of(channels.iter().map(|view| &view.trace)),
+            channels,
+        })
+    }
+
+    fn sidebar(&mut self, ui: &mut egui::Ui) {
+        ui.heading(&self.name);
+        ui.monospace(self.summary.to_string());
+        ui.separator();
+
+        for view in &mut self.channels {
+            let label = egui::RichText::new(format!("{:?}", view.channel)).color(view.color());
+            ui.checkbox(&mut view.visible, label);
+            if !view.visible {
+                continue;
+            }
+
+            let m = &view.measurements;
+            let unit = format!("{:?}", view.trace.unit);
+            let rows = [
+                ("Vmax", m.vmax, unit.as_str()),
+                ("Vmin", m.vmin, &unit),
+                ("Vpp", m.vpp, &unit),
+                ("Mean", m.mean, &unit),
+                ("RMS", m.rms, &unit),
+                ("Frequency", m.frequency, "Hz"),
+                ("Period", m.period, "s"),
+                ("Duty cycle", m.duty_cycle.map(|duty| 100.0 * duty), "%"),
+                ("Rise time", m.rise_time, "s"),
+                ("Fall time", m.fall_time, "s"),
+            ];
+            egui::Grid::new(view.channel as u8).show(ui, |ui| {
+                for (name, value, unit) in rows.iter() {
+                    ui.label(*name);
+                    ui.label(match value {
+                        Some(value) => format!("{:.4e} {}", value, unit),
+                        None => "-".into(),
+                    });
+                    ui.end_row();
+                }
+            });
+            ui.separator();
+        }
+    }
+}
+
+#[derive(Default)]
+struct Viewer {
+    capture: Option<Capture>,
+    error: Option<String>,
+}
+
+impl Viewer {
+    fn open(&mut self, name: String, input: core::result::Result<Vec<u8>, String>) {
+        let capture =
+            input.and_then(|input| Capture::load(name, &input).map_err(|error| error.to_string()));
+
+        match capture {
+            Ok(capture) => {
+                self.capture = Some(capture);
+                self.error = None;
+            }
+            Err(error) => self.error = Some(error),
+        }
+    }
+}
+
+impl eframe::App for Viewer {
+    fn ui(&mut self, ui: &mut egui::Ui, _frame: &mut eframe::Frame) {
+        let dropped = ui
+            .ctx()
+            .input(|input| input.raw.dropped_files.first().cloned());
+        if let Some(file) = dropped {
+            self.open(file.path().display().to_string(), file.bytes());
+        }
+
+        egui::Panel::left("info").show(ui, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                if let Some(error) = &self.error {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+                match &mut self.capture {
+                    Some(capture) => capture.sidebar(ui),
+                    None => {
+                        ui.label("Drop .wfm file here");
+                    }
+                }
+            });
+        });
+
+        egui::CentralPanel::default().show(ui, |ui| {
+            if let Some(capture) = &mut self.capture {
+                plot::show(ui, &mut capture.view, &capture.channels);
+            }
+        });
+    }
+}
+
+fn main() -> eframe::Result {
+    let mut viewer = Viewer::default();
+    if let Some(path) = std::env::args_os().nth(1) {
+        let input = std::fs::read(&path).map_err(|error| error.to_string());
+        viewer.open(path.to_string_lossy().into(), input);
+    }
+
+    eframe::run_native(
+        "Rigol WFM viewer",
+        eframe::NativeOptions::default(),
+        Box::new(|_| Ok(Box::new(viewer))),
+    )
+}
@@ -0,0 +1,129 @@
+/*!
+
+Zoomable trace plot
+
+Visible part of each trace is decimated to one min/max bucket per pixel
+column, so drawing cost does not depend on record length.
+
+*/
+use super::ChannelView;
+use eframe::egui::{self, Color32, Pos2, Rect, Sense, Stroke};
+use rigol_wfm::{analysis::envelope::Envelope, Viewport};
+
+/// Number of horizontal divisions
+const COLUMNS: usize = 12;
+
+/// Number of vertical divisions
+const ROWS: usize = 8;
+
+/// Horizontal screen position of time
+fn x(view: &Viewport, rect: Rect, time: f32) -> f32 {
+    rect.left() + (time - view.start) / view.width() * rect.width()
+}
+
+/// Draw traces with screen-like grid and handle zoom and pan
+pub fn show(ui: &mut egui::Ui, view: &mut Viewport, channels: &[ChannelView]) {
+    let (response, painter) = ui.allocate_painter(ui.available_size(), Sense::click_and_drag());
+    let rect = response.rect;
+
+    if response.dragged() {
+        view.pan(-response.drag_delta().x / rect.width() * view.width());
+    }
+    if let Some(position) = response.hover_pos() {
+        let scroll = ui.ctx().input(|input| input.smooth_scroll_delta().y);
+        if scroll != 0.0 {
+            let time = view.start + (position.x - rect.left()) / rect.width() * view.width();
+            view.zoom(time, (scroll * 0.005).exp());
+        }
+    }
+    if response.double_clicked() {
+        view.reset();
+    }
+
+    painter.rect_filled(rect, 0.0, Color32::BLACK);
+    let grid = Stroke::new(1.0, Color32::from_gray(48));
+    for column in 1..COLUMNS {
+        let x = rect.left() + column as f32 * rect.width() / COLUMNS as f32;
+        painter.line_segment(
+            [Pos2::new(x, rect.top()), Pos2::new(x, rect.bottom())],
+            grid,
+        );
+    }
+    for row in 1..ROWS {
+        let y = rect.top() + row as f32 * rect.height() / ROWS as f32;
+        painter.line_segment(
+            [Pos2::new(rect.left(), y), Pos2::new(rect.right(), y)],
+            grid,
+        );
+    }
+    if view.start <= 0.0 && view.end >= 0.0 {
+        let trigger = Stroke::new(1.0, Color32::from_rgb(0xff, 0x80, 0x00));
+        painter.line_segment(
+            [
+                Pos2::new(x(view, rect, 0.0), rect.top()),
+                Pos2::new(x(view, rect, 0.0), rect.bottom()),
+            ],
+            trigger,
+        );
+    }
+
+    for channel in channels.iter().filter(|channel| channel.visible) {
+        draw(&painter, rect, view, channel);
+    }
+
+    painter.text(
+        rect.left_bottom() + egui::vec2(4.0, -4.0),
+        egui::Align2::LEFT_BOTTOM,
+        format!(
+            "{:.4e} s .. {:.4e} s, {:.3e} s/div",
+            view.start,
+            view.end,
+            view.width() / COLUMNS as f32
+        ),
+        egui::FontId::monospace(12.0),
+        Color32::GRAY,
+    );
+}
+
+fn draw(painter: &egui::Painter, rect: Rect, view: &Viewport, channel: &ChannelView) {
+    let trace = &channel.trace;
+    let step = trace.time_step();
+    let from = ((view.start - trace.time_offset) / step).floor().max(0.0) as usize;
+    let to = ((view.end - trace.time_offset) / step).ceil().max(0.0) as usize + 1;
+    if from >= trace.len() || from >= to {
+        return;
+    }
+
+    // One bucket per pixel column unless samples are fewer
+    let buckets = (rect.width() as usize).clamp(1, to.min(trace.len()) - from);
+    let sparse = buckets < rect.width() as usize;
+    let envelope = Envelope::of_range(trace, from..to, buckets);
+
+    let y = |value: f32| {
+        rect.center().y - (value - channel.center) / channel.scale * rect.height() / ROWS as f32
+    };
+    let stroke = Stroke::new(1.0, channel.color());
+
+    let mut previous: Option<Pos2> = None;
+    for (index, bucket) in envelope.buckets.iter().enumerate() {
+        let column = x(view, rect, envelope.time(index));
+
+        if sparse {
+            // Single samples are connected by lines
+            let point = Pos2::new(column, y(bucket.min));
+            if let Some(previous) = previous {
+                painter.line_segment([previous, point], stroke);
+            }
+            previous = Some(point);
+        } else {
+            // Span of bucket is extended to previous one to keep trace continuous
+            let (mut low, mut high) = (y(bucket.min), y(bucket.max));
+            if let Some(previous) = previous {
+                low = low.max(previous.y);
+                high = high.min(previous.y);
+            }
+            painter.line_segment([Pos2::new(column, low), Pos2::new(column, high)], stroke);
+            previous = Some(Pos2::new(column, (low + high) / 2.0));
+        }
+    }
+}
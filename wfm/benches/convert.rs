@@ -0,0 +1,25 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use rigol_wfm::parse;
+
+fn convert(c: &mut Criterion) {
+    let input = std::fs::read("test/ds1052e_2ch.wfm").unwrap();
+    let data = parse(&input).unwrap();
+    let header = &data.header.ch1;
+    let raw = &data.data.ch1;
+
+    let mut group = c.benchmark_group("convert");
+    group.throughput(Throughput::Elements(raw.len() as u64));
+    group.bench_function("per_sample", |b| {
+        b.iter(|| {
+            black_box(raw)
+                .iter()
+                .map(|&raw| header.volts(raw))
+                .collect::<Vec<_>>()
+        })
+    });
+    group.bench_function("volts_all", |b| b.iter(|| header.volts_all(black_box(raw))));
+    group.finish();
+}
+
+criterion_group!(benches, convert);
+criterion_main!(benches);
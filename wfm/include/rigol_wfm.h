@@ -0,0 +1,81 @@
+/*
+ * Rigol oscilloscopes waveform file format handling library
+ *
+ * Channels are numbered from 0 (CH1) to 1 (CH2).
+ */
+#ifndef RIGOL_WFM_H
+#define RIGOL_WFM_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Parsed waveform handle */
+typedef struct RigolWfm RigolWfm;
+
+/*
+ * Parse waveform file contents
+ *
+ * Returns NULL on failure and stores error message to `error` when it is not
+ * NULL. Message should be released using `rigol_wfm_string_free`.
+ */
+RigolWfm *rigol_wfm_parse(const uint8_t *data, size_t length, char **error);
+
+/* Release waveform */
+void rigol_wfm_free(RigolWfm *wfm);
+
+/* Release error message */
+void rigol_wfm_string_free(char *string);
+
+/* Active channel */
+int rigol_wfm_active_channel(const RigolWfm *wfm);
+
+/* Sample rate in Hz */
+float rigol_wfm_sample_rate(const RigolWfm *wfm);
+
+/* Horizontal scale in seconds per division */
+float rigol_wfm_time_scale(const RigolWfm *wfm);
+
+/* Horizontal offset in seconds */
+float rigol_wfm_time_offset(const RigolWfm *wfm);
+
+/* Channel is enabled */
+bool rigol_wfm_channel_enabled(const RigolWfm *wfm, int channel);
+
+/* Vertical scale of channel in units per ADC step, NaN for invalid channel */
+float rigol_wfm_volt_scale(const RigolWfm *wfm, int channel);
+
+/* Vertical offset of channel in units, NaN for invalid channel */
+float rigol_wfm_volt_offset(const RigolWfm *wfm, int channel);
+
+/*
+ * Raw ADC samples of channel
+ *
+ * Stores number of samples to `length` and returns pointer which is valid
+ * until waveform is released. Returns NULL for invalid channel.
+ */
+const uint8_t *rigol_wfm_raw_samples(const RigolWfm *wfm, int channel, size_t *length);
+
+/*
+ * Copy scaled samples of channel to buffer
+ *
+ * Returns total number of samples, so call with NULL buffer can be used to
+ * get required capacity. At most `capacity` samples are copied.
+ */
+size_t rigol_wfm_samples(const RigolWfm *wfm, int channel, float *buffer, size_t capacity);
+
+/*
+ * Time of first sample of channel in seconds relative to trigger, NaN when
+ * channel is disabled
+ */
+float rigol_wfm_start_time(const RigolWfm *wfm, int channel);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* RIGOL_WFM_H */
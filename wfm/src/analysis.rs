@@ -0,0 +1,35 @@
+/*!
+
+Waveform analysis
+
+*/
+pub mod average;
+pub mod calibrate;
+pub mod clipping;
+pub mod correlate;
+pub mod dc;
+pub mod distortion;
+pub mod edge;
+pub mod envelope;
+pub mod eye;
+pub mod filter;
+pub mod gate;
+pub mod histogram;
+pub mod jitter;
+pub mod mask;
+pub mod math;
+pub mod measure;
+pub mod merge;
+pub mod persistence;
+pub mod pulse;
+pub mod resample;
+pub mod spectrum;
+pub mod xy;
+
+pub use clipping::clipping;
+pub use dc::{detrend, remove_dc};
+pub use edge::edges;
+pub use gate::Gate;
+pub use histogram::histogram;
+pub use mask::Mask;
+pub use resample::resample;
@@ -0,0 +1,154 @@
+/*!
+
+Waveform averaging across captures
+
+*/
+use crate::{Channel, Error, Result, Trace, Unit, WaveformData};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Per-point average accumulator
+///
+/// Captures are aligned on trigger position using the time grid of the first
+/// added capture. Each point is averaged over captures which cover it.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Average {
+    sample_rate_hz: f32,
+    time_offset: f32,
+    unit: Option<Unit>,
+    counts: Vec<u32>,
+    mean: Vec<f64>,
+    /// Sums of squared deviations (Welford's method)
+    deviation: Vec<f64>,
+}
+
+impl Average {
+    /// Empty accumulator
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of accumulated captures
+    pub fn count(&self) -> u32 {
+        self.counts.iter().copied().max().unwrap_or(0)
+    }
+
+    /// Add channel of capture
+    pub fn add(&mut self, data: &WaveformData, channel: Channel) -> Result<()> {
+        let trace = data.trace(channel).ok_or(Error::ChannelDisabled(channel))?;
+
+        self.add_trace(&trace)
+    }
+
+    /// Add trace
+    pub fn add_trace(&mut self, trace: &Trace) -> Result<()> {
+        match self.unit {
+            None => {
+                self.sample_rate_hz = trace.sample_rate_hz;
+                self.time_offset = trace.time_offset;
+                self.unit = Some(trace.unit);
+                self.counts = vec![0; trace.len()];
+                self.mean = vec![0.0; trace.len()];
+                self.deviation = vec![0.0; trace.len()];
+            }
+            Some(unit) => {
+                if trace.sample_rate_hz != self.sample_rate_hz {
+                    return Err(Error::SampleRateMismatch(
+                        trace.sample_rate_hz,
+                        self.sample_rate_hz,
+                    ));
+                }
+                if trace.unit != unit {
+                    return Err(Error::UnitMismatch(trace.unit, unit));
+                }
+            }
+        }
+
+        // Offset of trace first sample on accumulator grid
+        let shift = ((trace.time_offset - self.time_offset) as f64 * self.sample_rate_hz as f64)
+            .round() as isize;
+
+        for (index, &value) in trace.samples.iter().enumerate() {
+            let target = index as isize + shift;
+            if target < 0 || target as usize >= self.mean.len() {
+                continue;
+            }
+            let target = target as usize;
+            let value = value as f64;
+
+            self.counts[target] += 1;
+            let delta = value - self.mean[target];
+            self.mean[target] += delta / self.counts[target] as f64;
+            self.deviation[target] += delta * (value - self.mean[target]);
+        }
+
+        Ok(())
+    }
+
+    fn trace(&self, samples: Vec<f32>) -> Option<Trace> {
+        Some(Trace {
+            samples,
+            sample_rate_hz: self.sample_rate_hz,
+            time_offset: self.time_offset,
+            unit: self.unit?,
+        })
+    }
+
+    /// Averaged waveform
+    pub fn mean(&self) -> Option<Trace> {
+        self.trace(self.mean.iter().map(|&mean| mean as f32).collect())
+    }
+
+    /// Per-point sample standard deviation
+    pub fn stddev(&self) -> Option<Trace> {
+        self.trace(
+            self.deviation
+                .iter()
+                .zip(&self.counts)
+                .map(|(&deviation, &count)| {
+                    if count > 1 {
+                        (deviation / (count - 1) as f64).sqrt() as f32
+                    } else {
+                        0.0
+                    }
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn aligned() {
+        let trace = |samples: Vec<f32>, time_offset| Trace {
+            samples,
+            sample_rate_hz: 10.0,
+            time_offset,
+            unit: Unit::V,
+        };
+        let mut average = Average::new();
+
+        average
+            .add_trace(&trace(vec![1.0, 2.0, 3.0, 4.0], 0.0))
+            .unwrap();
+        // Shifted by one sample
+        average
+            .add_trace(&trace(vec![4.0, 5.0, 6.0, 7.0], 0.1))
+            .unwrap();
+
+        assert_eq!(average.count(), 2);
+        assert_eq!(average.mean().unwrap().samples, vec![1.0, 3.0, 4.0, 5.0]);
+        let stddev = average.stddev().unwrap().samples;
+        assert_eq!(stddev[0], 0.0);
+        assert!((stddev[1] - core::f32::consts::SQRT_2).abs() < 1e-6);
+
+        let mut other = trace(vec![0.0], 0.0);
+        other.sample_rate_hz = 20.0;
+        assert!(average.add_trace(&other).is_err());
+    }
+}
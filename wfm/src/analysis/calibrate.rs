@@ -0,0 +1,148 @@
+/*!
+
+Correction of known measurement errors
+
+User-supplied corrections are applied to scaled samples before export or
+measurement, so known fixture and probe errors can be removed in software.
+
+*/
+use super::filter::{Biquad, Fir, Iir};
+use crate::{Channel, Trace, WaveformData};
+use core::f64::consts::PI;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Single correction step
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Correction {
+    /// Gain and offset trim, applied as `value * gain + offset`
+    Trim { gain: f32, offset: f32 },
+    /// Compensation of probe with first-order response
+    ///
+    /// Probe transfer function is `(1 + s/ωz) / (1 + s/ωp)` with zero and
+    /// pole frequencies in Hz, correction applies its inverse. Equal
+    /// frequencies mean properly compensated probe.
+    Probe { zero_hz: f32, pole_hz: f32 },
+    /// De-embedding filter
+    Fir(Fir),
+}
+
+impl Correction {
+    /// Recursive filter which inverts probe response
+    fn probe_filter(sample_rate_hz: f32, zero_hz: f32, pole_hz: f32) -> Iir {
+        // Bilinear transform of (1 + s/ωp) / (1 + s/ωz)
+        let k = 2.0 * sample_rate_hz as f64;
+        let numerator = k / (2.0 * PI * pole_hz as f64);
+        let denominator = k / (2.0 * PI * zero_hz as f64);
+        let a0 = 1.0 + denominator;
+
+        Iir {
+            sections: vec![Biquad {
+                b0: (1.0 + numerator) / a0,
+                b1: (1.0 - numerator) / a0,
+                b2: 0.0,
+                a1: (1.0 - denominator) / a0,
+                a2: 0.0,
+            }],
+            parallel: Vec::new(),
+        }
+    }
+
+    /// Correct trace
+    pub fn apply(&self, trace: &Trace) -> Trace {
+        match self {
+            Correction::Trim { gain, offset } => Trace {
+                samples: trace
+                    .samples
+                    .iter()
+                    .map(|value| value * gain + offset)
+                    .collect(),
+                ..trace.clone()
+            },
+            Correction::Probe { zero_hz, pole_hz } => {
+                Self::probe_filter(trace.sample_rate_hz, *zero_hz, *pole_hz).apply(trace)
+            }
+            Correction::Fir(fir) => fir.apply(trace),
+        }
+    }
+}
+
+/// Corrections of channels applied in order
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Calibration {
+    pub ch1: Vec<Correction>,
+    pub ch2: Vec<Correction>,
+}
+
+impl Calibration {
+    /// Corrections of channel
+    pub fn corrections(&self, channel: Channel) -> &[Correction] {
+        match channel {
+            Channel::Ch1 => &self.ch1,
+            Channel::Ch2 => &self.ch2,
+        }
+    }
+
+    /// Correct trace of channel
+    pub fn apply(&self, channel: Channel, trace: &Trace) -> Trace {
+        self.corrections(channel)
+            .iter()
+            .fold(trace.clone(), |trace, correction| correction.apply(&trace))
+    }
+
+    /// Corrected scaled samples of channel
+    ///
+    /// Returns `None` when channel is disabled or has no samples.
+    pub fn trace(&self, data: &WaveformData, channel: Channel) -> Option<Trace> {
+        Some(self.apply(channel, &data.trace(channel)?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Unit;
+
+    #[test]
+    fn corrections() {
+        let step = Trace {
+            samples: (0..1000)
+                .map(|index| if index < 500 { 0.0 } else { 1.0 })
+                .collect(),
+            sample_rate_hz: 1.0e6,
+            time_offset: 0.0,
+            unit: Unit::V,
+        };
+
+        // Overcompensated probe makes overshoot
+        let probe = Correction::probe_filter(1.0e6, 2.0e3, 1.0e3).apply(&step);
+        assert!(probe.samples[501] > 1.5);
+
+        let calibration = Calibration {
+            ch1: vec![
+                Correction::Probe {
+                    zero_hz: 1.0e3,
+                    pole_hz: 2.0e3,
+                },
+                Correction::Trim {
+                    gain: 2.0,
+                    offset: -0.5,
+                },
+            ],
+            ch2: Vec::new(),
+        };
+        let corrected = calibration.apply(Channel::Ch1, &probe);
+        assert!(corrected
+            .samples
+            .iter()
+            .zip(&step.samples)
+            .all(|(corrected, step)| (corrected - (step * 2.0 - 0.5)).abs() < 1.0e-3));
+        assert_eq!(
+            calibration.apply(Channel::Ch2, &probe).samples,
+            probe.samples
+        );
+    }
+}
@@ -0,0 +1,192 @@
+/*!
+
+ADC clipping detection
+
+Signals beyond input range saturate ADC, so their samples stick to rail
+codes and measurements of such captures are wrong. Clipping is detected on
+raw samples, since scaled values do not tell which codes are rails.
+
+*/
+use crate::{Channel, WaveformData};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Consecutive samples at ADC rail
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Run {
+    /// Index of first clipped sample
+    pub start: usize,
+    /// Number of clipped samples
+    pub len: usize,
+    /// Samples are at upper rail code
+    ///
+    /// Upper code corresponds to lowest level unless channel is inverted.
+    pub upper: bool,
+}
+
+/// Clipping of channel
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Clipping {
+    pub channel: Channel,
+    /// Number of checked samples
+    pub samples: usize,
+    pub runs: Vec<Run>,
+}
+
+impl Clipping {
+    /// Number of clipped samples
+    pub fn clipped(&self) -> usize {
+        self.runs.iter().map(|run| run.len).sum()
+    }
+
+    /// Part of clipped samples in percents
+    pub fn percent(&self) -> f32 {
+        if self.samples == 0 {
+            0.0
+        } else {
+            100.0 * self.clipped() as f32 / self.samples as f32
+        }
+    }
+
+    /// Channel has clipped samples
+    pub fn is_clipped(&self) -> bool {
+        !self.runs.is_empty()
+    }
+}
+
+/// Find runs of raw samples at rail codes
+pub fn runs(raw: &[u8], (lower, upper): (u8, u8)) -> Vec<Run> {
+    let mut runs: Vec<Run> = Vec::new();
+
+    for (index, &sample) in raw.iter().enumerate() {
+        let at_upper = if sample >= upper {
+            true
+        } else if sample <= lower {
+            false
+        } else {
+            continue;
+        };
+
+        match runs.last_mut() {
+            Some(run) if run.upper == at_upper && run.start + run.len == index => run.len += 1,
+            _ => runs.push(Run {
+                start: index,
+                len: 1,
+                upper: at_upper,
+            }),
+        }
+    }
+    runs
+}
+
+/// Detect clipping of enabled channels
+///
+/// Rails are taken from model, invalid samples are not checked.
+pub fn clipping(data: &WaveformData) -> Vec<Clipping> {
+    let rails = data.header.model.adc_rails();
+
+    [Channel::Ch1, Channel::Ch2]
+        .iter()
+        .filter_map(|&channel| {
+            let enabled = match channel {
+                Channel::Ch1 => data.header.ch1.enabled,
+                Channel::Ch2 => data.header.ch2.enabled,
+            };
+            let raw = data.data.valid(channel);
+            if !enabled || raw.is_empty() {
+                return None;
+            }
+
+            Some(Clipping {
+                channel,
+                samples: raw.len(),
+                runs: runs(raw, rails),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{parse, RawData, WaveformHeader};
+    use std::fs::read;
+
+    #[test]
+    fn rails() {
+        let data = WaveformHeader::builder()
+            .channel(Channel::Ch1, 1.0, 0.0)
+            .build_with(RawData {
+                ch1: vec![0, 0, 10, 255, 255, 255, 0, 125].into(),
+                ..RawData::default()
+            });
+        let clipping = clipping(&data);
+
+        assert_eq!(clipping.len(), 1);
+        assert_eq!(
+            clipping[0].runs,
+            [
+                Run {
+                    start: 0,
+                    len: 2,
+                    upper: false
+                },
+                Run {
+                    start: 3,
+                    len: 3,
+                    upper: true
+                },
+                Run {
+                    start: 6,
+                    len: 1,
+                    upper: false
+                },
+            ]
+        );
+        assert_eq!(clipping[0].percent(), 75.0);
+    }
+
+    #[test]
+    fn ds1052e_2ch() {
+        let i = read("test/ds1052e_2ch.wfm").unwrap();
+        let mut r = parse(&i).unwrap();
+
+        let channels = clipping(&r);
+        assert_eq!(channels.len(), 2);
+        for channel in &channels {
+            assert_eq!(channel.samples, 524284);
+            assert_eq!(channel.clipped(), 0);
+            assert!(!channel.is_clipped());
+        }
+
+        // Saturate part of second channel to both rails
+        r.data.ch2[1000..1100]
+            .iter_mut()
+            .for_each(|sample| *sample = 255);
+        r.data.ch2[2000..2050]
+            .iter_mut()
+            .for_each(|sample| *sample = 0);
+
+        let channels = clipping(&r);
+        assert_eq!(channels[0].clipped(), 0);
+        assert_eq!(
+            channels[1].runs,
+            [
+                Run {
+                    start: 1000,
+                    len: 100,
+                    upper: true
+                },
+                Run {
+                    start: 2000,
+                    len: 50,
+                    upper: false
+                },
+            ]
+        );
+        assert_eq!(channels[1].clipped(), 150);
+    }
+}
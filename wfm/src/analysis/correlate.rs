@@ -0,0 +1,167 @@
+/*!
+
+Cross-correlation delay measurement
+
+*/
+use super::{
+    filter::{Band, Iir},
+    resample::{resample_like, Interpolation},
+    spectrum::{fft, ifft, Complex},
+};
+use crate::{Channel, Trace, WaveformData};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Order of band limiting filter
+const FILTER_ORDER: usize = 4;
+
+/// Delay between traces
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Delay {
+    /// Lag of second trace in fractional samples of first trace
+    ///
+    /// Positive lag means second trace is delayed.
+    pub lag_samples: f64,
+    /// Lag of second trace in seconds including traces time offsets difference
+    pub lag_seconds: f64,
+    /// Normalized correlation at peak (-1..1)
+    pub correlation: f32,
+}
+
+/// Measure delay of second trace relative to first one
+///
+/// Second trace is resampled on time grid of first trace when sample rates
+/// differ. When band is given both traces are filtered before correlation.
+/// Returns `None` when traces are empty or constant.
+pub fn delay(first: &Trace, second: &Trace, band: Option<Band>) -> Option<Delay> {
+    if first.is_empty() || second.is_empty() {
+        return None;
+    }
+
+    let second = if first.sample_rate_hz != second.sample_rate_hz {
+        let mut grid = first.clone();
+        grid.time_offset = second.time_offset;
+        grid.samples
+            .truncate((second.duration() as f64 * first.sample_rate_hz as f64) as usize);
+        resample_like(second, &grid, Interpolation::default())
+    } else {
+        second.clone()
+    };
+
+    let (first, second) = match band {
+        Some(band) => {
+            let iir = Iir::butterworth(band, FILTER_ORDER, first.sample_rate_hz);
+            (iir.apply(first), iir.apply(&second))
+        }
+        None => (first.clone(), second),
+    };
+
+    let centered = |trace: &Trace| {
+        let mean = trace.samples.iter().map(|&v| v as f64).sum::<f64>() / trace.len() as f64;
+        trace
+            .samples
+            .iter()
+            .map(|&value| value as f64 - mean)
+            .collect::<Vec<_>>()
+    };
+    let a = centered(&first);
+    let b = centered(&second);
+
+    let energy = |values: &[f64]| values.iter().map(|value| value * value).sum::<f64>();
+    let norm = (energy(&a) * energy(&b)).sqrt();
+    if norm <= 0.0 {
+        return None;
+    }
+
+    // Zero padding avoids circular wrap of correlation
+    let length = (a.len() + b.len()).next_power_of_two();
+    let spectrum = |values: &[f64]| {
+        let mut data = vec![Complex(0.0, 0.0); length];
+        for (target, &value) in data.iter_mut().zip(values) {
+            target.0 = value;
+        }
+        fft(&mut data);
+        data
+    };
+
+    let mut data = spectrum(&a)
+        .iter()
+        .zip(spectrum(&b).iter())
+        .map(|(a, b)| a.conj().mul(b))
+        .collect::<Vec<_>>();
+    ifft(&mut data);
+
+    // Valid lags are -(a.len() - 1)..b.len()
+    let lag = |index: usize| {
+        if index < b.len() {
+            index as isize
+        } else {
+            index as isize - length as isize
+        }
+    };
+    let value = |index: usize| data[index].0 / length as f64;
+
+    let peak = (0..length)
+        .filter(|&index| index < b.len() || index + a.len() > length)
+        .max_by(|&x, &y| value(x).total_cmp(&value(y)))?;
+
+    // Parabolic interpolation around peak
+    let before = value((peak + length - 1) % length);
+    let center = value(peak);
+    let after = value((peak + 1) % length);
+    let curvature = before - 2.0 * center + after;
+    let fraction = if curvature < 0.0 {
+        0.5 * (before - after) / curvature
+    } else {
+        0.0
+    };
+
+    let lag_samples = lag(peak) as f64 + fraction;
+    let lag_seconds = lag_samples / first.sample_rate_hz as f64
+        + (second.time_offset as f64 - first.time_offset as f64);
+
+    Some(Delay {
+        lag_samples,
+        lag_seconds,
+        correlation: (center / norm) as f32,
+    })
+}
+
+/// Measure delay of second channel relative to first one
+pub fn channel_delay(data: &WaveformData, band: Option<Band>) -> Option<Delay> {
+    delay(&data.trace(Channel::Ch1)?, &data.trace(Channel::Ch2)?, band)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Unit;
+
+    fn pulse(shift: f64) -> Trace {
+        Trace {
+            samples: (0..1000)
+                .map(|index| {
+                    let x = (index as f64 - 300.0 - shift) / 20.0;
+                    (-x * x).exp() as f32
+                })
+                .collect(),
+            sample_rate_hz: 1.0e6,
+            time_offset: 0.0,
+            unit: Unit::V,
+        }
+    }
+
+    #[test]
+    fn subsample_lag() {
+        let d = delay(&pulse(0.0), &pulse(12.3), None).unwrap();
+
+        assert!((d.lag_samples - 12.3).abs() < 0.05);
+        assert!((d.lag_seconds - 12.3e-6).abs() < 0.05e-6);
+        assert!(d.correlation > 0.99);
+
+        let d = delay(&pulse(7.0), &pulse(0.0), Some(Band::LowPass(50.0e3))).unwrap();
+        assert!((d.lag_samples + 7.0).abs() < 0.05);
+    }
+}
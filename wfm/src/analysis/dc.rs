@@ -0,0 +1,125 @@
+/*!
+
+DC offset removal and detrending
+
+*/
+use crate::{ChannelHeader, Trace};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// DC level to remove
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Dc {
+    /// Average of samples
+    Mean,
+    /// Fixed level in units
+    Level(f32),
+}
+
+impl Dc {
+    /// Level of screen center according to channel vertical shift
+    pub fn shift(header: &ChannelHeader) -> Self {
+        Dc::Level(header.center_volts())
+    }
+
+    /// Actual level for trace
+    pub fn level(&self, trace: &Trace) -> f32 {
+        match *self {
+            Dc::Mean => {
+                let sum: f64 = trace.samples.iter().map(|&value| value as f64).sum();
+                (sum / trace.len().max(1) as f64) as f32
+            }
+            Dc::Level(level) => level,
+        }
+    }
+}
+
+/// Subtract DC level from trace
+pub fn remove_dc(trace: &Trace, dc: Dc) -> Trace {
+    let level = dc.level(trace);
+
+    Trace {
+        samples: trace.samples.iter().map(|&value| value - level).collect(),
+        ..trace.clone()
+    }
+}
+
+/// Least squares line fit of trace as offset at first sample and slope per sample
+pub fn trend(trace: &Trace) -> (f32, f32) {
+    let count = trace.len() as f64;
+    if count < 2.0 {
+        return (Dc::Mean.level(trace), 0.0);
+    }
+
+    // Sample indexes are centered to keep sums well conditioned
+    let center = (count - 1.0) / 2.0;
+    let (sum, sum_xy, sum_xx) = trace.samples.iter().enumerate().fold(
+        (0.0, 0.0, 0.0),
+        |(sum, sum_xy, sum_xx), (index, &value)| {
+            let x = index as f64 - center;
+            let y = value as f64;
+            (sum + y, sum_xy + x * y, sum_xx + x * x)
+        },
+    );
+
+    let slope = sum_xy / sum_xx;
+    let offset = sum / count - slope * center;
+
+    (offset as f32, slope as f32)
+}
+
+/// Subtract least squares line from trace
+pub fn detrend(trace: &Trace) -> Trace {
+    let (offset, slope) = trend(trace);
+
+    Trace {
+        samples: trace
+            .samples
+            .iter()
+            .enumerate()
+            .map(|(index, &value)| value - (offset + slope * index as f32))
+            .collect(),
+        ..trace.clone()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{parse, Channel, Unit};
+    use std::fs::read;
+
+    #[test]
+    fn ramp() {
+        let trace = Trace {
+            samples: (0..100)
+                .map(|index| 1.0 + 0.5 * index as f32 + if index % 2 == 0 { 0.1 } else { -0.1 })
+                .collect(),
+            sample_rate_hz: 1.0,
+            time_offset: 0.0,
+            unit: Unit::V,
+        };
+        let (offset, slope) = trend(&trace);
+
+        assert!((offset - 1.0).abs() < 1e-2);
+        assert!((slope - 0.5).abs() < 1e-3);
+        assert!(detrend(&trace)
+            .samples
+            .iter()
+            .all(|value| (value.abs() - 0.1).abs() < 1e-2));
+        assert!(Dc::Mean.level(&remove_dc(&trace, Dc::Mean)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn ds1052e_2ch() {
+        let i = read("test/ds1052e_2ch.wfm").unwrap();
+        let r = parse(&i).unwrap();
+        let t = r.trace(Channel::Ch1).unwrap();
+        let shifted = remove_dc(&t, Dc::shift(&r.header.ch1));
+
+        // Channel 1 is shifted down by 51 points of 0.2 V
+        assert!((shifted.samples[0] - (t.samples[0] - 10.2)).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,148 @@
+/*!
+
+Spectral quality metrics
+
+*/
+use super::spectrum::{Spectrum, Window};
+use crate::Trace;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Default number of harmonics taken into account
+pub const HARMONICS: usize = 9;
+
+/// Distortion and noise metrics
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Distortion {
+    /// Frequency of fundamental in Hz
+    pub fundamental_hz: f32,
+    /// Power of fundamental in squared units
+    pub fundamental_power: f32,
+    /// Total power of found harmonics in squared units
+    pub harmonics_power: f32,
+    /// Number of harmonics below Nyquist frequency
+    pub harmonics: usize,
+    /// Power of remaining components except DC in squared units
+    pub noise_power: f32,
+    /// Median power of bins in squared units
+    pub noise_floor: f32,
+}
+
+impl Distortion {
+    /// Analyze trace using Blackman-Harris window
+    ///
+    /// Returns `None` when no fundamental can be found.
+    pub fn of(trace: &Trace, harmonics: usize) -> Option<Self> {
+        Self::of_spectrum(&Spectrum::of(trace, Window::BlackmanHarris)?, harmonics)
+    }
+
+    /// Analyze precomputed spectrum
+    pub fn of_spectrum(spectrum: &Spectrum, harmonics: usize) -> Option<Self> {
+        let lobe = spectrum.window.lobe();
+        let last = spectrum.len().checked_sub(1)?;
+
+        // Skip DC lobe and pick the strongest bin
+        let fundamental = (lobe + 1..spectrum.len())
+            .max_by(|&a, &b| spectrum.power[a].total_cmp(&spectrum.power[b]))?;
+
+        let band = |bin: usize| bin.saturating_sub(lobe)..(bin + lobe + 1).min(spectrum.len());
+
+        let dc_power = spectrum.band_power(0..lobe + 1);
+        let fundamental_power = spectrum.band_power(band(fundamental));
+
+        if fundamental_power <= 0.0 {
+            return None;
+        }
+
+        let mut harmonics_power = 0.0;
+        let mut found = 0;
+
+        for order in 2..harmonics + 2 {
+            let center = fundamental * order;
+            if center + lobe > last {
+                break;
+            }
+            // Harmonic peak may drift from exact multiple of estimated fundamental
+            let peak = band(center)
+                .max_by(|&a, &b| spectrum.power[a].total_cmp(&spectrum.power[b]))
+                .unwrap_or(center);
+            harmonics_power += spectrum.band_power(band(peak));
+            found += 1;
+        }
+
+        let total_power = spectrum.band_power(0..spectrum.len());
+        let noise_power = (total_power - dc_power - fundamental_power - harmonics_power).max(0.0);
+
+        let mut sorted = spectrum.power.clone();
+        sorted.sort_unstable_by(|a, b| a.total_cmp(b));
+        let noise_floor = sorted[sorted.len() / 2];
+
+        Some(Self {
+            fundamental_hz: spectrum.frequency(fundamental),
+            fundamental_power: fundamental_power as f32,
+            harmonics_power: harmonics_power as f32,
+            harmonics: found,
+            noise_power: noise_power as f32,
+            noise_floor,
+        })
+    }
+
+    /// Total harmonic distortion as amplitude ratio
+    pub fn thd(&self) -> f32 {
+        (self.harmonics_power / self.fundamental_power).sqrt()
+    }
+
+    /// Total harmonic distortion in dB
+    pub fn thd_db(&self) -> f32 {
+        db(self.harmonics_power / self.fundamental_power)
+    }
+
+    /// Signal to noise ratio in dB
+    pub fn snr_db(&self) -> f32 {
+        db(self.fundamental_power / self.noise_power)
+    }
+
+    /// Signal to noise and distortion ratio in dB
+    pub fn sinad_db(&self) -> f32 {
+        db(self.fundamental_power / (self.noise_power + self.harmonics_power))
+    }
+
+    /// Effective number of bits
+    pub fn enob(&self) -> f32 {
+        (self.sinad_db() - 1.76) / 6.02
+    }
+}
+
+fn db(ratio: f32) -> f32 {
+    10.0 * ratio.log10()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Unit;
+    use core::f32::consts::PI;
+
+    #[test]
+    fn second_harmonic() {
+        let phase = |index: usize| 2.0 * PI * 100.0 * index as f32 / 8192.0;
+        let trace = Trace {
+            samples: (0..8192)
+                .map(|index| phase(index).sin() + 0.01 * (2.0 * phase(index)).sin())
+                .collect(),
+            sample_rate_hz: 8192.0,
+            time_offset: 0.0,
+            unit: Unit::V,
+        };
+        let d = Distortion::of(&trace, HARMONICS).unwrap();
+
+        assert_eq!(d.fundamental_hz, 100.0);
+        assert_eq!(d.harmonics, HARMONICS);
+        assert!((d.thd() - 0.01).abs() < 1e-4);
+        assert!((d.thd_db() + 40.0).abs() < 0.1);
+        assert!(d.snr_db() > 80.0);
+        assert!((d.sinad_db() - 40.0).abs() < 0.1);
+    }
+}
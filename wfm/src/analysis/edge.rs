@@ -0,0 +1,268 @@
+/*!
+
+Edge detection
+
+Signal crossings are detected using hysteresis band around threshold, so
+noise near threshold doesn't produce spurious edges. Edge positions are
+linearly interpolated between samples.
+
+*/
+use crate::Trace;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Edge slope
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Slope {
+    Rising,
+    Falling,
+    Both,
+}
+
+impl Slope {
+    /// Check that edge matches slope
+    pub fn matches(&self, rising: bool) -> bool {
+        match self {
+            Slope::Rising => rising,
+            Slope::Falling => !rising,
+            Slope::Both => true,
+        }
+    }
+}
+
+/// Detection levels
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Levels {
+    /// Lower edge of hysteresis band
+    pub low: f32,
+    /// Crossing level
+    pub threshold: f32,
+    /// Upper edge of hysteresis band
+    pub high: f32,
+}
+
+impl Levels {
+    /// Levels with hysteresis band centered on threshold
+    pub fn new(threshold: f32, hysteresis: f32) -> Self {
+        let half = 0.5 * hysteresis.abs();
+
+        Self {
+            low: threshold - half,
+            threshold,
+            high: threshold + half,
+        }
+    }
+}
+
+/// Single edge
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Edge {
+    pub rising: bool,
+    /// Fractional sample position where signal leaves hysteresis band
+    pub start: f64,
+    /// Fractional sample position where signal crosses threshold
+    pub position: f64,
+    /// Fractional sample position where signal enters opposite side of band
+    pub end: f64,
+    /// Time of threshold crossing in seconds relative to trigger
+    pub time: f64,
+}
+
+impl Edge {
+    /// Transition through hysteresis band in samples
+    pub fn transition(&self) -> f64 {
+        self.end - self.start
+    }
+}
+
+/// Find edges in trace
+///
+/// Hysteresis is the full width of band centered on threshold.
+pub fn edges(trace: &Trace, threshold: f32, hysteresis: f32, slope: Slope) -> Vec<Edge> {
+    find(trace, Levels::new(threshold, hysteresis))
+        .into_iter()
+        .filter(|edge| slope.matches(edge.rising))
+        .collect()
+}
+
+/// Find alternating rising and falling edges in trace
+pub fn find(trace: &Trace, levels: Levels) -> Vec<Edge> {
+    let time_offset = trace.time_offset as f64;
+    let time_step = trace.time_step() as f64;
+
+    let mut edges = find_samples(&trace.samples, levels);
+    for edge in &mut edges {
+        edge.time = time_offset + edge.position * time_step;
+    }
+    edges
+}
+
+/// Find alternating rising and falling edges in samples
+///
+/// Edge times are set to sample positions.
+pub fn find_samples(samples: &[f32], levels: Levels) -> Vec<Edge> {
+    let Levels {
+        low,
+        threshold,
+        high,
+    } = levels;
+
+    let mut edges = Vec::new();
+    // Current logic state, unknown until signal leaves band first time
+    let mut state = None;
+    // Last index where signal was beyond the band
+    let mut start = 0;
+    // Last index where signal was before threshold
+    let mut middle = 0;
+
+    let mut push = |rising, start, middle, end| {
+        let position = cross(samples, middle, threshold);
+        edges.push(Edge {
+            rising,
+            start: cross(samples, start, if rising { low } else { high }),
+            position,
+            end: cross(samples, end, if rising { high } else { low }),
+            time: position,
+        });
+    };
+
+    for (index, &value) in samples.iter().enumerate() {
+        match state {
+            // Without hysteresis sample at threshold is both beyond band and
+            // crossing, so crossing is checked first
+            Some(false) => {
+                if value >= high {
+                    push(true, start, middle, index - 1);
+                    state = Some(true);
+                    start = index;
+                    middle = index;
+                } else {
+                    if value <= low {
+                        start = index;
+                    }
+                    if value < threshold {
+                        middle = index;
+                    }
+                }
+            }
+            Some(true) => {
+                if value <= low {
+                    push(false, start, middle, index - 1);
+                    state = Some(false);
+                    start = index;
+                    middle = index;
+                } else {
+                    if value >= high {
+                        start = index;
+                    }
+                    if value > threshold {
+                        middle = index;
+                    }
+                }
+            }
+            None => {
+                if value <= low {
+                    state = Some(false);
+                    start = index;
+                    middle = index;
+                } else if value >= high {
+                    state = Some(true);
+                    start = index;
+                    middle = index;
+                }
+            }
+        }
+    }
+
+    edges
+}
+
+/// Fractional position where signal crosses level between index and next sample
+fn cross(samples: &[f32], index: usize, level: f32) -> f64 {
+    let index = index.min(samples.len().saturating_sub(2));
+    let from = samples[index];
+    let to = samples[index + 1];
+
+    if from == to {
+        index as f64
+    } else {
+        index as f64 + ((level - from) / (to - from)) as f64
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Unit;
+    use core::f32::consts::PI;
+
+    fn trace(samples: Vec<f32>) -> Trace {
+        Trace {
+            samples,
+            sample_rate_hz: 1.0e3,
+            time_offset: -0.5,
+            unit: Unit::V,
+        }
+    }
+
+    #[test]
+    fn interpolation() {
+        let t = trace(vec![0.0, 0.0, 1.0, 1.0, 0.5, 0.0]);
+        let e = edges(&t, 0.25, 0.0, Slope::Both);
+
+        assert_eq!(e.len(), 2);
+        assert!(e[0].rising);
+        assert_eq!(e[0].position, 1.25);
+        assert!((e[0].time - (-0.5 + 1.25e-3)).abs() < 1e-9);
+        assert!(!e[1].rising);
+        assert_eq!(e[1].position, 4.5);
+    }
+
+    #[test]
+    fn slope_filter() {
+        let t = trace(vec![0.0, 1.0, 0.0, 1.0, 0.0, 1.0]);
+
+        assert_eq!(edges(&t, 0.5, 0.2, Slope::Rising).len(), 3);
+        assert_eq!(edges(&t, 0.5, 0.2, Slope::Falling).len(), 2);
+        assert_eq!(edges(&t, 0.5, 0.2, Slope::Both).len(), 5);
+    }
+
+    #[test]
+    fn hysteresis() {
+        // Slow sine with dither which crosses threshold several times per edge
+        let t = trace(
+            (0..1000)
+                .map(|i| {
+                    (2.0 * PI * i as f32 / 500.0).sin() + if i % 2 == 0 { 0.05 } else { -0.05 }
+                })
+                .collect(),
+        );
+
+        assert!(edges(&t, 0.0, 0.0, Slope::Both).len() > 4);
+
+        let e = edges(&t, 0.0, 0.3, Slope::Both);
+        assert_eq!(e.len(), 3);
+        // Crossings near half of period
+        assert!((e[0].position - 250.0).abs() < 5.0);
+        assert!((e[1].position - 500.0).abs() < 5.0);
+        assert!((e[2].position - 750.0).abs() < 5.0);
+        assert!(e[0].start < e[0].position && e[0].position < e[0].end);
+    }
+
+    #[test]
+    fn zero_hysteresis() {
+        // Samples at threshold are beyond band on both sides
+        let e = find_samples(&[0.0, 0.5], Levels::new(0.5, 0.0));
+        assert_eq!(e.len(), 1);
+        assert!(e[0].rising);
+        assert_eq!(e[0].position, 1.0);
+
+        let e = find_samples(&[1.0, 0.5, 0.5, 1.0], Levels::new(0.5, 0.0));
+        assert_eq!(e.len(), 2);
+        assert!(!e[0].rising && e[1].rising);
+    }
+}
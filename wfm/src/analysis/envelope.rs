@@ -0,0 +1,124 @@
+/*!
+
+Min/max envelope decimation
+
+Reduces long records to fixed number of buckets keeping extreme values in
+each, like peak detect acquisition does, so narrow glitches remain visible
+when plotting millions of samples.
+
+*/
+use crate::Trace;
+use core::ops::Range;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Extreme values of bucket
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MinMax {
+    pub min: f32,
+    pub max: f32,
+}
+
+/// Decimated trace
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Envelope {
+    pub buckets: Vec<MinMax>,
+    /// Time of first bucket start relative to trigger in seconds
+    pub time_offset: f32,
+    /// Duration of single bucket in seconds
+    pub bucket_time: f32,
+}
+
+impl Envelope {
+    /// Decimate whole trace
+    pub fn of(trace: &Trace, buckets: usize) -> Self {
+        Self::of_range(trace, 0..trace.len(), buckets)
+    }
+
+    /// Decimate range of samples (as for zoomed view)
+    ///
+    /// Range is clamped to trace length. When there are less samples than
+    /// buckets, samples are repeated.
+    pub fn of_range(trace: &Trace, range: Range<usize>, buckets: usize) -> Self {
+        let end = range.end.min(trace.len());
+        let start = range.start.min(end);
+        let samples = &trace.samples[start..end];
+        let length = samples.len();
+
+        let buckets = if length == 0 {
+            Vec::new()
+        } else {
+            (0..buckets)
+                .map(|bucket| {
+                    let from = bucket * length / buckets;
+                    let to = ((bucket + 1) * length / buckets).max(from + 1);
+
+                    samples[from..to].iter().fold(
+                        MinMax {
+                            min: f32::INFINITY,
+                            max: f32::NEG_INFINITY,
+                        },
+                        |bucket, &value| MinMax {
+                            min: bucket.min.min(value),
+                            max: bucket.max.max(value),
+                        },
+                    )
+                })
+                .collect()
+        };
+
+        Self {
+            bucket_time: length as f32 * trace.time_step() / buckets.len().max(1) as f32,
+            time_offset: trace.time(start),
+            buckets,
+        }
+    }
+
+    /// Number of buckets
+    pub fn len(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// Envelope has no buckets
+    pub fn is_empty(&self) -> bool {
+        self.buckets.is_empty()
+    }
+
+    /// Start time of bucket in seconds relative to trigger
+    pub fn time(&self, bucket: usize) -> f32 {
+        self.time_offset + bucket as f32 * self.bucket_time
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{parse, Channel};
+    use std::fs::read;
+
+    #[test]
+    fn ds1052e_2ch() {
+        let i = read("test/ds1052e_2ch.wfm").unwrap();
+        let r = parse(&i).unwrap();
+        let t = r.trace(Channel::Ch2).unwrap();
+        let e = Envelope::of(&t, 1000);
+
+        assert_eq!(e.len(), 1000);
+        assert_eq!(e.time(0), t.time_offset);
+        assert!((e.bucket_time * 1000.0 - t.duration()).abs() < 1e-9);
+        // Extremes are preserved
+        let max = e.buckets.iter().map(|b| b.max).fold(f32::MIN, f32::max);
+        let min = e.buckets.iter().map(|b| b.min).fold(f32::MAX, f32::min);
+        assert!((max - 5.2).abs() < 1e-3);
+        assert!((min + 0.4).abs() < 1e-3);
+
+        // Zoom in more than sample resolution
+        let e = Envelope::of_range(&t, 10..15, 10);
+        assert_eq!(e.len(), 10);
+        assert_eq!(e.buckets[0], e.buckets[1]);
+        assert_eq!(e.buckets[0].min, t.samples[10]);
+    }
+}
@@ -0,0 +1,240 @@
+/*!
+
+Eye diagram
+
+*/
+use super::{
+    edge::Slope,
+    jitter::{edge_times, periods},
+    xy::Density,
+};
+use crate::{Threshold, Trace};
+use core::f64::consts::TAU;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Part of unit interval around eye center used to measure levels
+const CENTER: f64 = 0.1;
+
+/// Unit interval selection
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum UnitInterval {
+    /// Recover from edges spacing
+    #[default]
+    Recover,
+    /// Given unit interval in seconds
+    Fixed(f64),
+}
+
+/// Recover unit interval from edge times
+///
+/// Shortest edges spacing gives estimate which is refined over all intervals
+/// as each one should span whole number of unit intervals.
+pub fn recover_unit_interval(times: &[f64]) -> Option<f64> {
+    let periods = periods(times);
+    let estimate = periods
+        .iter()
+        .copied()
+        .filter(|&period| period > 0.0)
+        .min_by(f64::total_cmp)?;
+
+    let (time, count) = periods.iter().fold((0.0, 0.0), |(time, count), &period| {
+        (time + period, count + (period / estimate).round())
+    });
+
+    Some(time / count)
+}
+
+/// Eye diagram of data signal
+///
+/// Signal is folded over two unit intervals with crossings placed at 0.5 and
+/// 1.5 UI so eye is opened at center of diagram.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Eye {
+    /// Unit interval in seconds
+    pub unit_interval: f64,
+    /// Time of crossing in seconds relative to trigger
+    pub phase: f64,
+    /// Samples density with x in unit intervals (0..2) and y in units
+    pub density: Density,
+    /// Lowest level of ones at eye center
+    pub one_level: f32,
+    /// Highest level of zeros at eye center
+    pub zero_level: f32,
+    /// Vertical opening in units
+    pub height: f32,
+    /// Horizontal opening in seconds
+    pub width: f64,
+}
+
+impl Eye {
+    /// Build eye diagram with given density size
+    ///
+    /// Returns `None` when there are not enough edges or eye center lacks
+    /// samples of ones or zeros.
+    pub fn of(
+        trace: &Trace,
+        threshold: Threshold,
+        unit_interval: UnitInterval,
+        width: usize,
+        height: usize,
+    ) -> Option<Self> {
+        let level = threshold.levels(trace).threshold;
+        let times = edge_times(trace, threshold, Slope::Both);
+
+        let unit_interval = match unit_interval {
+            UnitInterval::Recover => recover_unit_interval(&times)?,
+            UnitInterval::Fixed(unit_interval) => unit_interval,
+        };
+        if times.is_empty() || unit_interval <= 0.0 {
+            return None;
+        }
+
+        // Mean crossing phase as circular mean of edges
+        let origin = times[0];
+        let (sin, cos) = times.iter().fold((0.0, 0.0), |(sin, cos), &time| {
+            let angle = TAU * (time - origin) / unit_interval;
+            (sin + angle.sin(), cos + angle.cos())
+        });
+        let phase = origin + f64::atan2(sin, cos) / TAU * unit_interval;
+
+        // Offset from crossing in unit intervals
+        let offset = |time: f64| (time - phase) / unit_interval;
+
+        let (crossing_min, crossing_max) =
+            times
+                .iter()
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &time| {
+                    let offset = offset(time);
+                    let offset = offset - offset.round();
+                    (min.min(offset), max.max(offset))
+                });
+
+        let (min, max) = trace
+            .samples
+            .iter()
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), &value| {
+                (min.min(value), max.max(value))
+            });
+        let mut density = Density::new(width, height, 0.0..2.0, min..max);
+
+        let mut one_level = f32::INFINITY;
+        let mut zero_level = f32::NEG_INFINITY;
+
+        let time_offset = trace.time_offset as f64;
+        let time_step = trace.time_step() as f64;
+        let points = trace
+            .samples
+            .iter()
+            .enumerate()
+            .map(|(index, &value)| {
+                let x = (offset(time_offset + index as f64 * time_step) + 0.5).rem_euclid(2.0);
+                if (x - x.round()).abs() <= 0.5 * CENTER {
+                    if value >= level {
+                        one_level = one_level.min(value);
+                    } else {
+                        zero_level = zero_level.max(value);
+                    }
+                }
+                (x as f32, value)
+            })
+            .collect::<Vec<_>>();
+        density.add(&points);
+
+        if !one_level.is_finite() || !zero_level.is_finite() {
+            return None;
+        }
+
+        Some(Self {
+            unit_interval,
+            phase,
+            density,
+            one_level,
+            zero_level,
+            height: (one_level - zero_level).max(0.0),
+            width: ((1.0 - (crossing_max - crossing_min)) * unit_interval).max(0.0),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Unit;
+
+    /// Pseudo random bits with 10 samples per bit and 2 samples transitions
+    fn data() -> Trace {
+        let mut lfsr = 0x5au8;
+        let bits = (0..200)
+            .map(|_| {
+                let bit = lfsr & 1;
+                lfsr = (lfsr >> 1) | (((lfsr ^ (lfsr >> 2) ^ (lfsr >> 3) ^ (lfsr >> 4)) & 1) << 7);
+                bit as f32
+            })
+            .collect::<Vec<_>>();
+
+        Trace {
+            samples: (0..bits.len() * 10)
+                .map(|index| {
+                    let (bit, sample) = (index / 10, index % 10);
+                    match (sample, bit.checked_sub(1).map(|prev| bits[prev])) {
+                        (0, Some(prev)) => prev + (bits[bit] - prev) * 0.5,
+                        _ => bits[bit],
+                    }
+                })
+                .collect(),
+            sample_rate_hz: 1.0e6,
+            time_offset: 0.0,
+            unit: Unit::V,
+        }
+    }
+
+    #[test]
+    fn open_eye() {
+        let t = data();
+        let e = Eye::of(&t, Threshold::default(), UnitInterval::Recover, 20, 10).unwrap();
+
+        assert!((e.unit_interval - 10.0e-6).abs() < 1e-9);
+        assert!((e.width - 10.0e-6).abs() < 1e-8);
+        assert_eq!(e.one_level, 1.0);
+        assert_eq!(e.zero_level, 0.0);
+        assert_eq!(e.height, 1.0);
+        assert_eq!(e.density.counts.iter().sum::<u32>() as usize, t.len());
+        // Eye center has no transitions
+        assert_eq!(e.density.count(10, 5), 0);
+
+        let f = Eye::of(
+            &t,
+            Threshold::default(),
+            UnitInterval::Fixed(10.0e-6),
+            20,
+            10,
+        )
+        .unwrap();
+        assert!((f.phase - e.phase).abs() < 1e-9);
+    }
+
+    #[test]
+    fn no_ones() {
+        // Short pulses do not reach eye center
+        let t = Trace {
+            samples: (0..400)
+                .map(|index| if index % 20 == 10 { 1.0 } else { 0.0 })
+                .collect(),
+            sample_rate_hz: 1.0e6,
+            time_offset: 0.0,
+            unit: Unit::V,
+        };
+        let e = Eye::of(
+            &t,
+            Threshold::default(),
+            UnitInterval::Fixed(20.0e-6),
+            20,
+            10,
+        );
+        assert_eq!(e, None);
+    }
+}
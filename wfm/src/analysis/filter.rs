@@ -0,0 +1,352 @@
+/*!
+
+Digital filters
+
+Both recursive Butterworth filters built of second-order sections and
+windowed-sinc FIR filters are provided.
+
+*/
+use super::spectrum::Window;
+use crate::{Filter, Trace};
+use core::f64::consts::PI;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Filter band
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Band {
+    /// Pass frequencies below cutoff in Hz
+    LowPass(f32),
+    /// Pass frequencies above cutoff in Hz
+    HighPass(f32),
+    /// Pass frequencies between lower and upper cutoffs in Hz
+    BandPass(f32, f32),
+    /// Reject frequencies between lower and upper cutoffs in Hz
+    BandReject(f32, f32),
+}
+
+impl Band {
+    /// Kind of filter
+    pub fn kind(&self) -> Filter {
+        match self {
+            Band::LowPass(_) => Filter::LowPass,
+            Band::HighPass(_) => Filter::HighPass,
+            Band::BandPass(..) => Filter::BandPass,
+            Band::BandReject(..) => Filter::BandReject,
+        }
+    }
+}
+
+/// Second-order section
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Biquad {
+    pub b0: f64,
+    pub b1: f64,
+    pub b2: f64,
+    pub a1: f64,
+    pub a2: f64,
+}
+
+impl Biquad {
+    /// Low-pass section with quality factor
+    ///
+    /// Zero quality makes first-order section.
+    pub fn low_pass(sample_rate_hz: f32, cutoff_hz: f32, quality: f64) -> Self {
+        let omega = 2.0 * PI * cutoff_hz as f64 / sample_rate_hz as f64;
+
+        if quality == 0.0 {
+            let k = (omega / 2.0).tan();
+            return Self {
+                b0: k / (1.0 + k),
+                b1: k / (1.0 + k),
+                b2: 0.0,
+                a1: (k - 1.0) / (k + 1.0),
+                a2: 0.0,
+            };
+        }
+
+        let (sin, cos) = omega.sin_cos();
+        let alpha = sin / (2.0 * quality);
+        let a0 = 1.0 + alpha;
+
+        Self {
+            b0: (1.0 - cos) / 2.0 / a0,
+            b1: (1.0 - cos) / a0,
+            b2: (1.0 - cos) / 2.0 / a0,
+            a1: -2.0 * cos / a0,
+            a2: (1.0 - alpha) / a0,
+        }
+    }
+
+    /// High-pass section with quality factor
+    ///
+    /// Zero quality makes first-order section.
+    pub fn high_pass(sample_rate_hz: f32, cutoff_hz: f32, quality: f64) -> Self {
+        let omega = 2.0 * PI * cutoff_hz as f64 / sample_rate_hz as f64;
+
+        if quality == 0.0 {
+            let k = (omega / 2.0).tan();
+            return Self {
+                b0: 1.0 / (1.0 + k),
+                b1: -1.0 / (1.0 + k),
+                b2: 0.0,
+                a1: (k - 1.0) / (k + 1.0),
+                a2: 0.0,
+            };
+        }
+
+        let (sin, cos) = omega.sin_cos();
+        let alpha = sin / (2.0 * quality);
+        let a0 = 1.0 + alpha;
+
+        Self {
+            b0: (1.0 + cos) / 2.0 / a0,
+            b1: -(1.0 + cos) / a0,
+            b2: (1.0 + cos) / 2.0 / a0,
+            a1: -2.0 * cos / a0,
+            a2: (1.0 - alpha) / a0,
+        }
+    }
+}
+
+/// Recursive filter as cascade of second-order sections
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Iir {
+    /// Sections applied one after another
+    pub sections: Vec<Biquad>,
+    /// Sections applied in parallel to the input and summed (used for band reject)
+    pub parallel: Vec<Biquad>,
+}
+
+impl Iir {
+    /// Design Butterworth filter of given order
+    ///
+    /// Band filters are made of low-pass and high-pass filters of the same order:
+    /// cascaded for band pass and summed for band reject.
+    pub fn butterworth(band: Band, order: usize, sample_rate_hz: f32) -> Self {
+        let sections = |high: bool, cutoff_hz: f32| {
+            let design = if high {
+                Biquad::high_pass
+            } else {
+                Biquad::low_pass
+            };
+            let mut sections = (0..order / 2)
+                .map(|k| {
+                    let angle = PI * (2 * k + 1) as f64 / (2 * order) as f64;
+                    design(sample_rate_hz, cutoff_hz, 1.0 / (2.0 * angle.cos()))
+                })
+                .collect::<Vec<_>>();
+            if order % 2 == 1 {
+                sections.push(design(sample_rate_hz, cutoff_hz, 0.0));
+            }
+            sections
+        };
+
+        match band {
+            Band::LowPass(cutoff_hz) => Self {
+                sections: sections(false, cutoff_hz),
+                parallel: Vec::new(),
+            },
+            Band::HighPass(cutoff_hz) => Self {
+                sections: sections(true, cutoff_hz),
+                parallel: Vec::new(),
+            },
+            Band::BandPass(lower_hz, upper_hz) => Self {
+                sections: [sections(true, lower_hz), sections(false, upper_hz)].concat(),
+                parallel: Vec::new(),
+            },
+            Band::BandReject(lower_hz, upper_hz) => Self {
+                sections: sections(false, lower_hz),
+                parallel: sections(true, upper_hz),
+            },
+        }
+    }
+
+    /// Filter samples in place
+    pub fn process(&self, samples: &mut [f32]) {
+        if self.parallel.is_empty() {
+            cascade(&self.sections, samples);
+        } else {
+            let mut other = samples.to_vec();
+            cascade(&self.sections, samples);
+            cascade(&self.parallel, &mut other);
+            for (sample, other) in samples.iter_mut().zip(other) {
+                *sample += other;
+            }
+        }
+    }
+
+    /// Filter trace
+    pub fn apply(&self, trace: &Trace) -> Trace {
+        let mut trace = trace.clone();
+        self.process(&mut trace.samples);
+        trace
+    }
+}
+
+/// Apply sections using transposed direct form II
+fn cascade(sections: &[Biquad], samples: &mut [f32]) {
+    for section in sections {
+        // Start from steady state for the first sample to avoid turn-on transient
+        let first = samples.first().copied().unwrap_or(0.0) as f64;
+        let gain = (section.b0 + section.b1 + section.b2) / (1.0 + section.a1 + section.a2);
+        let mut s1 = first * (gain - section.b0);
+        let mut s2 = first * (section.b2 - gain * section.a2);
+
+        for sample in samples.iter_mut() {
+            let input = *sample as f64;
+            let output = section.b0 * input + s1;
+            s1 = section.b1 * input - section.a1 * output + s2;
+            s2 = section.b2 * input - section.a2 * output;
+            *sample = output as f32;
+        }
+    }
+}
+
+/// Finite impulse response filter
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Fir {
+    /// Symmetric filter coefficients
+    pub taps: Vec<f32>,
+}
+
+impl Fir {
+    /// Design windowed-sinc filter
+    ///
+    /// Number of taps is rounded up to odd value of at least 3.
+    pub fn windowed_sinc(band: Band, taps: usize, sample_rate_hz: f32, window: Window) -> Self {
+        let length = taps.max(3) | 1;
+        let center = (length / 2) as f64;
+
+        let low_pass = |cutoff_hz: f32| {
+            let cutoff = cutoff_hz as f64 / sample_rate_hz as f64;
+            let taps = (0..length)
+                .map(|index| {
+                    let offset = index as f64 - center;
+                    let sinc = if offset == 0.0 {
+                        2.0 * cutoff
+                    } else {
+                        (2.0 * PI * cutoff * offset).sin() / (PI * offset)
+                    };
+                    sinc * window.value(index, length - 1)
+                })
+                .collect::<Vec<_>>();
+            // Normalize to unity gain at DC
+            let sum: f64 = taps.iter().sum();
+            taps.into_iter().map(|tap| tap / sum).collect::<Vec<_>>()
+        };
+        let invert = |mut taps: Vec<f64>| {
+            taps.iter_mut().for_each(|tap| *tap = -*tap);
+            taps[length / 2] += 1.0;
+            taps
+        };
+        let subtract =
+            |a: Vec<f64>, b: Vec<f64>| a.into_iter().zip(b).map(|(a, b)| a - b).collect::<Vec<_>>();
+
+        let taps = match band {
+            Band::LowPass(cutoff_hz) => low_pass(cutoff_hz),
+            Band::HighPass(cutoff_hz) => invert(low_pass(cutoff_hz)),
+            Band::BandPass(lower_hz, upper_hz) => subtract(low_pass(upper_hz), low_pass(lower_hz)),
+            Band::BandReject(lower_hz, upper_hz) => {
+                invert(subtract(low_pass(upper_hz), low_pass(lower_hz)))
+            }
+        };
+
+        Self {
+            taps: taps.into_iter().map(|tap| tap as f32).collect(),
+        }
+    }
+
+    /// Filter samples without delay
+    ///
+    /// Samples beyond edges are assumed to be equal to edge samples.
+    pub fn process(&self, samples: &[f32]) -> Vec<f32> {
+        let length = samples.len() as isize;
+        let center = (self.taps.len() / 2) as isize;
+
+        (0..length)
+            .map(|index| {
+                self.taps
+                    .iter()
+                    .enumerate()
+                    .map(|(tap, &weight)| {
+                        let source = (index + center - tap as isize).clamp(0, length - 1);
+                        weight as f64 * samples[source as usize] as f64
+                    })
+                    .sum::<f64>() as f32
+            })
+            .collect()
+    }
+
+    /// Filter trace
+    pub fn apply(&self, trace: &Trace) -> Trace {
+        Trace {
+            samples: self.process(&trace.samples),
+            ..trace.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{analysis::measure, Unit};
+    use core::f32::consts::FRAC_1_SQRT_2;
+
+    fn sine(frequency_hz: f32) -> Trace {
+        Trace {
+            samples: (0..4000)
+                .map(|index| (2.0 * PI * frequency_hz as f64 * index as f64 / 10.0e3).sin() as f32)
+                .collect(),
+            sample_rate_hz: 10.0e3,
+            time_offset: 0.0,
+            unit: Unit::V,
+        }
+    }
+
+    /// RMS of settled part
+    fn rms(trace: &Trace) -> f32 {
+        measure::rms(&Trace {
+            samples: trace.samples[1000..3000].to_vec(),
+            ..trace.clone()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn butterworth() {
+        let iir = Iir::butterworth(Band::LowPass(100.0), 4, 10.0e3);
+
+        assert_eq!(iir.sections.len(), 2);
+        assert!((rms(&iir.apply(&sine(10.0))) - FRAC_1_SQRT_2).abs() < 1e-3);
+        // Half power at cutoff
+        assert!((rms(&iir.apply(&sine(100.0))) - 0.5).abs() < 1e-2);
+        // 80 dB per decade
+        assert!(rms(&iir.apply(&sine(1000.0))) < 1e-4);
+
+        let iir = Iir::butterworth(Band::BandReject(100.0, 1000.0), 3, 10.0e3);
+        assert!((rms(&iir.apply(&sine(10.0))) - FRAC_1_SQRT_2).abs() < 1e-2);
+        assert!(rms(&iir.apply(&sine(316.0))) < 0.1);
+    }
+
+    #[test]
+    fn windowed_sinc() {
+        let fir = Fir::windowed_sinc(Band::BandPass(200.0, 400.0), 200, 10.0e3, Window::Hann);
+
+        assert_eq!(fir.taps.len(), 201);
+        assert!((rms(&fir.apply(&sine(300.0))) - FRAC_1_SQRT_2).abs() < 1e-2);
+        assert!(rms(&fir.apply(&sine(10.0))) < 1e-2);
+        assert!(rms(&fir.apply(&sine(1000.0))) < 1e-2);
+
+        for &taps in [0, 1].iter() {
+            let fir = Fir::windowed_sinc(Band::LowPass(1.0e3), taps, 10.0e3, Window::Hann);
+            assert_eq!(fir.taps.len(), 3);
+            assert!(fir.taps.iter().all(|tap| tap.is_finite()));
+        }
+    }
+}
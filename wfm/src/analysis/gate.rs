@@ -0,0 +1,92 @@
+/*!
+
+Measurement gating
+
+Gate selects part of record like cursors on instrument screen do, so
+measurements and spectra are computed on that part only.
+
+*/
+use crate::Trace;
+use core::ops::Range;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Part of record to analyze
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Gate {
+    /// Range of sample indices
+    Samples(Range<usize>),
+    /// Time window in seconds relative to trigger
+    Time { start: f32, end: f32 },
+}
+
+impl Gate {
+    /// Gate between times in seconds relative to trigger
+    pub fn time(start: f32, end: f32) -> Self {
+        Gate::Time { start, end }
+    }
+
+    /// Samples of trace within gate
+    ///
+    /// Range is limited to trace, times select samples between them
+    /// inclusive.
+    pub fn range(&self, trace: &Trace) -> Range<usize> {
+        let length = trace.len();
+        let (start, end) = match self {
+            Gate::Samples(range) => (range.start, range.end),
+            Gate::Time { start, end } => {
+                let index = |time: f32| (time - trace.time_offset) * trace.sample_rate_hz;
+                let first = index(*start).ceil().max(0.0) as usize;
+                let last = index(*end).floor();
+                (first, if last < 0.0 { 0 } else { last as usize + 1 })
+            }
+        };
+        let end = end.min(length);
+        start.min(end)..end
+    }
+}
+
+impl Trace {
+    /// Part of trace within gate
+    pub fn gated(&self, gate: &Gate) -> Trace {
+        let range = gate.range(self);
+        Trace {
+            samples: self.samples[range.clone()].to_vec(),
+            sample_rate_hz: self.sample_rate_hz,
+            time_offset: self.time(range.start),
+            unit: self.unit,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{analysis::measure::Measurements, Unit};
+
+    #[test]
+    fn gates() {
+        let trace = Trace {
+            samples: (0..100).map(|index| index as f32).collect(),
+            sample_rate_hz: 1.0e3,
+            time_offset: -0.05,
+            unit: Unit::V,
+        };
+
+        assert_eq!(Gate::Samples(10..20).range(&trace), 10..20);
+        assert_eq!(Gate::Samples(90..200).range(&trace), 90..100);
+        assert_eq!(Gate::time(-0.0105, 0.0).range(&trace), 40..51);
+        assert_eq!(Gate::time(1.0, 2.0).range(&trace), 100..100);
+        assert_eq!(Gate::time(-2.0, -1.0).range(&trace), 0..0);
+
+        let gated = trace.gated(&Gate::time(0.0, 0.01));
+        assert_eq!(gated.samples[0], 50.0);
+        assert!(gated.time_offset.abs() < 1.0e-6);
+        assert_eq!(
+            Measurements::gated(&trace, &Gate::Samples(0..10)).vmax,
+            Some(9.0)
+        );
+    }
+}
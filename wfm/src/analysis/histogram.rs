@@ -0,0 +1,159 @@
+/*!
+
+Amplitude histogram
+
+*/
+use crate::Trace;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Amplitude distribution of trace samples
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Histogram {
+    /// Number of samples in each bin
+    pub counts: Vec<usize>,
+    /// Lower edge of first bin
+    pub min: f32,
+    /// Upper edge of last bin
+    pub max: f32,
+    /// Total number of samples
+    pub total: usize,
+    pub mean: f32,
+    /// Standard deviation
+    pub stddev: f32,
+}
+
+/// Build amplitude histogram of trace with given number of bins
+///
+/// Bins evenly cover the range between minimum and maximum samples.
+/// Returns `None` for empty trace or zero bins.
+pub fn histogram(trace: &Trace, bins: usize) -> Option<Histogram> {
+    if trace.is_empty() || bins == 0 {
+        return None;
+    }
+
+    let (min, max, sum) = trace.samples.iter().fold(
+        (f32::INFINITY, f32::NEG_INFINITY, 0.0f64),
+        |(min, max, sum), &value| (min.min(value), max.max(value), sum + value as f64),
+    );
+
+    let total = trace.len();
+    let mean = sum / total as f64;
+    let variance = trace
+        .samples
+        .iter()
+        .map(|&value| {
+            let delta = value as f64 - mean;
+            delta * delta
+        })
+        .sum::<f64>()
+        / total as f64;
+
+    let mut counts = vec![0; bins];
+    let width = (max - min) / bins as f32;
+
+    for &value in &trace.samples {
+        let bin = if width > 0.0 {
+            (((value - min) / width) as usize).min(bins - 1)
+        } else {
+            0
+        };
+        counts[bin] += 1;
+    }
+
+    Some(Histogram {
+        counts,
+        min,
+        max,
+        total,
+        mean: mean as f32,
+        stddev: variance.sqrt() as f32,
+    })
+}
+
+impl Histogram {
+    /// Width of single bin
+    pub fn bin_width(&self) -> f32 {
+        (self.max - self.min) / self.counts.len() as f32
+    }
+
+    /// Center value of bin
+    pub fn bin_center(&self, bin: usize) -> f32 {
+        self.min + (bin as f32 + 0.5) * self.bin_width()
+    }
+
+    /// Center value of most populated bin
+    pub fn mode(&self) -> f32 {
+        let bin = self
+            .counts
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, &count)| count)
+            .map(|(bin, _)| bin)
+            .unwrap_or(0);
+
+        self.bin_center(bin)
+    }
+
+    /// Value below which given percent of samples falls
+    ///
+    /// Values are interpolated linearly inside bins.
+    pub fn percentile(&self, percent: f32) -> f32 {
+        let target = (percent.clamp(0.0, 100.0) / 100.0) as f64 * self.total as f64;
+        let width = self.bin_width();
+        let mut below = 0.0;
+
+        for (bin, &count) in self.counts.iter().enumerate() {
+            let count = count as f64;
+            if count > 0.0 && below + count >= target {
+                let fraction = ((target - below) / count) as f32;
+                return self.min + (bin as f32 + fraction) * width;
+            }
+            below += count;
+        }
+
+        self.max
+    }
+
+    /// Median value
+    pub fn median(&self) -> f32 {
+        self.percentile(50.0)
+    }
+
+    /// Fractions of samples in the lowest and the highest bins
+    ///
+    /// Large values usually mean the signal is clipped by ADC.
+    pub fn edge_fractions(&self) -> (f32, f32) {
+        let fraction =
+            |count: Option<&usize>| count.copied().unwrap_or(0) as f32 / self.total.max(1) as f32;
+
+        (fraction(self.counts.first()), fraction(self.counts.last()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Unit;
+
+    #[test]
+    fn ramp() {
+        let trace = Trace {
+            samples: (0..1000).map(|index| index as f32 / 10.0).collect(),
+            sample_rate_hz: 1.0,
+            time_offset: 0.0,
+            unit: Unit::V,
+        };
+        let h = histogram(&trace, 10).unwrap();
+
+        assert_eq!(h.counts, vec![100; 10]);
+        assert_eq!(h.total, 1000);
+        assert!((h.mean - 49.95).abs() < 1e-3);
+        assert!((h.stddev - 28.866).abs() < 1e-2);
+        assert!((h.median() - 49.95).abs() < 1e-3);
+        assert!((h.percentile(10.0) - 9.99).abs() < 1e-3);
+        assert_eq!(h.edge_fractions(), (0.1, 0.1));
+    }
+}
@@ -0,0 +1,188 @@
+/*!
+
+Period jitter and frequency stability
+
+*/
+use super::edge::{self, Slope};
+use crate::{Threshold, Trace};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Jitter statistics in seconds
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Jitter {
+    /// Number of complete cycles
+    pub cycles: usize,
+    pub mean_period: f64,
+    /// Standard deviation of periods
+    pub period_rms: f64,
+    /// Difference between longest and shortest period
+    pub period_pp: f64,
+    /// Root mean square of adjacent periods differences
+    pub cycle_to_cycle_rms: f64,
+    /// Maximum absolute difference of adjacent periods
+    pub cycle_to_cycle_max: f64,
+    /// Standard deviation of edges from ideal clock (time interval error)
+    pub tie_rms: f64,
+    /// Allan deviation of fractional frequency for averaging over 1, 2, 4... cycles
+    pub allan_deviation: Vec<(usize, f64)>,
+}
+
+/// Times of edges matching slope in seconds
+pub fn edge_times(trace: &Trace, threshold: Threshold, slope: Slope) -> Vec<f64> {
+    edge::find(trace, threshold.levels(trace))
+        .into_iter()
+        .filter(|edge| slope.matches(edge.rising))
+        .map(|edge| edge.time)
+        .collect()
+}
+
+/// Per-cycle periods series
+pub fn periods(times: &[f64]) -> Vec<f64> {
+    times.windows(2).map(|pair| pair[1] - pair[0]).collect()
+}
+
+impl Jitter {
+    /// Analyze edges of trace
+    pub fn of(trace: &Trace, threshold: Threshold, slope: Slope) -> Option<Self> {
+        Self::of_times(&edge_times(trace, threshold, slope))
+    }
+
+    /// Analyze edge times
+    ///
+    /// Returns `None` when there are less than three edges.
+    pub fn of_times(times: &[f64]) -> Option<Self> {
+        if times.len() < 3 {
+            return None;
+        }
+
+        let periods = periods(times);
+        let cycles = periods.len();
+
+        let mean_period = periods.iter().sum::<f64>() / cycles as f64;
+        let period_rms = (periods
+            .iter()
+            .map(|period| (period - mean_period).powi(2))
+            .sum::<f64>()
+            / cycles as f64)
+            .sqrt();
+        let (min, max) = periods
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &period| {
+                (min.min(period), max.max(period))
+            });
+
+        let differences = periods
+            .windows(2)
+            .map(|pair| pair[1] - pair[0])
+            .collect::<Vec<_>>();
+        let cycle_to_cycle_rms =
+            (differences.iter().map(|d| d * d).sum::<f64>() / differences.len() as f64).sqrt();
+        let cycle_to_cycle_max = differences.iter().fold(0.0f64, |max, d| max.max(d.abs()));
+
+        // Ideal clock is least squares line through edge times
+        let count = times.len() as f64;
+        let center = (count - 1.0) / 2.0;
+        let mean_time = times.iter().sum::<f64>() / count;
+        let (sum_xy, sum_xx) =
+            times
+                .iter()
+                .enumerate()
+                .fold((0.0, 0.0), |(sum_xy, sum_xx), (index, &time)| {
+                    let x = index as f64 - center;
+                    (sum_xy + x * (time - mean_time), sum_xx + x * x)
+                });
+        let slope = sum_xy / sum_xx;
+        let tie_rms = (times
+            .iter()
+            .enumerate()
+            .map(|(index, &time)| {
+                let error = time - (mean_time + slope * (index as f64 - center));
+                error * error
+            })
+            .sum::<f64>()
+            / count)
+            .sqrt();
+
+        // Overlapping Allan deviation from edge times as phase data
+        let mut allan_deviation = Vec::new();
+        let mut span = 1;
+        while times.len() > 2 * span {
+            let terms = times.len() - 2 * span;
+            let sum = (0..terms)
+                .map(|index| {
+                    let second = times[index + 2 * span] - 2.0 * times[index + span] + times[index];
+                    second * second
+                })
+                .sum::<f64>();
+            let tau = span as f64 * mean_period;
+            let variance = sum / (2.0 * tau * tau * terms as f64);
+            allan_deviation.push((span, variance.sqrt()));
+            span *= 2;
+        }
+
+        Some(Self {
+            cycles,
+            mean_period,
+            period_rms,
+            period_pp: max - min,
+            cycle_to_cycle_rms,
+            cycle_to_cycle_max,
+            tie_rms,
+            allan_deviation,
+        })
+    }
+
+    /// Mean frequency in Hz
+    pub fn frequency(&self) -> f64 {
+        1.0 / self.mean_period
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{parse, Channel};
+    use std::fs::read;
+
+    #[test]
+    fn alternating_periods() {
+        // Periods alternate between 0.9 and 1.1 ms
+        let times = (0..11)
+            .map(|index| index as f64 * 1.0e-3 + if index % 2 == 1 { -0.1e-3 } else { 0.0 })
+            .collect::<Vec<_>>();
+        let j = Jitter::of_times(&times).unwrap();
+
+        assert_eq!(j.cycles, 10);
+        assert!((j.mean_period - 1.0e-3).abs() < 1e-12);
+        assert!((j.frequency() - 1.0e3).abs() < 1e-6);
+        assert!((j.period_rms - 0.1e-3).abs() < 1e-12);
+        assert!((j.period_pp - 0.2e-3).abs() < 1e-12);
+        assert!((j.cycle_to_cycle_rms - 0.2e-3).abs() < 1e-12);
+        assert!((j.cycle_to_cycle_max - 0.2e-3).abs() < 1e-12);
+        assert!(j.tie_rms > 0.0 && j.tie_rms < 0.1e-3);
+        assert_eq!(j.allan_deviation.len(), 3);
+        assert_eq!(j.allan_deviation[0].0, 1);
+        // Uniform clock has no instability
+        let ideal = (0..11).map(|index| index as f64).collect::<Vec<_>>();
+        let j = Jitter::of_times(&ideal).unwrap();
+        assert_eq!(j.allan_deviation[0].1, 0.0);
+        assert_eq!(j.tie_rms, 0.0);
+    }
+
+    #[test]
+    fn ds1052e_2ch() {
+        let i = read("test/ds1052e_2ch.wfm").unwrap();
+        let r = parse(&i).unwrap();
+        let t = r.trace(Channel::Ch1).unwrap();
+        let e = edge_times(&t, Threshold::default(), Slope::Rising);
+        let j = Jitter::of(&t, Threshold::default(), Slope::Rising).unwrap();
+
+        assert_eq!(j.cycles + 1, e.len());
+        assert_eq!(periods(&e).len(), j.cycles);
+        assert!(j.period_rms <= j.period_pp);
+        assert!(j.cycle_to_cycle_max >= j.cycle_to_cycle_rms);
+    }
+}
@@ -0,0 +1,269 @@
+/*!
+
+Pass/fail mask testing
+
+Mask is tolerance envelope around golden capture: upper and lower limits
+follow extremes of golden trace within time margin widened by voltage
+margin, so edges may move by time margin and levels by voltage margin.
+Captures are tested against mask sample by sample like mask test of
+instrument does.
+
+*/
+use crate::{Channel, Error, Result, Trace, Unit, WaveformData};
+use std::collections::VecDeque;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Tolerance envelope of channel
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Mask {
+    pub channel: Channel,
+    /// Upper limits in units
+    pub upper: Vec<f32>,
+    /// Lower limits in units
+    pub lower: Vec<f32>,
+    pub sample_rate_hz: f32,
+    /// Time of first limits relative to trigger in seconds
+    pub time_offset: f32,
+    pub unit: Unit,
+}
+
+/// Sample outside of mask
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Violation {
+    /// Index of sample in tested trace
+    pub index: usize,
+    /// Time of sample relative to trigger in seconds
+    pub time: f32,
+    pub value: f32,
+    /// Exceeded limit
+    pub limit: f32,
+    /// Upper limit is exceeded, otherwise lower one
+    pub upper: bool,
+}
+
+/// Result of mask test
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MaskReport {
+    pub channel: Channel,
+    /// Number of samples within time span of mask
+    pub tested: usize,
+    /// Number of samples outside of time span of mask
+    pub untested: usize,
+    pub violations: Vec<Violation>,
+}
+
+impl MaskReport {
+    /// All tested samples are within mask
+    pub fn passed(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// Part of tested samples outside of mask in percents
+    pub fn percent(&self) -> f32 {
+        if self.tested == 0 {
+            0.0
+        } else {
+            100.0 * self.violations.len() as f32 / self.tested as f32
+        }
+    }
+}
+
+/// Extremes of samples within distance from each one
+///
+/// Monotonic queue keeps candidates in order, so each sample is pushed and
+/// popped once.
+fn sliding(samples: &[f32], distance: usize, better: impl Fn(f32, f32) -> bool) -> Vec<f32> {
+    let mut queue = VecDeque::<usize>::new();
+    let mut extremes = Vec::with_capacity(samples.len());
+    let mut next = 0;
+
+    for index in 0..samples.len() {
+        let end = (index + distance + 1).min(samples.len());
+        while next < end {
+            while queue
+                .back()
+                .is_some_and(|&last| !better(samples[last], samples[next]))
+            {
+                queue.pop_back();
+            }
+            queue.push_back(next);
+            next += 1;
+        }
+        while queue.front().is_some_and(|&first| first + distance < index) {
+            queue.pop_front();
+        }
+        extremes.push(samples[queue[0]]);
+    }
+    extremes
+}
+
+impl Mask {
+    /// Mask around golden trace with voltage margin in units and time margin
+    /// in seconds
+    pub fn around(channel: Channel, golden: &Trace, volts: f32, time: f32) -> Self {
+        let distance = (time.abs() * golden.sample_rate_hz).floor() as usize;
+        let volts = volts.abs();
+        let upper = sliding(&golden.samples, distance, |a, b| a > b);
+        let lower = sliding(&golden.samples, distance, |a, b| a < b);
+
+        Self {
+            channel,
+            upper: upper.into_iter().map(|limit| limit + volts).collect(),
+            lower: lower.into_iter().map(|limit| limit - volts).collect(),
+            sample_rate_hz: golden.sample_rate_hz,
+            time_offset: golden.time_offset,
+            unit: golden.unit,
+        }
+    }
+
+    /// Mask around channel of golden capture
+    pub fn golden(data: &WaveformData, channel: Channel, volts: f32, time: f32) -> Result<Self> {
+        let golden = data.trace(channel).ok_or(Error::ChannelDisabled(channel))?;
+        Ok(Self::around(channel, &golden, volts, time))
+    }
+
+    /// Number of limits
+    pub fn len(&self) -> usize {
+        self.upper.len()
+    }
+
+    /// Mask has no limits
+    pub fn is_empty(&self) -> bool {
+        self.upper.is_empty()
+    }
+
+    /// Index of limits nearest to time
+    fn index(&self, time: f32) -> Option<usize> {
+        let index = ((time - self.time_offset) * self.sample_rate_hz).round();
+        if index >= 0.0 && (index as usize) < self.len() {
+            Some(index as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Test trace
+    ///
+    /// Samples are compared with limits nearest in time, samples outside of
+    /// mask time span are not tested.
+    pub fn test_trace(&self, trace: &Trace) -> Result<MaskReport> {
+        if trace.unit != self.unit {
+            return Err(Error::UnitMismatch(self.unit, trace.unit));
+        }
+
+        let mut report = MaskReport {
+            channel: self.channel,
+            tested: 0,
+            untested: 0,
+            violations: Vec::new(),
+        };
+        for (index, &value) in trace.samples.iter().enumerate() {
+            let time = trace.time(index);
+            let limits = match self.index(time) {
+                Some(limits) => limits,
+                None => {
+                    report.untested += 1;
+                    continue;
+                }
+            };
+            report.tested += 1;
+
+            let (upper, lower) = (self.upper[limits], self.lower[limits]);
+            // NaN is treated as violation of upper limit
+            let exceeded = if value > upper || value.is_nan() {
+                Some((upper, true))
+            } else if value < lower {
+                Some((lower, false))
+            } else {
+                None
+            };
+            if let Some((limit, upper)) = exceeded {
+                report.violations.push(Violation {
+                    index,
+                    time,
+                    value,
+                    limit,
+                    upper,
+                });
+            }
+        }
+        Ok(report)
+    }
+
+    /// Test channel of capture
+    pub fn test(&self, data: &WaveformData) -> Result<MaskReport> {
+        let trace = data
+            .trace(self.channel)
+            .ok_or(Error::ChannelDisabled(self.channel))?;
+        self.test_trace(&trace)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse;
+    use std::fs::read;
+
+    fn step(at: usize) -> Trace {
+        Trace {
+            samples: (0..100)
+                .map(|index| if index < at { 0.0 } else { 1.0 })
+                .collect(),
+            sample_rate_hz: 1.0e3,
+            time_offset: -0.05,
+            unit: Unit::V,
+        }
+    }
+
+    #[test]
+    fn step_edge() {
+        let mask = Mask::around(Channel::Ch1, &step(50), 0.1, 0.002);
+        assert_eq!(mask.upper[47], 0.1);
+        assert_eq!(mask.upper[48], 1.1);
+        assert_eq!(mask.lower[51], -0.1);
+        assert_eq!(mask.lower[52], 0.9);
+
+        // Edge within time margin passes
+        assert!(mask.test_trace(&step(52)).unwrap().passed());
+
+        let report = mask.test_trace(&step(53)).unwrap();
+        assert_eq!(report.tested, 100);
+        assert_eq!(report.violations.len(), 1);
+        let violation = report.violations[0];
+        assert_eq!(violation.index, 52);
+        assert!((violation.time - 0.002).abs() < 1.0e-6);
+        assert!(!violation.upper);
+        assert_eq!(violation.limit, 0.9);
+
+        // Shifted capture is tested within mask span only
+        let mut shifted = step(50);
+        shifted.time_offset = 0.0;
+        let report = mask.test_trace(&shifted).unwrap();
+        assert_eq!((report.tested, report.untested), (50, 50));
+        assert!(!report.passed());
+    }
+
+    #[test]
+    fn ds1052e_2ch() {
+        let i = read("test/ds1052e_2ch.wfm").unwrap();
+        let r = parse(&i).unwrap();
+        let mask = Mask::golden(&r, Channel::Ch2, 0.01, 1.0e-7).unwrap();
+
+        let report = mask.test(&r).unwrap();
+        assert!(report.passed());
+        assert_eq!(report.tested, mask.len());
+
+        let mut trace = r.trace(Channel::Ch2).unwrap();
+        trace.samples[1000] += 20.0;
+        let report = mask.test_trace(&trace).unwrap();
+        assert_eq!(report.violations.len(), 1);
+        assert_eq!(report.violations[0].index, 1000);
+        assert!(report.violations[0].upper);
+    }
+}
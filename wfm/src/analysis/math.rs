@@ -0,0 +1,134 @@
+/*!
+
+Math channel operations
+
+*/
+use crate::{Channel, Error, Result, Trace, Unit, WaveformData};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Elementwise operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Operation {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+impl Operation {
+    /// Apply operation to values
+    pub fn apply(&self, a: f32, b: f32) -> f32 {
+        match self {
+            Operation::Add => a + b,
+            Operation::Subtract => a - b,
+            Operation::Multiply => a * b,
+            Operation::Divide => a / b,
+        }
+    }
+
+    /// Unit of result
+    ///
+    /// Combinations which have no own unit give [`Unit::U`].
+    pub fn unit(&self, a: Unit, b: Unit) -> Unit {
+        use Unit::*;
+
+        match (self, a, b) {
+            (Operation::Add | Operation::Subtract, a, b) if a == b => a,
+            (Operation::Multiply, V, A) | (Operation::Multiply, A, V) => W,
+            (Operation::Divide, W, V) => A,
+            (Operation::Divide, W, A) => V,
+            _ => U,
+        }
+    }
+}
+
+/// Apply operation to traces sample by sample
+///
+/// Traces should have same sample rate and length, use resampling to align
+/// captures with different timebases.
+pub fn apply(a: &Trace, b: &Trace, operation: Operation) -> Result<Trace> {
+    if a.sample_rate_hz != b.sample_rate_hz {
+        return Err(Error::SampleRateMismatch(
+            a.sample_rate_hz,
+            b.sample_rate_hz,
+        ));
+    }
+    if a.len() != b.len() {
+        return Err(Error::LengthMismatch(a.len(), b.len()));
+    }
+
+    Ok(Trace {
+        samples: a
+            .samples
+            .iter()
+            .zip(b.samples.iter())
+            .map(|(&a, &b)| operation.apply(a, b))
+            .collect(),
+        sample_rate_hz: a.sample_rate_hz,
+        time_offset: a.time_offset,
+        unit: operation.unit(a.unit, b.unit),
+    })
+}
+
+/// Sum of traces
+pub fn add(a: &Trace, b: &Trace) -> Result<Trace> {
+    apply(a, b, Operation::Add)
+}
+
+/// Difference of traces
+pub fn subtract(a: &Trace, b: &Trace) -> Result<Trace> {
+    apply(a, b, Operation::Subtract)
+}
+
+/// Product of traces
+pub fn multiply(a: &Trace, b: &Trace) -> Result<Trace> {
+    apply(a, b, Operation::Multiply)
+}
+
+/// Quotient of traces
+pub fn divide(a: &Trace, b: &Trace) -> Result<Trace> {
+    apply(a, b, Operation::Divide)
+}
+
+impl WaveformData {
+    /// Math channel of first channel with second one like scope computes it
+    pub fn math(&self, operation: Operation) -> Result<Trace> {
+        let trace = |channel| self.trace(channel).ok_or(Error::ChannelDisabled(channel));
+
+        apply(&trace(Channel::Ch1)?, &trace(Channel::Ch2)?, operation)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse;
+    use std::fs::read;
+
+    #[test]
+    fn ds1052e_2ch() {
+        let i = read("test/ds1052e_2ch.wfm").unwrap();
+        let r = parse(&i).unwrap();
+        let ch1 = r.trace(Channel::Ch1).unwrap();
+        let ch2 = r.trace(Channel::Ch2).unwrap();
+        let sum = r.math(Operation::Add).unwrap();
+
+        assert_eq!(sum.unit, Unit::V);
+        assert_eq!(sum.len(), ch1.len());
+        assert_eq!(sum.samples[1000], ch1.samples[1000] + ch2.samples[1000]);
+
+        let mut current = ch2.clone();
+        current.unit = Unit::A;
+        assert_eq!(multiply(&ch1, &current).unwrap().unit, Unit::W);
+        assert_eq!(divide(&ch1, &ch2).unwrap().unit, Unit::U);
+
+        current.samples.pop();
+        assert_eq!(
+            subtract(&ch1, &current).unwrap_err(),
+            Error::LengthMismatch(ch1.len(), current.len())
+        );
+    }
+}
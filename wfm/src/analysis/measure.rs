@@ -0,0 +1,364 @@
+/*!
+
+Automatic measurements
+
+Definitions follow the scope's on-screen measurements: reference levels are
+taken at 10%, 50% and 90% between minimum and maximum values, time values are
+averaged over all complete edges or pulses found in the record.
+
+*/
+use super::{
+    edge::{self, Edge, Levels},
+    Gate,
+};
+use crate::Trace;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Lower reference level
+const LOW_LEVEL: f32 = 0.1;
+/// Middle reference level
+const MID_LEVEL: f32 = 0.5;
+/// Upper reference level
+const HIGH_LEVEL: f32 = 0.9;
+
+/// Frequency counter reading
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FrequencyCount {
+    /// Mean frequency in Hz
+    pub frequency: f32,
+    /// Number of complete cycles
+    pub cycles: usize,
+    /// Ratio in range 0 to 1 which grows with number of cycles and falls
+    /// with spread of their periods
+    pub confidence: f32,
+}
+
+/// All automatic measurements of trace
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Measurements {
+    pub vmax: Option<f32>,
+    pub vmin: Option<f32>,
+    pub vpp: Option<f32>,
+    pub mean: Option<f32>,
+    pub rms: Option<f32>,
+    pub frequency: Option<f32>,
+    pub period: Option<f32>,
+    pub duty_cycle: Option<f32>,
+    pub rise_time: Option<f32>,
+    pub fall_time: Option<f32>,
+    pub positive_width: Option<f32>,
+    pub negative_width: Option<f32>,
+}
+
+impl Measurements {
+    /// Measure all parameters of trace
+    pub fn of(trace: &Trace) -> Self {
+        let edges = Edges::of(trace);
+
+        Self {
+            vmax: vmax(trace),
+            vmin: vmin(trace),
+            vpp: vpp(trace),
+            mean: mean(trace),
+            rms: rms(trace),
+            frequency: edges.counter().map(|count| count.frequency),
+            period: edges.period(),
+            duty_cycle: edges.duty_cycle(),
+            rise_time: edges.rise_time(),
+            fall_time: edges.fall_time(),
+            positive_width: edges.positive_width(),
+            negative_width: edges.negative_width(),
+        }
+    }
+
+    /// Measure parameters of part of trace within gate
+    pub fn gated(trace: &Trace, gate: &Gate) -> Self {
+        Self::of(&trace.gated(gate))
+    }
+}
+
+/// Maximum value
+pub fn vmax(trace: &Trace) -> Option<f32> {
+    trace.samples.iter().copied().fold(None, |max, value| {
+        Some(max.map_or(value, |max: f32| max.max(value)))
+    })
+}
+
+/// Minimum value
+pub fn vmin(trace: &Trace) -> Option<f32> {
+    trace.samples.iter().copied().fold(None, |min, value| {
+        Some(min.map_or(value, |min: f32| min.min(value)))
+    })
+}
+
+/// Peak-to-peak value
+pub fn vpp(trace: &Trace) -> Option<f32> {
+    Some(vmax(trace)? - vmin(trace)?)
+}
+
+/// Average value
+pub fn mean(trace: &Trace) -> Option<f32> {
+    if trace.is_empty() {
+        return None;
+    }
+
+    let sum: f64 = trace.samples.iter().map(|&value| value as f64).sum();
+
+    Some((sum / trace.len() as f64) as f32)
+}
+
+/// Root mean square value (includes DC component)
+pub fn rms(trace: &Trace) -> Option<f32> {
+    if trace.is_empty() {
+        return None;
+    }
+
+    let sum: f64 = trace
+        .samples
+        .iter()
+        .map(|&value| value as f64 * value as f64)
+        .sum();
+
+    Some((sum / trace.len() as f64).sqrt() as f32)
+}
+
+/// Frequency in Hz
+pub fn frequency(trace: &Trace) -> Option<f32> {
+    counter(trace).map(|count| count.frequency)
+}
+
+/// Count cycles between interpolated rising crossings of middle level
+///
+/// Crossings are detected with hysteresis between lower and upper levels,
+/// so noise does not add cycles. Unlike spectrum peak it is accurate for
+/// records of few cycles.
+pub fn counter(trace: &Trace) -> Option<FrequencyCount> {
+    Edges::of(trace).counter()
+}
+
+/// Period in seconds
+pub fn period(trace: &Trace) -> Option<f32> {
+    Edges::of(trace).period()
+}
+
+/// Positive duty cycle as ratio of positive width to period
+pub fn duty_cycle(trace: &Trace) -> Option<f32> {
+    Edges::of(trace).duty_cycle()
+}
+
+/// Rise time between lower and upper levels in seconds
+pub fn rise_time(trace: &Trace) -> Option<f32> {
+    Edges::of(trace).rise_time()
+}
+
+/// Fall time between upper and lower levels in seconds
+pub fn fall_time(trace: &Trace) -> Option<f32> {
+    Edges::of(trace).fall_time()
+}
+
+/// Positive pulse width at middle level in seconds
+pub fn positive_width(trace: &Trace) -> Option<f32> {
+    Edges::of(trace).positive_width()
+}
+
+/// Negative pulse width at middle level in seconds
+pub fn negative_width(trace: &Trace) -> Option<f32> {
+    Edges::of(trace).negative_width()
+}
+
+/// Edges found in trace
+struct Edges {
+    edges: Vec<Edge>,
+    time_step: f64,
+}
+
+impl Edges {
+    fn of(trace: &Trace) -> Self {
+        let time_step = trace.time_step() as f64;
+        let edges = match (vmin(trace), vmax(trace)) {
+            (Some(min), Some(max)) if max > min => {
+                let level = |ratio| min + (max - min) * ratio;
+                edge::find_samples(
+                    &trace.samples,
+                    Levels {
+                        low: level(LOW_LEVEL),
+                        threshold: level(MID_LEVEL),
+                        high: level(HIGH_LEVEL),
+                    },
+                )
+            }
+            _ => Vec::new(),
+        };
+
+        Self { edges, time_step }
+    }
+
+    fn positions(&self, rising: bool) -> impl Iterator<Item = f64> + '_ {
+        self.edges
+            .iter()
+            .filter(move |edge| edge.rising == rising)
+            .map(|edge| edge.position)
+    }
+
+    fn period(&self) -> Option<f32> {
+        let mut rising = self.positions(true);
+        let first = rising.next()?;
+        let (count, last) = rising.fold((0, first), |(count, _), position| (count + 1, position));
+
+        if count > 0 {
+            Some(((last - first) / count as f64 * self.time_step) as f32)
+        } else {
+            None
+        }
+    }
+
+    fn counter(&self) -> Option<FrequencyCount> {
+        let rising = self.positions(true).collect::<Vec<_>>();
+        let cycles = rising.len().checked_sub(1).filter(|&cycles| cycles > 0)?;
+        let mean = (rising[cycles] - rising[0]) / cycles as f64;
+
+        let variance = rising
+            .windows(2)
+            .map(|pair| {
+                let delta = pair[1] - pair[0] - mean;
+                delta * delta
+            })
+            .sum::<f64>()
+            / cycles as f64;
+        let spread = variance.sqrt() / mean;
+
+        Some(FrequencyCount {
+            frequency: (1.0 / (mean * self.time_step)) as f32,
+            cycles,
+            confidence: ((1.0 - spread).max(0.0) * cycles as f64 / (cycles + 1) as f64) as f32,
+        })
+    }
+
+    fn duty_cycle(&self) -> Option<f32> {
+        Some(self.positive_width()? / self.period()?)
+    }
+
+    fn transition(&self, rising: bool) -> Option<f32> {
+        average(
+            self.edges
+                .iter()
+                .filter(|edge| edge.rising == rising)
+                .map(|edge| edge.transition()),
+        )
+        .map(|samples| (samples * self.time_step) as f32)
+    }
+
+    fn rise_time(&self) -> Option<f32> {
+        self.transition(true)
+    }
+
+    fn fall_time(&self) -> Option<f32> {
+        self.transition(false)
+    }
+
+    fn width(&self, rising: bool) -> Option<f32> {
+        average(
+            self.edges
+                .windows(2)
+                .filter(|pair| pair[0].rising == rising)
+                .map(|pair| pair[1].position - pair[0].position),
+        )
+        .map(|samples| (samples * self.time_step) as f32)
+    }
+
+    fn positive_width(&self) -> Option<f32> {
+        self.width(true)
+    }
+
+    fn negative_width(&self) -> Option<f32> {
+        self.width(false)
+    }
+}
+
+fn average(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let (count, sum) = values.fold((0usize, 0.0), |(count, sum), value| {
+        (count + 1, sum + value)
+    });
+
+    if count > 0 {
+        Some(sum / count as f64)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{parse, Channel, Unit};
+    use std::fs::read;
+
+    fn square(period: usize, high: usize, cycles: usize) -> Trace {
+        let samples = (0..period * cycles)
+            .map(|index| if index % period < high { 1.0 } else { -1.0 })
+            .collect();
+
+        Trace {
+            samples,
+            sample_rate_hz: 1.0e6,
+            time_offset: 0.0,
+            unit: Unit::V,
+        }
+    }
+
+    #[test]
+    fn square_wave() {
+        let m = Measurements::of(&square(100, 25, 10));
+
+        assert_eq!(m.vmax, Some(1.0));
+        assert_eq!(m.vmin, Some(-1.0));
+        assert_eq!(m.vpp, Some(2.0));
+        assert!((m.mean.unwrap() + 0.5).abs() < 1e-6);
+        assert!((m.rms.unwrap() - 1.0).abs() < 1e-6);
+        assert!((m.period.unwrap() - 100.0e-6).abs() < 1e-9);
+        assert!((m.frequency.unwrap() - 10.0e3).abs() < 1e-2);
+        assert!((m.duty_cycle.unwrap() - 0.25).abs() < 1e-6);
+        assert!((m.positive_width.unwrap() - 25.0e-6).abs() < 1e-9);
+        assert!((m.negative_width.unwrap() - 75.0e-6).abs() < 1e-9);
+        assert!((m.rise_time.unwrap() - 0.8e-6).abs() < 1e-9);
+        assert!((m.fall_time.unwrap() - 0.8e-6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn noisy_sine() {
+        // Noise stays within hysteresis band, crossing at record start is missed
+        let samples = (0..450)
+            .map(|index| {
+                let phase = 2.0 * core::f32::consts::PI * index as f32 / 100.0;
+                phase.sin() + if index % 2 == 0 { 0.2 } else { -0.2 }
+            })
+            .collect();
+        let count = counter(&Trace {
+            samples,
+            sample_rate_hz: 1.0e6,
+            time_offset: 0.0,
+            unit: Unit::V,
+        })
+        .unwrap();
+
+        assert_eq!(count.cycles, 3);
+        assert!((count.frequency - 10.0e3).abs() < 10.0);
+        assert!((count.confidence - 0.75).abs() < 0.01);
+        assert_eq!(counter(&square(100, 25, 1)), None);
+    }
+
+    #[test]
+    fn ds1052e_2ch() {
+        let i = read("test/ds1052e_2ch.wfm").unwrap();
+        let r = parse(&i).unwrap();
+        let t = r.trace(Channel::Ch2).unwrap();
+
+        assert!((vmax(&t).unwrap() - 5.2).abs() < 1e-3);
+        assert!((vmin(&t).unwrap() + 0.4).abs() < 1e-3);
+        assert!(frequency(&t).is_some());
+    }
+}
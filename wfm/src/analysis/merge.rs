@@ -0,0 +1,195 @@
+/*!
+
+Merge of captures from multiple instruments
+
+Captures triggered by the same event are aligned on the time grid of the
+first one, either by known skew or by cross-correlation of a channel which
+both instruments observe.
+
+*/
+use super::{
+    correlate::delay,
+    filter::Band,
+    resample::{resample_grid, Interpolation},
+};
+use crate::{Channel, Trace, WaveformData};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Way to align second capture
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Alignment {
+    /// Known skew in seconds
+    ///
+    /// Positive skew means events appear later in second capture.
+    Skew(f32),
+    /// Cross-correlate channels observing the same signal
+    Correlate {
+        first: Channel,
+        second: Channel,
+        /// Band limit applied before correlation
+        band: Option<Band>,
+    },
+}
+
+/// Trace of merged dataset
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MergedTrace {
+    /// Index of source capture
+    pub capture: usize,
+    pub channel: Channel,
+    pub trace: Trace,
+}
+
+/// Traces of captures on common time axis
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Merged {
+    /// Traces of both captures with equal sample rate, offset and length
+    pub traces: Vec<MergedTrace>,
+    /// Skew of second capture in seconds
+    pub skew: f32,
+    /// Peak correlation when aligned by correlation
+    pub correlation: Option<f32>,
+}
+
+/// Enabled channels of capture
+fn traces(data: &WaveformData) -> impl Iterator<Item = (Channel, Trace)> + '_ {
+    [Channel::Ch1, Channel::Ch2]
+        .iter()
+        .filter_map(move |&channel| Some((channel, data.trace(channel)?)))
+}
+
+/// Align and merge two captures
+///
+/// Common time axis uses sample rate of the first capture and covers the time
+/// range where all traces have samples. Returns `None` when captures can not
+/// be correlated or do not overlap.
+pub fn merge(first: &WaveformData, second: &WaveformData, alignment: Alignment) -> Option<Merged> {
+    let (skew, correlation) = match alignment {
+        Alignment::Skew(skew) => (skew, None),
+        Alignment::Correlate {
+            first: a,
+            second: b,
+            band,
+        } => {
+            let delay = delay(&first.trace(a)?, &second.trace(b)?, band)?;
+            (delay.lag_seconds as f32, Some(delay.correlation))
+        }
+    };
+
+    let mut sources = traces(first)
+        .map(|(channel, trace)| (0, channel, trace))
+        .collect::<Vec<_>>();
+    sources.extend(traces(second).map(|(channel, mut trace)| {
+        trace.time_offset -= skew;
+        (1, channel, trace)
+    }));
+
+    let sample_rate_hz = sources.first()?.2.sample_rate_hz;
+    let start = sources
+        .iter()
+        .map(|(_, _, trace)| trace.time_offset)
+        .fold(f32::NEG_INFINITY, f32::max);
+    let end = sources
+        .iter()
+        .map(|(_, _, trace)| trace.time(trace.len() - 1))
+        .fold(f32::INFINITY, f32::min);
+    if end < start {
+        return None;
+    }
+    let length = ((end - start) as f64 * sample_rate_hz as f64) as usize + 1;
+
+    Some(Merged {
+        traces: sources
+            .into_iter()
+            .map(|(capture, channel, trace)| MergedTrace {
+                capture,
+                channel,
+                trace: resample_grid(
+                    &trace,
+                    sample_rate_hz,
+                    start,
+                    length,
+                    Interpolation::default(),
+                ),
+            })
+            .collect(),
+        skew,
+        correlation,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{RawData, WaveformHeader};
+
+    fn capture(shift: f64, ch2: bool) -> WaveformData {
+        let pulse = |scale: f64| {
+            (0..1000)
+                .map(|index| {
+                    let x = (index as f64 - 400.0 - shift) / 20.0;
+                    (125.0 - scale * (-x * x).exp()) as u8
+                })
+                .collect::<Vec<_>>()
+        };
+        let mut builder = WaveformHeader::builder()
+            .channel(Channel::Ch1, 1.0, 0.0)
+            .sample_rate(1.0e6);
+        if ch2 {
+            builder = builder.channel(Channel::Ch2, 1.0, 0.0);
+        }
+        builder.build_with(RawData {
+            ch1: pulse(100.0).into(),
+            ch2: if ch2 {
+                pulse(50.0).into()
+            } else {
+                Default::default()
+            },
+            ..RawData::default()
+        })
+    }
+
+    #[test]
+    fn correlated() {
+        let first = capture(0.0, true);
+        let second = capture(25.0, false);
+        let m = merge(
+            &first,
+            &second,
+            Alignment::Correlate {
+                first: Channel::Ch1,
+                second: Channel::Ch1,
+                band: None,
+            },
+        )
+        .unwrap();
+
+        assert!((m.skew - 25.0e-6).abs() < 0.5e-6);
+        assert!(m.correlation.unwrap() > 0.99);
+        assert_eq!(m.traces.len(), 3);
+        assert_eq!(m.traces[2].capture, 1);
+        assert!(m
+            .traces
+            .iter()
+            .all(|t| t.trace.len() == m.traces[0].trace.len()));
+
+        // Pulses coincide on common axis
+        let peak = |trace: &Trace| {
+            (0..trace.len())
+                .max_by(|&a, &b| trace.samples[a].total_cmp(&trace.samples[b]))
+                .unwrap()
+        };
+        let a = peak(&m.traces[0].trace) as isize;
+        let b = peak(&m.traces[2].trace) as isize;
+        assert!((a - b).abs() <= 1);
+        // Overlap only
+        assert!(m.traces[0].trace.len() < 1000 - 20);
+
+        assert!(merge(&first, &second, Alignment::Skew(1.0)).is_none());
+    }
+}
@@ -0,0 +1,152 @@
+/*!
+
+Persistence display
+
+Captures are accumulated into time-voltage map like infinite persistence
+on instrument screen. Each cell counts captures which passed through it, so
+rarely hit cells show glitches and their rate.
+
+*/
+use super::xy::Density;
+use crate::{Channel, Trace, WaveformData};
+use core::ops::Range;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Number of horizontal divisions on screen
+const COLUMNS: f32 = 12.0;
+
+/// Number of vertical divisions on screen
+const ROWS: f32 = 8.0;
+
+/// Accumulated captures
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Persistence {
+    /// Captures per cell with x in seconds relative to trigger and y in units
+    pub density: Density,
+    /// Number of accumulated captures
+    pub captures: usize,
+}
+
+impl Persistence {
+    /// Empty map over time and level ranges
+    pub fn new(width: usize, height: usize, time: Range<f32>, level: Range<f32>) -> Self {
+        Self {
+            density: Density::new(width, height, time, level),
+            captures: 0,
+        }
+    }
+
+    /// Empty map covering screen of channel in capture
+    ///
+    /// Returns `None` when channel is disabled.
+    pub fn screen(
+        data: &WaveformData,
+        channel: Channel,
+        width: usize,
+        height: usize,
+    ) -> Option<Self> {
+        let header = match channel {
+            Channel::Ch1 => &data.header.ch1,
+            Channel::Ch2 => &data.header.ch2,
+        };
+        if !header.enabled {
+            return None;
+        }
+
+        let time = &data.header.time;
+        let half_width = 0.5 * COLUMNS * time.scale();
+        // Division is 25 ADC steps
+        let half_height = 0.5 * ROWS * 25.0 * header.volt_scale;
+        let center = header.center_volts();
+
+        Some(Self::new(
+            width,
+            height,
+            time.offset() - half_width..time.offset() + half_width,
+            center - half_height..center + half_height,
+        ))
+    }
+
+    /// Accumulate capture
+    ///
+    /// Cells between consecutive samples in column are filled like vectors
+    /// are drawn on screen, so fast edges do not leave gaps.
+    pub fn add(&mut self, trace: &Trace) {
+        let density = &self.density;
+        let mut hit = vec![false; density.counts.len()];
+        let mut previous: Option<(usize, usize)> = None;
+
+        for (index, &value) in trace.samples.iter().enumerate() {
+            let cell = density.cell(trace.time(index), value);
+            if let Some((column, row)) = cell {
+                let rows = match previous {
+                    Some((from, from_row)) if from == column || from + 1 == column => {
+                        from_row.min(row)..=from_row.max(row)
+                    }
+                    _ => row..=row,
+                };
+                for row in rows {
+                    hit[row * density.width + column] = true;
+                }
+            }
+            previous = cell;
+        }
+
+        for (count, hit) in self.density.counts.iter_mut().zip(hit) {
+            *count += hit as u32;
+        }
+        self.captures += 1;
+    }
+
+    /// Part of captures which passed through cell
+    pub fn rate(&self, column: usize, row: usize) -> f32 {
+        if self.captures == 0 {
+            0.0
+        } else {
+            self.density.count(column, row) as f32 / self.captures as f32
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{parse, Unit};
+    use std::fs::read;
+
+    #[test]
+    fn glitch() {
+        let trace = |glitch: bool| Trace {
+            samples: (0..100)
+                .map(|index| if glitch && index == 50 { 1.0 } else { 0.0 })
+                .collect(),
+            sample_rate_hz: 100.0,
+            time_offset: 0.0,
+            unit: Unit::V,
+        };
+        let mut p = Persistence::new(10, 4, 0.0..1.0, -1.0..1.0);
+        for capture in 0..10 {
+            p.add(&trace(capture == 0));
+        }
+
+        assert_eq!(p.captures, 10);
+        assert_eq!(p.rate(0, 2), 1.0);
+        assert_eq!(p.rate(5, 3), 0.1);
+        assert_eq!(p.rate(6, 3), 0.0);
+        assert_eq!(p.rate(0, 0), 0.0);
+    }
+
+    #[test]
+    fn ds1052e_2ch() {
+        let i = read("test/ds1052e_2ch.wfm").unwrap();
+        let r = parse(&i).unwrap();
+        let mut p = Persistence::screen(&r, Channel::Ch2, 60, 40).unwrap();
+        p.add(&r.trace(Channel::Ch2).unwrap());
+
+        assert_eq!(p.density.max(), 1);
+        assert!(p.density.counts.contains(&0));
+    }
+}
@@ -0,0 +1,227 @@
+/*!
+
+Pulse parameters
+
+Measurements follow IEEE 181: base and top state levels are modes of lower
+and upper halves of amplitude histogram, so overshoot and ringing do not
+move them like minimum and maximum do. Aberrations are given in percents
+of amplitude between state levels.
+
+*/
+use super::{
+    edge::{self, Levels},
+    histogram::histogram,
+};
+use crate::Trace;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Number of histogram bins for state levels
+const BINS: usize = 100;
+
+/// Base and top state levels
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StateLevels {
+    pub base: f32,
+    pub top: f32,
+}
+
+impl StateLevels {
+    /// Difference between top and base
+    pub fn amplitude(&self) -> f32 {
+        self.top - self.base
+    }
+
+    /// Level at given percent of amplitude above base
+    pub fn reference(&self, percent: f32) -> f32 {
+        self.base + self.amplitude() * percent / 100.0
+    }
+}
+
+/// Transition between states
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Transition {
+    pub rising: bool,
+    /// Time of middle level crossing in seconds relative to trigger
+    pub time: f64,
+    /// Duration between 10% and 90% levels in seconds
+    pub duration: f32,
+    /// Excursion beyond final state after transition in percents
+    pub overshoot: f32,
+    /// Excursion beyond initial state before transition in percents
+    pub preshoot: f32,
+    /// Time from middle level crossing until signal stays within tolerance
+    /// band around final state, `None` when it does not settle before next
+    /// transition
+    pub settling_time: Option<f32>,
+}
+
+/// Pulse parameters of trace
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Pulse {
+    pub levels: StateLevels,
+    pub transitions: Vec<Transition>,
+}
+
+/// Estimate state levels from amplitude histogram
+pub fn state_levels(trace: &Trace) -> Option<StateLevels> {
+    let histogram = histogram(trace, BINS)?;
+    if histogram.max <= histogram.min {
+        return None;
+    }
+
+    // Mean of samples in most populated bin is not quantized to bins
+    let width = histogram.bin_width();
+    let mode = |bins: core::ops::Range<usize>| {
+        let bin = bins.max_by_key(|&bin| histogram.counts[bin])?;
+        let (count, sum) = trace
+            .samples
+            .iter()
+            .filter(|&&value| (((value - histogram.min) / width) as usize).min(BINS - 1) == bin)
+            .fold((0usize, 0.0f64), |(count, sum), &value| {
+                (count + 1, sum + value as f64)
+            });
+        Some(if count > 0 {
+            (sum / count as f64) as f32
+        } else {
+            histogram.bin_center(bin)
+        })
+    };
+    Some(StateLevels {
+        base: mode(0..BINS / 2)?,
+        top: mode(BINS / 2..BINS)?,
+    })
+}
+
+/// Measure pulse parameters with settling tolerance in percents of amplitude
+pub fn pulse(trace: &Trace, tolerance: f32) -> Option<Pulse> {
+    let levels = state_levels(trace)?;
+    let amplitude = levels.amplitude();
+    let time_step = trace.time_step();
+    let samples = &trace.samples;
+
+    let edges = edge::find(
+        trace,
+        Levels {
+            low: levels.reference(10.0),
+            threshold: levels.reference(50.0),
+            high: levels.reference(90.0),
+        },
+    );
+
+    let transitions = edges
+        .iter()
+        .enumerate()
+        .map(|(index, edge)| {
+            // Aberrations are searched up to neighbouring transitions
+            let before = index
+                .checked_sub(1)
+                .map_or(0, |index| edges[index].end.ceil() as usize);
+            let after = edges
+                .get(index + 1)
+                .map_or(samples.len(), |next| next.start.floor() as usize + 1);
+            let start = (edge.start.floor() as usize + 1).max(before);
+            let end = (edge.end.ceil() as usize).min(after);
+
+            let (initial, last) = if edge.rising {
+                (levels.base, levels.top)
+            } else {
+                (levels.top, levels.base)
+            };
+            // Excursion in direction of transition
+            let excursion = |from: usize, to: usize, level: f32, sign: f32| {
+                samples[from..to]
+                    .iter()
+                    .map(|&value| sign * (value - level))
+                    .fold(0.0f32, f32::max)
+                    / amplitude
+                    * 100.0
+            };
+            let sign = if edge.rising { 1.0 } else { -1.0 };
+
+            let band = 0.01 * tolerance.abs() * amplitude;
+            let from = (edge.position.ceil() as usize).min(after);
+            let settled = match samples[from..after]
+                .iter()
+                .rposition(|&value| (value - last).abs() > band)
+            {
+                Some(outside) if from + outside + 1 == after => None,
+                Some(outside) => Some(from + outside + 1),
+                None => Some(from),
+            };
+
+            Transition {
+                rising: edge.rising,
+                time: edge.time,
+                duration: edge.transition() as f32 * time_step,
+                overshoot: excursion(end, after, last, sign),
+                preshoot: excursion(before, start, initial, -sign),
+                settling_time: settled
+                    .map(|settled| (settled as f64 - edge.position) as f32 * time_step),
+            }
+        })
+        .collect();
+
+    Some(Pulse {
+        levels,
+        transitions,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Unit;
+
+    #[test]
+    fn ringing_step() {
+        // Step from 0 to 1 with decaying ringing after it
+        let samples = (0..1000)
+            .map(|index| {
+                if index < 300 {
+                    if index == 290 {
+                        -0.05
+                    } else {
+                        0.0
+                    }
+                } else {
+                    let t = (index - 300) as f32;
+                    1.0 + 0.2 * (-t / 20.0).exp() * (t / 5.0).cos()
+                }
+            })
+            .collect();
+        let trace = Trace {
+            samples,
+            sample_rate_hz: 1.0e6,
+            time_offset: 0.0,
+            unit: Unit::V,
+        };
+        let pulse = pulse(&trace, 2.0).unwrap();
+
+        assert!(pulse.levels.base.abs() < 1.0e-3);
+        assert!((pulse.levels.top - 1.0).abs() < 1.0e-3);
+        assert_eq!(pulse.transitions.len(), 1);
+
+        let step = pulse.transitions[0];
+        assert!(step.rising);
+        assert!((step.overshoot - 20.0).abs() < 0.5);
+        assert!((step.preshoot - 5.0).abs() < 0.5);
+        // Last ringing peak beyond 2% is at about 31 samples after step
+        let settling = step.settling_time.unwrap();
+        assert!(settling > 31.0e-6 && settling < 40.0e-6);
+
+        // Same aberrations on falling step
+        let inverted = Trace {
+            samples: trace.samples.iter().map(|value| -value).collect(),
+            ..trace
+        };
+        let fall = super::pulse(&inverted, 2.0).unwrap().transitions[0];
+        assert!(!fall.rising);
+        assert!((fall.overshoot - step.overshoot).abs() < 0.1);
+        assert!((fall.preshoot - step.preshoot).abs() < 0.1);
+    }
+}
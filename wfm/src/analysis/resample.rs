@@ -0,0 +1,179 @@
+/*!
+
+Resampling and interpolation
+
+*/
+use crate::Trace;
+use core::f64::consts::PI;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Interpolation method
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Interpolation {
+    Linear,
+    /// Hann-windowed sinc with given half width in samples
+    Sinc(usize),
+}
+
+impl Default for Interpolation {
+    fn default() -> Self {
+        Interpolation::Sinc(16)
+    }
+}
+
+/// Resample trace to new sample rate
+///
+/// First sample time is kept, number of samples is chosen to cover the
+/// same time range. Sinc interpolation limits bandwidth when decimating.
+pub fn resample(trace: &Trace, sample_rate_hz: f32, interpolation: Interpolation) -> Trace {
+    let length = if trace.is_empty() {
+        0
+    } else {
+        ((trace.len() - 1) as f64 * sample_rate_hz as f64 / trace.sample_rate_hz as f64) as usize
+            + 1
+    };
+
+    resample_grid(
+        trace,
+        sample_rate_hz,
+        trace.time_offset,
+        length,
+        interpolation,
+    )
+}
+
+/// Resample trace on time grid of reference trace
+///
+/// Samples out of trace time range are set to the nearest edge values.
+pub fn resample_like(trace: &Trace, reference: &Trace, interpolation: Interpolation) -> Trace {
+    resample_grid(
+        trace,
+        reference.sample_rate_hz,
+        reference.time_offset,
+        reference.len(),
+        interpolation,
+    )
+}
+
+/// Resample trace on arbitrary uniform time grid
+pub fn resample_grid(
+    trace: &Trace,
+    sample_rate_hz: f32,
+    time_offset: f32,
+    length: usize,
+    interpolation: Interpolation,
+) -> Trace {
+    let ratio = trace.sample_rate_hz as f64 / sample_rate_hz as f64;
+    let start = (time_offset as f64 - trace.time_offset as f64) * trace.sample_rate_hz as f64;
+
+    Trace {
+        samples: (0..length)
+            .map(|index| interpolate(trace, start + index as f64 * ratio, interpolation, ratio))
+            .collect(),
+        sample_rate_hz,
+        time_offset,
+        unit: trace.unit,
+    }
+}
+
+/// Interpolated value at fractional sample position
+///
+/// Step is the distance between output samples in input samples, used to
+/// limit sinc bandwidth.
+fn interpolate(trace: &Trace, position: f64, interpolation: Interpolation, step: f64) -> f32 {
+    let samples = &trace.samples;
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let last = samples.len() - 1;
+    let position = position.clamp(0.0, last as f64);
+
+    match interpolation {
+        Interpolation::Linear => {
+            let index = (position as usize).min(last.saturating_sub(1));
+            let fraction = position - index as f64;
+            let from = samples[index] as f64;
+            let to = samples[(index + 1).min(last)] as f64;
+            (from + (to - from) * fraction) as f32
+        }
+        Interpolation::Sinc(width) => {
+            // Widen kernel when decimating to suppress aliasing
+            let scale = step.max(1.0);
+            let half = width.max(1) as f64 * scale;
+            let from = (position - half).ceil().max(0.0) as usize;
+            let to = ((position + half).floor() as usize).min(last);
+
+            let (sum, weights) = (from..=to).fold((0.0, 0.0), |(sum, weights), index| {
+                let offset = (index as f64 - position) / scale;
+                let sinc = if offset == 0.0 {
+                    1.0
+                } else {
+                    (PI * offset).sin() / (PI * offset)
+                };
+                let window = 0.5 + 0.5 * (PI * offset * scale / half).cos();
+                let weight = sinc * window;
+                (sum + weight * samples[index] as f64, weights + weight)
+            });
+
+            // Normalize weights to keep DC level near edges
+            (sum / weights) as f32
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Unit;
+
+    fn sine(sample_rate_hz: f32, length: usize) -> Trace {
+        Trace {
+            samples: (0..length)
+                .map(|index| (2.0 * PI * 10.0 * index as f64 / sample_rate_hz as f64).sin() as f32)
+                .collect(),
+            sample_rate_hz,
+            time_offset: -0.5,
+            unit: Unit::V,
+        }
+    }
+
+    #[test]
+    fn upsample() {
+        let trace = sine(100.0, 101);
+        let exact = sine(250.0, 251);
+
+        for interpolation in [Interpolation::Linear, Interpolation::default()].iter() {
+            let resampled = resample(&trace, 250.0, *interpolation);
+
+            assert_eq!(resampled.len(), 251);
+            assert_eq!(resampled.time_offset, -0.5);
+            assert_eq!(resampled.sample_rate_hz, 250.0);
+
+            let error = resampled.samples[20..230]
+                .iter()
+                .zip(&exact.samples[20..230])
+                .map(|(a, b)| (a - b).abs())
+                .fold(0.0f32, f32::max);
+            let tolerance = if *interpolation == Interpolation::Linear {
+                0.05
+            } else {
+                5e-3
+            };
+            assert!(error < tolerance);
+        }
+    }
+
+    #[test]
+    fn align() {
+        let trace = sine(100.0, 101);
+        let mut reference = sine(100.0, 50);
+        reference.time_offset = -0.25;
+
+        let aligned = resample_like(&trace, &reference, Interpolation::Linear);
+        assert_eq!(aligned.len(), 50);
+        assert!((aligned.samples[0] - trace.samples[25]).abs() < 1e-5);
+    }
+}
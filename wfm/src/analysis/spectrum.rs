@@ -0,0 +1,263 @@
+/*!
+
+Frequency spectrum
+
+*/
+use super::Gate;
+use crate::Trace;
+use core::f64::consts::PI;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Window function
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Window {
+    Rectangular,
+    Hann,
+    BlackmanHarris,
+}
+
+impl Window {
+    /// Window value at sample index
+    pub fn value(&self, index: usize, length: usize) -> f64 {
+        let phase = 2.0 * PI * index as f64 / length as f64;
+
+        match self {
+            Window::Rectangular => 1.0,
+            Window::Hann => 0.5 - 0.5 * phase.cos(),
+            Window::BlackmanHarris => {
+                0.35875 - 0.48829 * phase.cos() + 0.14128 * (2.0 * phase).cos()
+                    - 0.01168 * (3.0 * phase).cos()
+            }
+        }
+    }
+
+    /// Half width of main lobe in bins
+    pub fn lobe(&self) -> usize {
+        match self {
+            Window::Rectangular => 1,
+            Window::Hann => 2,
+            Window::BlackmanHarris => 4,
+        }
+    }
+}
+
+/// Single-sided power spectrum
+///
+/// Bin powers are normalized so that their sum is equal to the mean square
+/// value of the signal, so power of any band can be obtained by summation.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Spectrum {
+    /// Power of bins in squared units
+    pub power: Vec<f32>,
+    /// Frequency step between bins in Hz
+    pub bin_width_hz: f32,
+    pub window: Window,
+}
+
+impl Spectrum {
+    /// Compute spectrum of trace
+    ///
+    /// Windowed samples are padded with zeros to power of two length, window
+    /// gain is taken over samples only so bin powers keep normalization.
+    /// Returns `None` when trace has less than two samples.
+    pub fn of(trace: &Trace, window: Window) -> Option<Self> {
+        let samples = trace.len();
+        if samples < 2 {
+            return None;
+        }
+
+        let length = samples.next_power_of_two();
+
+        let mut gain = 0.0;
+        let mut data = trace
+            .samples
+            .iter()
+            .enumerate()
+            .map(|(index, &value)| {
+                let weight = window.value(index, samples);
+                gain += weight * weight;
+                Complex(value as f64 * weight, 0.0)
+            })
+            .chain(core::iter::repeat(Complex(0.0, 0.0)))
+            .take(length)
+            .collect::<Vec<_>>();
+
+        fft(&mut data);
+
+        let scale = 1.0 / (length as f64 * gain);
+        let power = data[..=length / 2]
+            .iter()
+            .enumerate()
+            .map(|(bin, value)| {
+                let power = value.norm_sqr() * scale;
+                // Fold negative frequencies except DC and Nyquist bins
+                if bin == 0 || bin == length / 2 {
+                    power as f32
+                } else {
+                    (2.0 * power) as f32
+                }
+            })
+            .collect();
+
+        Some(Self {
+            power,
+            bin_width_hz: trace.sample_rate_hz / length as f32,
+            window,
+        })
+    }
+
+    /// Compute spectrum of part of trace within gate
+    pub fn gated(trace: &Trace, gate: &Gate, window: Window) -> Option<Self> {
+        Self::of(&trace.gated(gate), window)
+    }
+
+    /// Number of bins
+    pub fn len(&self) -> usize {
+        self.power.len()
+    }
+
+    /// Spectrum has no bins
+    pub fn is_empty(&self) -> bool {
+        self.power.is_empty()
+    }
+
+    /// Frequency of bin in Hz
+    pub fn frequency(&self, bin: usize) -> f32 {
+        bin as f32 * self.bin_width_hz
+    }
+
+    /// Nearest bin for frequency
+    pub fn bin(&self, frequency_hz: f32) -> usize {
+        (frequency_hz / self.bin_width_hz).round() as usize
+    }
+
+    /// Bin power in decibels relative to squared unit
+    pub fn db(&self, bin: usize) -> f32 {
+        10.0 * self.power[bin].log10()
+    }
+
+    /// Total power of bins range
+    pub fn band_power(&self, bins: core::ops::Range<usize>) -> f64 {
+        let end = bins.end.min(self.power.len());
+        let start = bins.start.min(end);
+
+        self.power[start..end]
+            .iter()
+            .map(|&power| power as f64)
+            .sum()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Complex(pub f64, pub f64);
+
+impl Complex {
+    pub fn norm_sqr(&self) -> f64 {
+        self.0 * self.0 + self.1 * self.1
+    }
+
+    pub fn conj(&self) -> Self {
+        Complex(self.0, -self.1)
+    }
+
+    pub fn mul(&self, other: &Self) -> Self {
+        Complex(
+            self.0 * other.0 - self.1 * other.1,
+            self.0 * other.1 + self.1 * other.0,
+        )
+    }
+}
+
+/// In-place inverse FFT (without normalization)
+pub(crate) fn ifft(data: &mut [Complex]) {
+    data.iter_mut().for_each(|value| *value = value.conj());
+    fft(data);
+    data.iter_mut().for_each(|value| *value = value.conj());
+}
+
+/// In-place iterative radix-2 FFT
+pub(crate) fn fft(data: &mut [Complex]) {
+    let length = data.len();
+
+    // Bit-reversal permutation
+    let mut target = 0;
+    for index in 0..length {
+        if index < target {
+            data.swap(index, target);
+        }
+        let mut mask = length >> 1;
+        while mask > 0 && target & mask != 0 {
+            target &= !mask;
+            mask >>= 1;
+        }
+        target |= mask;
+    }
+
+    let mut size = 2;
+    while size <= length {
+        let angle = -2.0 * PI / size as f64;
+        let step = Complex(angle.cos(), angle.sin());
+
+        for chunk in data.chunks_mut(size) {
+            let mut twiddle = Complex(1.0, 0.0);
+            let (even, odd) = chunk.split_at_mut(size / 2);
+
+            for (even, odd) in even.iter_mut().zip(odd.iter_mut()) {
+                let product = odd.mul(&twiddle);
+                *odd = Complex(even.0 - product.0, even.1 - product.1);
+                *even = Complex(even.0 + product.0, even.1 + product.1);
+                twiddle = twiddle.mul(&step);
+            }
+        }
+
+        size <<= 1;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Unit;
+
+    #[test]
+    fn sine_power() {
+        let trace = Trace {
+            samples: (0..1024)
+                .map(|index| (2.0 * PI * 64.0 * index as f64 / 1024.0).sin() as f32 * 2.0)
+                .collect(),
+            sample_rate_hz: 1024.0,
+            time_offset: 0.0,
+            unit: Unit::V,
+        };
+        let spectrum = Spectrum::of(&trace, Window::Hann).unwrap();
+
+        assert_eq!(spectrum.len(), 513);
+        assert_eq!(spectrum.bin(64.0), 64);
+        // Mean square of sine with amplitude 2 is 2
+        assert!((spectrum.band_power(62..67) - 2.0).abs() < 1e-3);
+        assert!(spectrum.band_power(0..60) < 1e-6);
+    }
+
+    #[test]
+    fn padded() {
+        let trace = Trace {
+            samples: (0..1000)
+                .map(|index| (2.0 * PI * 100.0 * index as f64 / 1000.0).sin() as f32 * 2.0)
+                .collect(),
+            sample_rate_hz: 1000.0,
+            time_offset: 0.0,
+            unit: Unit::V,
+        };
+        let spectrum = Spectrum::of(&trace, Window::Hann).unwrap();
+
+        assert_eq!(spectrum.len(), 513);
+        assert_eq!(spectrum.bin_width_hz, 1000.0 / 1024.0);
+        let bin = spectrum.bin(100.0);
+        assert!((spectrum.band_power(bin - 4..bin + 5) - 2.0).abs() < 1e-2);
+        assert!((spectrum.band_power(0..spectrum.len()) - 2.0).abs() < 1e-2);
+    }
+}
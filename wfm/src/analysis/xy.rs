@@ -0,0 +1,142 @@
+/*!
+
+XY mode data
+
+*/
+use crate::{Channel, Trace, WaveformData};
+use core::ops::Range;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Pair values of two traces as points
+///
+/// Every n-th sample is taken when decimation is greater than one.
+pub fn points(x: &Trace, y: &Trace, decimation: usize) -> Vec<(f32, f32)> {
+    x.samples
+        .iter()
+        .zip(y.samples.iter())
+        .step_by(decimation.max(1))
+        .map(|(&x, &y)| (x, y))
+        .collect()
+}
+
+impl WaveformData {
+    /// Points of first channel against second one
+    pub fn xy(&self, decimation: usize) -> Option<Vec<(f32, f32)>> {
+        Some(points(
+            &self.trace(Channel::Ch1)?,
+            &self.trace(Channel::Ch2)?,
+            decimation,
+        ))
+    }
+}
+
+/// Two-dimensional points density map
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Density {
+    pub width: usize,
+    pub height: usize,
+    pub x_range: Range<f32>,
+    pub y_range: Range<f32>,
+    /// Counts of points in cells row by row starting from lowest y
+    pub counts: Vec<u32>,
+}
+
+impl Density {
+    /// Empty map
+    pub fn new(width: usize, height: usize, x_range: Range<f32>, y_range: Range<f32>) -> Self {
+        Self {
+            width,
+            height,
+            x_range,
+            y_range,
+            counts: vec![0; width * height],
+        }
+    }
+
+    /// Cell of point, `None` when it is out of ranges or map is empty
+    pub fn cell(&self, x: f32, y: f32) -> Option<(usize, usize)> {
+        let position = |value: f32, range: &Range<f32>, size: usize| {
+            let ratio = (value - range.start) / (range.end - range.start);
+            if size == 0 {
+                None
+            } else if (0.0..1.0).contains(&ratio) {
+                Some((ratio * size as f32) as usize)
+            } else if ratio == 1.0 {
+                Some(size - 1)
+            } else {
+                None
+            }
+        };
+
+        Some((
+            position(x, &self.x_range, self.width)?,
+            position(y, &self.y_range, self.height)?,
+        ))
+    }
+
+    /// Accumulate points, points out of ranges are ignored
+    pub fn add(&mut self, points: &[(f32, f32)]) {
+        for &(x, y) in points {
+            if let Some((column, row)) = self.cell(x, y) {
+                self.counts[row * self.width + column] += 1;
+            }
+        }
+    }
+
+    /// Count of points in cell
+    pub fn count(&self, column: usize, row: usize) -> u32 {
+        self.counts[row * self.width + column]
+    }
+
+    /// Maximum count of cells
+    pub fn max(&self) -> u32 {
+        self.counts.iter().copied().max().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{parse, Unit};
+    use std::fs::read;
+
+    #[test]
+    fn ds1052e_2ch() {
+        let i = read("test/ds1052e_2ch.wfm").unwrap();
+        let r = parse(&i).unwrap();
+        let p = r.xy(10).unwrap();
+
+        assert_eq!(p.len(), r.data.ch1.len().div_ceil(10));
+
+        let mut d = Density::new(8, 8, -0.5..7.5, -0.5..7.5);
+        d.add(&p);
+        assert_eq!(d.counts.iter().sum::<u32>() as usize, p.len());
+        // Most of time both signals are high
+        assert_eq!(d.max(), d.count(5, 5));
+
+        let mut empty = Density::new(0, 8, -0.5..7.5, -0.5..7.5);
+        empty.add(&p);
+        assert_eq!(empty.cell(0.0, 0.0), None);
+        assert_eq!(empty.max(), 0);
+    }
+
+    #[test]
+    fn decimation() {
+        let trace = |samples| Trace {
+            samples,
+            sample_rate_hz: 1.0,
+            time_offset: 0.0,
+            unit: Unit::V,
+        };
+        let p = points(
+            &trace(vec![0.0, 1.0, 2.0, 3.0]),
+            &trace(vec![4.0, 5.0, 6.0, 7.0]),
+            2,
+        );
+
+        assert_eq!(p, vec![(0.0, 4.0), (2.0, 6.0)]);
+    }
+}
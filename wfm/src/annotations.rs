@@ -0,0 +1,132 @@
+/*!
+
+Cursors, regions and notes attached to waveform
+
+Annotations are stored in [`Archive`](crate::schema::Archive) and JSON
+exports, so analysis results can travel with the capture. Times are in
+seconds relative to trigger like in [`Trace`].
+
+*/
+use super::{Channel, Trace};
+use core::ops::Range;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Annotation of waveform
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum Annotation {
+    /// Vertical cursor at time
+    TimeCursor { time: f32, label: String },
+    /// Horizontal cursor at level of channel in units
+    LevelCursor {
+        channel: Channel,
+        level: f32,
+        label: String,
+    },
+    /// Labeled time range
+    Region { start: f32, end: f32, label: String },
+    /// Text note optionally anchored at time
+    Note { time: Option<f32>, text: String },
+}
+
+impl Annotation {
+    /// Time cursor
+    pub fn time_cursor(time: f32, label: impl Into<String>) -> Self {
+        Annotation::TimeCursor {
+            time,
+            label: label.into(),
+        }
+    }
+
+    /// Level cursor
+    pub fn level_cursor(channel: Channel, level: f32, label: impl Into<String>) -> Self {
+        Annotation::LevelCursor {
+            channel,
+            level,
+            label: label.into(),
+        }
+    }
+
+    /// Region covering samples of trace
+    pub fn region(trace: &Trace, samples: Range<usize>, label: impl Into<String>) -> Self {
+        Annotation::Region {
+            start: trace.time(samples.start),
+            end: trace.time(samples.end),
+            label: label.into(),
+        }
+    }
+
+    /// Note
+    pub fn note(time: Option<f32>, text: impl Into<String>) -> Self {
+        Annotation::Note {
+            time,
+            text: text.into(),
+        }
+    }
+
+    /// Label or text
+    pub fn label(&self) -> &str {
+        match self {
+            Annotation::TimeCursor { label, .. }
+            | Annotation::LevelCursor { label, .. }
+            | Annotation::Region { label, .. } => label,
+            Annotation::Note { text, .. } => text,
+        }
+    }
+
+    /// Time span, `None` for annotations not bound to time
+    pub fn time_span(&self) -> Option<(f32, f32)> {
+        match *self {
+            Annotation::TimeCursor { time, .. } => Some((time, time)),
+            Annotation::Region { start, end, .. } => Some((start.min(end), start.max(end))),
+            Annotation::Note { time, .. } => time.map(|time| (time, time)),
+            Annotation::LevelCursor { .. } => None,
+        }
+    }
+
+    /// Annotation is visible in time window
+    ///
+    /// Annotations not bound to time are always visible.
+    pub fn is_visible(&self, start: f32, end: f32) -> bool {
+        self.time_span()
+            .is_none_or(|(from, to)| from <= end && to >= start)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse;
+    use std::fs::read;
+
+    #[test]
+    fn ds1052e_2ch() {
+        let i = read("test/ds1052e_2ch.wfm").unwrap();
+        let r = parse(&i).unwrap();
+        let t = r.trace(Channel::Ch1).unwrap();
+
+        let a = Annotation::region(&t, 100..200, "burst");
+        let (start, end) = a.time_span().unwrap();
+        assert_eq!(start, t.time(100));
+        assert!((end - start - 100.0 * t.time_step()).abs() < 1.0e-9);
+        assert!(a.is_visible(t.time(150), t.time(1000)));
+        assert!(!a.is_visible(t.time(300), t.time(1000)));
+        assert!(Annotation::level_cursor(Channel::Ch1, 2.5, "threshold").is_visible(0.0, 0.0));
+
+        #[cfg(feature = "json")]
+        {
+            let mut archive = r.to_archive();
+            archive.annotations = vec![a, Annotation::note(None, "first capture")];
+            let json = serde_json::to_string(&archive).unwrap();
+            assert!(json.contains(r#"{"kind":"note","time":null,"text":"first capture"}"#));
+            let loaded: crate::schema::Archive = serde_json::from_str(&json).unwrap();
+            assert_eq!(loaded, archive);
+            assert!(!serde_json::to_string(&r.to_archive())
+                .unwrap()
+                .contains("annotations"));
+        }
+    }
+}
@@ -0,0 +1,96 @@
+/*!
+
+Batch processing of files
+
+*/
+use crate::{import, parse, Error, Result, WaveformData};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+mod watch;
+
+pub use watch::*;
+
+/// Extension of waveform files
+pub const EXTENSION: &str = "wfm";
+
+/// Read and parse file
+///
+/// Files with `csv` extension are imported as columns of time and values.
+pub fn parse_file(path: impl AsRef<Path>) -> Result<WaveformData> {
+    let path = path.as_ref();
+    let input = fs::read(path).map_err(|error| Error::Io(error.kind()))?;
+    if path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("csv"))
+    {
+        let text = String::from_utf8(input).map_err(|error| Error::Invalid {
+            context: "UTF-8 text",
+            offset: error.utf8_error().valid_up_to(),
+        })?;
+        return import::from_csv(&text);
+    }
+    parse(&input)
+}
+
+/// Parse files, concurrently when `rayon` feature is enabled
+///
+/// Results are in order of paths.
+pub fn parse_files(paths: &[PathBuf]) -> Vec<Result<WaveformData>> {
+    #[cfg(feature = "rayon")]
+    {
+        paths.par_iter().map(parse_file).collect()
+    }
+    #[cfg(not(feature = "rayon"))]
+    {
+        paths.iter().map(parse_file).collect()
+    }
+}
+
+/// Waveform files in directory sorted by name
+pub fn waveform_files(dir: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
+    let mut paths = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+
+    paths.retain(|path| {
+        path.is_file()
+            && path
+                .extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case(EXTENSION))
+    });
+    paths.sort();
+
+    Ok(paths)
+}
+
+/// Parse all waveform files in directory
+pub fn parse_dir(dir: impl AsRef<Path>) -> io::Result<Vec<(PathBuf, Result<WaveformData>)>> {
+    let paths = waveform_files(dir)?;
+    let results = parse_files(&paths);
+
+    Ok(paths.into_iter().zip(results).collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_dir() {
+        let r = parse_dir("test").unwrap();
+
+        assert_eq!(r.len(), 1);
+        assert_eq!(r[0].0, Path::new("test/ds1052e_2ch.wfm"));
+        assert_eq!(r[0].1.as_ref().unwrap().header.ch1_points, 524284);
+        assert_eq!(
+            parse_file("test/missing.wfm").unwrap_err(),
+            Error::Io(io::ErrorKind::NotFound)
+        );
+    }
+}
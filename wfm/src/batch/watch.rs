@@ -0,0 +1,256 @@
+/*!
+
+Directory watching
+
+New waveform files appearing in directory are converted and recorded in
+index file, so restarted watcher does not convert them again.
+
+*/
+use super::{parse_file, waveform_files};
+use crate::export::Format;
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, UNIX_EPOCH},
+};
+
+/// Name of index file in output directory
+pub const INDEX: &str = "index.tsv";
+
+/// Indexed waveform file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// File name in watched directory
+    pub name: String,
+    /// File size in bytes
+    pub size: u64,
+    /// Modification time in seconds since Unix epoch
+    pub modified: u64,
+    /// Name of converted file or conversion error
+    pub result: Result<String, String>,
+}
+
+impl Entry {
+    fn line(&self) -> String {
+        let (status, value) = match &self.result {
+            Ok(output) => ("ok", output.as_str()),
+            Err(error) => ("error", error.as_str()),
+        };
+        // Keep one entry per line
+        let value = value.replace(['\t', '\n'], " ");
+        format!(
+            "{}\t{}\t{}\t{}\t{}",
+            self.name, self.size, self.modified, status, value
+        )
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.splitn(5, '\t');
+        let name = fields.next()?.into();
+        let size = fields.next()?.parse().ok()?;
+        let modified = fields.next()?.parse().ok()?;
+        let result = match (fields.next()?, fields.next()?.into()) {
+            ("ok", output) => Ok(output),
+            ("error", error) => Err(error),
+            _ => return None,
+        };
+        Some(Self {
+            name,
+            size,
+            modified,
+            result,
+        })
+    }
+}
+
+/// Size and modification time of file
+fn stamp(path: &Path) -> io::Result<(u64, u64)> {
+    let metadata = fs::metadata(path)?;
+    let modified = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    Ok((metadata.len(), modified))
+}
+
+/// Converter of new waveform files in directory
+///
+/// File is converted once it did not change between two scans, so files
+/// which are still being copied are not picked up.
+#[derive(Debug)]
+pub struct Watcher {
+    input: PathBuf,
+    output: PathBuf,
+    format: Format,
+    entries: Vec<Entry>,
+    pending: HashMap<String, (u64, u64)>,
+}
+
+impl Watcher {
+    /// Watch input directory converting files to output directory
+    ///
+    /// Existing index in output directory is loaded.
+    pub fn new(
+        input: impl Into<PathBuf>,
+        output: impl Into<PathBuf>,
+        format: Format,
+    ) -> io::Result<Self> {
+        let output = output.into();
+        fs::create_dir_all(&output)?;
+
+        let entries = match fs::read_to_string(output.join(INDEX)) {
+            Ok(index) => index.lines().filter_map(Entry::parse).collect(),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(error) => return Err(error),
+        };
+
+        Ok(Self {
+            input: input.into(),
+            output,
+            format,
+            entries,
+            pending: HashMap::new(),
+        })
+    }
+
+    /// Indexed files
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Convert new and changed files, returns their entries
+    pub fn scan(&mut self) -> io::Result<Vec<Entry>> {
+        let mut converted = Vec::new();
+
+        for path in waveform_files(&self.input)? {
+            let name = match path.file_name().and_then(|name| name.to_str()) {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+            let (size, modified) = match stamp(&path) {
+                Ok(stamp) => stamp,
+                // Removed meanwhile
+                Err(_) => continue,
+            };
+
+            let indexed = self.entries.iter().any(|entry| {
+                entry.name == name && entry.size == size && entry.modified == modified
+            });
+            if indexed {
+                continue;
+            }
+            if self.pending.insert(name.clone(), (size, modified)) != Some((size, modified)) {
+                continue;
+            }
+            self.pending.remove(&name);
+
+            let entry = Entry {
+                result: self.convert(&path, &name),
+                name,
+                size,
+                modified,
+            };
+            self.entries.retain(|indexed| indexed.name != entry.name);
+            self.entries.push(entry.clone());
+            converted.push(entry);
+        }
+
+        if !converted.is_empty() {
+            self.write_index()?;
+        }
+        Ok(converted)
+    }
+
+    /// Scan directory with interval until error
+    pub fn watch(
+        &mut self,
+        interval: Duration,
+        mut callback: impl FnMut(&Entry),
+    ) -> io::Result<()> {
+        loop {
+            for entry in self.scan()? {
+                callback(&entry);
+            }
+            thread::sleep(interval);
+        }
+    }
+
+    fn convert(&self, path: &Path, name: &str) -> Result<String, String> {
+        let data = parse_file(path).map_err(|error| error.to_string())?;
+        let output = Path::new(name)
+            .with_extension(self.format.extension())
+            .display()
+            .to_string();
+
+        // Readers of output never see partially written file
+        let temporary = self.output.join(format!(".{}.tmp", output));
+        let write = || -> io::Result<()> {
+            let mut writer = BufWriter::new(fs::File::create(&temporary)?);
+            self.format.write(&data, &mut writer)?;
+            writer.flush()?;
+            fs::rename(&temporary, self.output.join(&output))
+        };
+        write().map_err(|error| error.to_string())?;
+
+        Ok(output)
+    }
+
+    fn write_index(&self) -> io::Result<()> {
+        let temporary = self.output.join(format!(".{}.tmp", INDEX));
+        let mut writer = BufWriter::new(fs::File::create(&temporary)?);
+        for entry in &self.entries {
+            writeln!(writer, "{}", entry.line())?;
+        }
+        writer.flush()?;
+        fs::rename(temporary, self.output.join(INDEX))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{write, Channel, RawData, WaveformHeader};
+
+    #[test]
+    fn convert_new() {
+        let dir = std::env::temp_dir().join(format!("rigol-wfm-watch-{}", std::process::id()));
+        let output = dir.join("csv");
+        fs::create_dir_all(&dir).unwrap();
+
+        let data = WaveformHeader::builder()
+            .channel(Channel::Ch1, 1.0, 0.0)
+            .build_with(RawData {
+                ch1: vec![100; 64].into(),
+                ..RawData::default()
+            });
+        write(&data, fs::File::create(dir.join("good.wfm")).unwrap()).unwrap();
+        fs::write(dir.join("bad.wfm"), b"garbage").unwrap();
+        fs::write(dir.join("other.txt"), b"skipped").unwrap();
+
+        let mut watcher = Watcher::new(&dir, &output, Format::Csv).unwrap();
+        assert!(watcher.scan().unwrap().is_empty());
+        let converted = watcher.scan().unwrap();
+        assert_eq!(converted.len(), 2);
+        assert_eq!(converted[0].name, "bad.wfm");
+        assert!(converted[0].result.is_err());
+        assert_eq!(converted[1].result, Ok("good.csv".into()));
+        assert_eq!(
+            fs::read_to_string(output.join("good.csv"))
+                .unwrap()
+                .lines()
+                .count(),
+            65
+        );
+
+        // Index survives restart
+        let mut watcher = Watcher::new(&dir, &output, Format::Csv).unwrap();
+        assert_eq!(watcher.entries(), &converted[..]);
+        assert!(watcher.scan().unwrap().is_empty());
+        assert!(watcher.scan().unwrap().is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,345 @@
+/*!
+
+Construction of consistent waveform headers
+
+*/
+use super::{
+    Channel, ChannelHeader, Coupling, LogicAnalyzerHeader, LogicLength, Model, Probe, RawData,
+    Source, TimeHeader, TriggerHeader, TriggerMode, Unit, WaveformData, WaveformHeader,
+};
+
+/// Primary settings of analog channel
+#[derive(Debug, Clone, Copy)]
+struct ChannelSettings {
+    enabled: bool,
+    volts_per_division: f32,
+    offset: f32,
+    probe: f32,
+    inverted: bool,
+}
+
+impl Default for ChannelSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            volts_per_division: 1.0,
+            offset: 0.0,
+            probe: 1.0,
+            inverted: false,
+        }
+    }
+}
+
+impl ChannelSettings {
+    fn header(&self) -> ChannelHeader {
+        // Measured scale is in microvolts per division at instrument input
+        let scale = (1.0e6 * self.volts_per_division / self.probe).round() as i32;
+        let volt_scale = 1.0e-6 * scale as f32 * self.probe / 25.0;
+        let shift = if volt_scale != 0.0 {
+            (self.offset / volt_scale)
+                .round()
+                .clamp(i16::MIN as f32, i16::MAX as f32) as i16
+        } else {
+            0
+        };
+
+        ChannelHeader {
+            scale_display: scale,
+            shift_display: shift,
+            probe_value: self.probe,
+            invert_display: self.inverted as u8,
+            scale_measured: scale,
+            shift_measured: shift,
+            inverted: self.inverted,
+            enabled: self.enabled,
+            volt_per_division: 0.0,
+            volt_scale: 0.0,
+            volt_offset: 0.0,
+            unit: Unit::V,
+        }
+        .scaled()
+    }
+}
+
+/// Builder of waveform header
+///
+/// Takes primary settings and computes stored and derived values
+/// consistently. By default both channels are disabled, sample rate is
+/// 100 MSa/s and timebase is 1 µs per division.
+#[derive(Debug, Clone)]
+pub struct HeaderBuilder {
+    ch1: ChannelSettings,
+    ch2: ChannelSettings,
+    sample_rate_hz: f32,
+    time_scale: f32,
+    time_offset: f32,
+    trigger_mode: TriggerMode,
+    trigger_source: Source,
+    trigger_level: f32,
+    logic_channels: u16,
+    logic_sample_rate_hz: f32,
+    points: u32,
+    roll_stop: u32,
+}
+
+impl Default for HeaderBuilder {
+    fn default() -> Self {
+        Self {
+            ch1: ChannelSettings::default(),
+            ch2: ChannelSettings::default(),
+            sample_rate_hz: 100.0e6,
+            time_scale: 1.0e-6,
+            time_offset: 0.0,
+            trigger_mode: TriggerMode::Edge,
+            trigger_source: Source::Ch1,
+            trigger_level: 0.0,
+            logic_channels: 0,
+            logic_sample_rate_hz: 0.0,
+            points: 0,
+            roll_stop: 0,
+        }
+    }
+}
+
+impl HeaderBuilder {
+    /// Create builder with default settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn settings(&mut self, channel: Channel) -> &mut ChannelSettings {
+        match channel {
+            Channel::Ch1 => &mut self.ch1,
+            Channel::Ch2 => &mut self.ch2,
+        }
+    }
+
+    /// Enable channel with vertical scale and offset in volts
+    pub fn channel(mut self, channel: Channel, volts_per_division: f32, offset: f32) -> Self {
+        let settings = self.settings(channel);
+        settings.enabled = true;
+        settings.volts_per_division = volts_per_division;
+        settings.offset = offset;
+        self
+    }
+
+    /// Set probe attenuation of channel
+    pub fn probe(mut self, channel: Channel, probe: impl Into<Probe>) -> Self {
+        self.settings(channel).probe = probe.into().ratio();
+        self
+    }
+
+    /// Set inversion of channel
+    pub fn inverted(mut self, channel: Channel, inverted: bool) -> Self {
+        self.settings(channel).inverted = inverted;
+        self
+    }
+
+    /// Set sample rate in Hz
+    pub fn sample_rate(mut self, sample_rate_hz: f32) -> Self {
+        self.sample_rate_hz = sample_rate_hz;
+        self
+    }
+
+    /// Set horizontal scale in seconds per division and offset in seconds
+    pub fn timebase(mut self, scale: f32, offset: f32) -> Self {
+        self.time_scale = scale;
+        self.time_offset = offset;
+        self
+    }
+
+    /// Set trigger mode, source and level in volts
+    pub fn trigger(mut self, mode: TriggerMode, source: Source, level: f32) -> Self {
+        self.trigger_mode = mode;
+        self.trigger_source = source;
+        self.trigger_level = level;
+        self
+    }
+
+    /// Enable logic analyzer channels by mask with sample rate in Hz
+    pub fn logic(mut self, channels: u16, sample_rate_hz: f32) -> Self {
+        self.logic_channels = channels;
+        self.logic_sample_rate_hz = sample_rate_hz;
+        self
+    }
+
+    /// Set number of valid points per channel
+    pub fn points(mut self, points: u32) -> Self {
+        self.points = points;
+        self
+    }
+
+    /// Set roll mode stop position, zero when not rolling
+    pub fn roll_stop(mut self, roll_stop: u32) -> Self {
+        self.roll_stop = roll_stop;
+        self
+    }
+
+    /// Build header
+    pub fn build(&self) -> WaveformHeader {
+        // Times are stored in picoseconds
+        let scale = (1.0e12 * self.time_scale as f64).round() as i64;
+        let offset = (1.0e12 * self.time_offset as f64).round() as i64;
+        let time = TimeHeader {
+            scale_display: scale,
+            offset_display: offset,
+            sample_rate_hz: self.sample_rate_hz,
+            scale_measured: scale,
+            offset_measured: offset,
+        };
+        let empty_time = TimeHeader {
+            scale_display: 0,
+            offset_display: 0,
+            sample_rate_hz: 0.0,
+            scale_measured: 0,
+            offset_measured: 0,
+        };
+
+        let trigger = TriggerHeader {
+            mode: self.trigger_mode,
+            source: self.trigger_source,
+            coupling: Coupling::Dc,
+            sweep: 0,
+            sens: 0.0,
+            holdoff: 0.0,
+            level: self.trigger_level,
+            direct: true,
+            pulse_type: 0,
+            pulse_width: 0.0,
+            slope_type: 0,
+            lower: 0.0,
+            slope_width: 0.0,
+            video_pol: 0,
+            video_sync: 0,
+            video_std: 0,
+        };
+
+        let logic = LogicAnalyzerHeader {
+            enabled: self.logic_channels != 0,
+            active_channel: 0,
+            enabled_channels: self.logic_channels,
+            position: [0; 16],
+            // Values stored by instrument
+            group8to15size: 7,
+            group0to7size: 7,
+        };
+
+        WaveformHeader {
+            model: if logic.enabled {
+                Model::Ds1000D
+            } else {
+                Model::Ds1000E
+            },
+            adc_mode: 0,
+            roll_stop: self.roll_stop,
+            active_channel: 0,
+            ch1: self.ch1.header(),
+            ch2: self.ch2.header(),
+            // In alternate trigger mode second channel has own timebase
+            time2: if self.trigger_mode == TriggerMode::Alt {
+                time.clone()
+            } else {
+                empty_time
+            },
+            time,
+            trigger1: trigger.clone(),
+            trigger2: trigger,
+            logic,
+            logic_sample_rate_hz: self.logic_sample_rate_hz,
+            trigger_mode: self.trigger_mode,
+            ch1_points: self.points,
+            ch1_skip: if self.roll_stop == 0 {
+                0
+            } else {
+                self.roll_stop + 2
+            },
+            ch2_points: self.points,
+            logic_length: LogicLength::written(self.logic_channels != 0, self.points),
+            raw: None,
+        }
+    }
+
+    /// Build waveform with raw samples
+    ///
+    /// Number of points is taken from the longest of samples.
+    pub fn build_with(self, data: RawData) -> WaveformData {
+        let analog = data
+            .ch1
+            .len()
+            .max(data.ch2.len())
+            .saturating_sub(data.invalid);
+        let points = analog.max(data.logic.len()) as u32;
+
+        WaveformData {
+            header: self.points(points).build(),
+            data,
+        }
+    }
+}
+
+impl WaveformHeader {
+    /// Builder of consistent header
+    pub fn builder() -> HeaderBuilder {
+        HeaderBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{parse, write};
+
+    #[test]
+    fn round_trip() {
+        let data = WaveformHeader::builder()
+            .channel(Channel::Ch1, 2.0, -1.2)
+            .channel(Channel::Ch2, 0.5, 0.0)
+            .probe(Channel::Ch2, Probe::X10)
+            .inverted(Channel::Ch2, true)
+            .sample_rate(50.0e6)
+            .timebase(2.0e-6, 1.0e-6)
+            .trigger(TriggerMode::Edge, Source::Ch2, 1.5)
+            .build_with(RawData {
+                ch1: (0..=255).collect(),
+                ch2: (0..=255).rev().collect(),
+                ..RawData::default()
+            });
+        let header = &data.header;
+
+        assert_eq!(header.ch1_points, 256);
+        assert_eq!(header.ch2_points, 256);
+        assert_eq!(header.ch1.scale_measured, 2_000_000);
+        assert_eq!(header.ch1.shift_measured, -15);
+        assert!((header.ch1.volt_offset + 1.2).abs() < 1.0e-6);
+        assert_eq!(header.ch2.scale_measured, 50_000);
+        assert!((header.ch2.volt_scale - 0.02).abs() < 1.0e-9);
+        assert_eq!(header.time.scale_measured, 2_000_000);
+        assert_eq!(header.time.offset_measured, 1_000_000);
+
+        let mut bytes = Vec::new();
+        write(&data, &mut bytes).unwrap();
+        assert_eq!(parse(&bytes).unwrap(), data);
+
+        let rolling = WaveformHeader::builder()
+            .channel(Channel::Ch1, 1.0, 0.0)
+            .logic(0xff, 200.0e6)
+            .roll_stop(10)
+            .build_with(RawData {
+                ch1: vec![125; 100].into(),
+                logic: vec![0x55; 100].into(),
+                ..RawData::default()
+            });
+        assert_eq!(rolling.header.model, Model::Ds1000D);
+        assert_eq!(rolling.header.ch1_skip, 12);
+        let mut bytes = Vec::new();
+        write(&rolling, &mut bytes).unwrap();
+        assert_eq!(parse(&bytes).unwrap(), rolling);
+
+        let empty = WaveformHeader::builder()
+            .logic(0xff, 200.0e6)
+            .build_with(RawData::default());
+        let mut bytes = Vec::new();
+        write(&empty, &mut bytes).unwrap();
+        assert_eq!(parse(&bytes).unwrap(), empty);
+    }
+}
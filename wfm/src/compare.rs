@@ -0,0 +1,253 @@
+/*!
+
+Comparison of waveforms
+
+*/
+use super::{Channel, WaveformData, WaveformHeader};
+use core::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Differing header field
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FieldDiff {
+    /// Path of field like `ch1.scale_measured`
+    pub field: String,
+    pub left: String,
+    pub right: String,
+}
+
+/// Deviation of samples
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SampleDiff {
+    /// Number of samples of both sides
+    pub lengths: (usize, usize),
+    /// Maximum absolute error over common length
+    pub max_error: f32,
+    /// RMS error over common length
+    pub rms_error: f32,
+    /// Index of first sample differing more than tolerance
+    ///
+    /// When lengths differ and common samples match, it is the common length.
+    pub first_mismatch: Option<usize>,
+}
+
+impl SampleDiff {
+    fn of<T: Copy>(left: &[T], right: &[T], error: impl Fn(T, T) -> f32, tolerance: f32) -> Self {
+        let mut max_error = 0.0f32;
+        let mut square_sum = 0.0f64;
+        let mut first_mismatch = None;
+
+        for (index, (&left, &right)) in left.iter().zip(right).enumerate() {
+            let error = error(left, right);
+            // NaN is treated as mismatch
+            if first_mismatch.is_none() && (error.is_nan() || error > tolerance) {
+                first_mismatch = Some(index);
+            }
+            max_error = max_error.max(error);
+            square_sum += error as f64 * error as f64;
+        }
+
+        let common = left.len().min(right.len());
+        if first_mismatch.is_none() && left.len() != right.len() {
+            first_mismatch = Some(common);
+        }
+
+        Self {
+            lengths: (left.len(), right.len()),
+            max_error,
+            rms_error: if common > 0 {
+                (square_sum / common as f64).sqrt() as f32
+            } else {
+                0.0
+            },
+            first_mismatch,
+        }
+    }
+
+    /// Samples match within tolerance
+    pub fn is_match(&self) -> bool {
+        self.first_mismatch.is_none()
+    }
+}
+
+/// Differences between two waveforms
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DiffReport {
+    pub header: Vec<FieldDiff>,
+    /// Scaled samples, `None` when channel has no samples on both sides
+    pub ch1: Option<SampleDiff>,
+    pub ch2: Option<SampleDiff>,
+    /// Logic analyzer words, error is number of differing bits
+    pub logic: Option<SampleDiff>,
+}
+
+impl DiffReport {
+    /// Waveforms are equal within tolerance
+    pub fn is_match(&self) -> bool {
+        self.header.is_empty()
+            && [&self.ch1, &self.ch2, &self.logic]
+                .iter()
+                .all(|diff| diff.as_ref().is_none_or(SampleDiff::is_match))
+    }
+}
+
+macro_rules! header_diff {
+    ( $left:ident, $right:ident; $( $($path:ident).+, )* ) => {
+        {
+            let mut diffs = Vec::new();
+            $(
+                let left = format!("{:?}", $left.$($path).+);
+                let right = format!("{:?}", $right.$($path).+);
+                if left != right {
+                    diffs.push(FieldDiff {
+                        field: stringify!($($path).+).replace(' ', ""),
+                        left,
+                        right,
+                    });
+                }
+            )*
+            diffs
+        }
+    };
+}
+
+fn header_diff(left: &WaveformHeader, right: &WaveformHeader) -> Vec<FieldDiff> {
+    header_diff! {
+        left, right;
+        model, adc_mode, roll_stop, active_channel,
+        ch1.enabled, ch1.inverted, ch1.probe_value, ch1.scale_measured, ch1.shift_measured,
+        ch1.scale_display, ch1.shift_display, ch1.invert_display, ch1.unit,
+        ch2.enabled, ch2.inverted, ch2.probe_value, ch2.scale_measured, ch2.shift_measured,
+        ch2.scale_display, ch2.shift_display, ch2.invert_display, ch2.unit,
+        time.scale_display, time.offset_display, time.sample_rate_hz,
+        time.scale_measured, time.offset_measured,
+        time2.scale_display, time2.offset_display, time2.sample_rate_hz,
+        time2.scale_measured, time2.offset_measured,
+        trigger_mode,
+        trigger1.mode, trigger1.source, trigger1.coupling, trigger1.sweep, trigger1.sens,
+        trigger1.holdoff, trigger1.level, trigger1.direct, trigger1.pulse_type,
+        trigger1.pulse_width, trigger1.slope_type, trigger1.lower, trigger1.slope_width,
+        trigger1.video_pol, trigger1.video_sync, trigger1.video_std,
+        trigger2.mode, trigger2.source, trigger2.coupling, trigger2.sweep, trigger2.sens,
+        trigger2.holdoff, trigger2.level, trigger2.direct, trigger2.pulse_type,
+        trigger2.pulse_width, trigger2.slope_type, trigger2.lower, trigger2.slope_width,
+        trigger2.video_pol, trigger2.video_sync, trigger2.video_std,
+        logic.enabled, logic.active_channel, logic.enabled_channels, logic.position,
+        logic.group8to15size, logic.group0to7size,
+        logic_sample_rate_hz, ch1_points, ch1_skip, ch2_points, logic_length,
+    }
+}
+
+impl WaveformData {
+    /// Compare with other waveform
+    ///
+    /// Derived scaling values are not compared as they follow from measured
+    /// ones. Scaled samples deviating at most by `tolerance` units match.
+    pub fn compare(&self, other: &WaveformData, tolerance: f32) -> DiffReport {
+        let samples = |data: &WaveformData, channel| {
+            data.trace(channel)
+                .map(|trace| trace.samples)
+                .unwrap_or_default()
+        };
+        let channel = |channel| {
+            let left = samples(self, channel);
+            let right = samples(other, channel);
+            if left.is_empty() && right.is_empty() {
+                None
+            } else {
+                Some(SampleDiff::of(
+                    &left,
+                    &right,
+                    |left, right| (left - right).abs(),
+                    tolerance,
+                ))
+            }
+        };
+
+        DiffReport {
+            header: header_diff(&self.header, &other.header),
+            ch1: channel(Channel::Ch1),
+            ch2: channel(Channel::Ch2),
+            logic: if self.data.logic.is_empty() && other.data.logic.is_empty() {
+                None
+            } else {
+                Some(SampleDiff::of(
+                    &self.data.logic,
+                    &other.data.logic,
+                    |left, right| (left ^ right).count_ones() as f32,
+                    0.0,
+                ))
+            },
+        }
+    }
+}
+
+impl fmt::Display for DiffReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for diff in &self.header {
+            writeln!(f, "{}: {} != {}", diff.field, diff.left, diff.right)?;
+        }
+        for (name, diff) in [
+            ("Ch1", &self.ch1),
+            ("Ch2", &self.ch2),
+            ("Logic", &self.logic),
+        ] {
+            if let Some(diff) = diff {
+                write!(
+                    f,
+                    "{}: max error {}, RMS error {}",
+                    name, diff.max_error, diff.rms_error
+                )?;
+                if diff.lengths.0 != diff.lengths.1 {
+                    write!(f, ", lengths {} != {}", diff.lengths.0, diff.lengths.1)?;
+                }
+                if let Some(index) = diff.first_mismatch {
+                    write!(f, ", first mismatch at {}", index)?;
+                }
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parse;
+    use std::fs::read;
+
+    #[test]
+    fn ds1052e_2ch() {
+        let i = read("test/ds1052e_2ch.wfm").unwrap();
+        let r = parse(&i).unwrap();
+        let d = r.compare(&r, 0.0);
+        assert!(d.is_match());
+        assert_eq!(d.ch1.unwrap().max_error, 0.0);
+        assert!(d.logic.is_none());
+
+        let mut o = r.clone();
+        o.header.ch2.shift_measured += 1;
+        o.header.ch2 = o.header.ch2.scaled();
+        o.data.ch1[100] = o.data.ch1[100].wrapping_add(1);
+        o.data.ch2.make_mut().pop();
+        let d = r.compare(&o, 0.1);
+        assert!(!d.is_match());
+        assert_eq!(d.header.len(), 1);
+        assert_eq!(d.header[0].field, "ch2.shift_measured");
+
+        let ch1 = d.ch1.as_ref().unwrap();
+        assert_eq!(ch1.first_mismatch, Some(100));
+        assert!((ch1.max_error - r.header.ch1.volt_scale).abs() < 1.0e-6);
+        let ch2 = d.ch2.as_ref().unwrap();
+        // Shifted by one step
+        assert_eq!(ch2.first_mismatch, Some(0));
+        assert_eq!(ch2.lengths, (524284, 524283));
+        assert!(r.compare(&o, 1.0).ch2.unwrap().first_mismatch == Some(524283));
+        assert!(d.to_string().starts_with("ch2.shift_measured: 0 != 1\n"));
+    }
+}
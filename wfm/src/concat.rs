@@ -0,0 +1,230 @@
+/*!
+
+Concatenation of sequential captures
+
+*/
+use super::{Channel, ChannelHeader, Error, LogicLength, RawData, Result, WaveformData};
+use core::{convert::TryFrom, ops::Range};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Part of concatenated record which comes from single capture
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Segment {
+    /// Analog samples of capture
+    pub analog: Range<usize>,
+    /// Logic analyzer samples of capture
+    pub logic: Range<usize>,
+    /// Time of first sample relative to trigger of capture in seconds
+    pub time_offset: f32,
+}
+
+/// Captures joined into one record
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Concatenated {
+    /// Record with header of first capture
+    pub data: WaveformData,
+    pub segments: Vec<Segment>,
+}
+
+/// Check that samples of channel convert to same units
+fn same_scaling(channel: Channel, first: &ChannelHeader, other: &ChannelHeader) -> Result<()> {
+    if first.unit != other.unit {
+        return Err(Error::UnitMismatch(first.unit, other.unit));
+    }
+    if first.enabled != other.enabled
+        || first.inverted != other.inverted
+        || first.volt_scale != other.volt_scale
+        || first.volt_offset != other.volt_offset
+    {
+        return Err(Error::ScaleMismatch(channel));
+    }
+    Ok(())
+}
+
+/// Check that capture can be appended to first one
+fn compatible(first: &WaveformData, other: &WaveformData) -> Result<()> {
+    let (a, b) = (&first.header, &other.header);
+
+    if a.time.sample_rate_hz != b.time.sample_rate_hz {
+        return Err(Error::SampleRateMismatch(
+            a.time.sample_rate_hz,
+            b.time.sample_rate_hz,
+        ));
+    }
+    if a.time2.sample_rate_hz != b.time2.sample_rate_hz {
+        return Err(Error::SampleRateMismatch(
+            a.time2.sample_rate_hz,
+            b.time2.sample_rate_hz,
+        ));
+    }
+    if a.logic.enabled != b.logic.enabled {
+        return Err(Error::Unsupported(
+            "concatenation of captures with and without logic analyzer".into(),
+        ));
+    }
+    if a.logic.enabled && a.logic_sample_rate_hz != b.logic_sample_rate_hz {
+        return Err(Error::SampleRateMismatch(
+            a.logic_sample_rate_hz,
+            b.logic_sample_rate_hz,
+        ));
+    }
+    same_scaling(Channel::Ch1, &a.ch1, &b.ch1)?;
+    same_scaling(Channel::Ch2, &a.ch2, &b.ch2)
+}
+
+/// Length of valid analog record, same for both channels
+fn analog_length(data: &WaveformData) -> Result<usize> {
+    let ch1 = data.data.valid(Channel::Ch1).len();
+    let ch2 = data.data.valid(Channel::Ch2).len();
+
+    match (ch1, ch2) {
+        (0, length) | (length, 0) => Ok(length),
+        (ch1, ch2) if ch1 == ch2 => Ok(ch1),
+        (ch1, ch2) => Err(Error::LengthMismatch(ch1, ch2)),
+    }
+}
+
+fn points(length: usize) -> Result<u32> {
+    u32::try_from(length).map_err(|_| Error::TooLarge {
+        size: length,
+        limit: u32::MAX as usize,
+    })
+}
+
+impl WaveformData {
+    /// Join sequential captures of long event into one record
+    ///
+    /// Captures should have same sample rates and vertical scaling. Invalid
+    /// samples are left out, time of resulting traces follows first
+    /// capture, so [`Segment`]s tell where following captures start.
+    pub fn concat(captures: &[WaveformData]) -> Result<Concatenated> {
+        let first = captures
+            .first()
+            .ok_or_else(|| Error::Unsupported("concatenation of no captures".into()))?;
+
+        let (mut ch1, mut ch2, mut logic) = (Vec::new(), Vec::new(), Vec::new());
+        let mut segments = Vec::with_capacity(captures.len());
+
+        for capture in captures {
+            compatible(first, capture)?;
+            let length = analog_length(capture)?;
+
+            let analog = ch1.len().max(ch2.len());
+            let start = logic.len();
+            ch1.extend_from_slice(capture.data.valid(Channel::Ch1));
+            ch2.extend_from_slice(capture.data.valid(Channel::Ch2));
+            logic.extend_from_slice(&capture.data.logic);
+
+            let time = &capture.header.time;
+            segments.push(Segment {
+                analog: analog..analog + length,
+                logic: start..logic.len(),
+                // Middle of valid samples corresponds to the middle of screen
+                time_offset: time.offset() - length as f32 / time.sample_rate_hz / 2.0,
+            });
+        }
+
+        let mut header = first.header.clone();
+        // Middle of record moves by half of added duration, so first
+        // sample keeps its time
+        let added = ch1.len().max(ch2.len()) - segments[0].analog.len();
+        for time in [&mut header.time, &mut header.time2].iter_mut() {
+            if time.sample_rate_hz > 0.0 {
+                let shift = (0.5e12 * added as f64 / time.sample_rate_hz as f64).round() as i64;
+                time.offset_measured += shift;
+                time.offset_display += shift;
+            }
+        }
+        header.ch1_points = points(ch1.len())?;
+        header.ch2_points = points(ch2.len())?;
+        header.logic_length = LogicLength::Detected(points(logic.len())?);
+        // Padding is dropped and header bytes do not describe record anymore
+        header.roll_stop = 0;
+        header.ch1_skip = 0;
+        header.raw = None;
+
+        let data = RawData {
+            ch1: ch1.into(),
+            ch2: ch2.into(),
+            logic: logic.into(),
+            invalid: 0,
+        };
+        Ok(Concatenated {
+            data: WaveformData { header, data },
+            segments,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{parse, write, Probe, SkipReason, WaveformHeader};
+    use std::fs::read;
+
+    #[test]
+    fn ds1052e_2ch() {
+        let i = read("test/ds1052e_2ch.wfm").unwrap();
+        let r = parse(&i).unwrap();
+        let points = r.data.ch1.len();
+
+        let c = WaveformData::concat(&[r.clone(), r.clone()]).unwrap();
+        assert_eq!(c.data.data.ch1.len(), 2 * points);
+        assert_eq!(c.data.data.ch2[points..], r.data.ch2[..]);
+        assert_eq!(c.data.header.ch1_points as usize, 2 * points);
+        assert_eq!(c.segments.len(), 2);
+        assert_eq!(
+            c.data.trace(Channel::Ch1).unwrap().time_offset,
+            c.segments[0].time_offset
+        );
+        assert_eq!(c.segments[1].analog, points..2 * points);
+        assert_eq!(
+            c.segments[1].time_offset,
+            r.trace(Channel::Ch1).unwrap().time_offset
+        );
+
+        let mut other = r.clone();
+        other.set_probe(Channel::Ch2, Probe::X10);
+        assert_eq!(
+            WaveformData::concat(&[r.clone(), other]),
+            Err(Error::ScaleMismatch(Channel::Ch2))
+        );
+
+        let other = WaveformHeader::builder()
+            .channel(Channel::Ch1, 5.0, 0.0)
+            .sample_rate(1.0e6)
+            .build_with(RawData::default());
+        assert_eq!(
+            WaveformData::concat(&[r, other]),
+            Err(Error::SampleRateMismatch(100.0e6, 1.0e6))
+        );
+    }
+
+    #[test]
+    fn rolling() {
+        let data = WaveformHeader::builder()
+            .channel(Channel::Ch1, 1.0, 0.0)
+            .channel(Channel::Ch2, 1.0, 0.0)
+            .roll_stop(10)
+            .build_with(RawData {
+                ch1: vec![50; 100].into(),
+                ch2: vec![100; 100].into(),
+                ..RawData::default()
+            });
+        let c = WaveformData::concat(&[data.clone(), data]).unwrap();
+        assert_eq!(c.data.header.ch1_skip, 0);
+        assert!(c
+            .data
+            .skipped()
+            .iter()
+            .all(|skip| skip.reason != SkipReason::RollStop));
+
+        let mut bytes = Vec::new();
+        write(&c.data, &mut bytes).unwrap();
+        assert_eq!(parse(&bytes).unwrap(), c.data);
+    }
+}
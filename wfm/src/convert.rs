@@ -0,0 +1,95 @@
+/*!
+
+Raw samples conversion
+
+*/
+use super::ChannelHeader;
+
+/// Convert raw ADC samples to units
+///
+/// Uses SSE2 on x86_64 when `simd` feature is enabled, results are the same
+/// as of [`ChannelHeader::volts`] for every sample.
+pub(crate) fn volts(header: &ChannelHeader, raw: &[u8], out: &mut [f32]) {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    {
+        // SSE2 is part of x86_64 baseline
+        unsafe { volts_sse2(header, raw, out) }
+    }
+    #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+    {
+        volts_scalar(header, raw, out)
+    }
+}
+
+fn volts_scalar(header: &ChannelHeader, raw: &[u8], out: &mut [f32]) {
+    for (out, &raw) in out.iter_mut().zip(raw) {
+        *out = header.volts(raw);
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+#[target_feature(enable = "sse2")]
+unsafe fn volts_sse2(header: &ChannelHeader, raw: &[u8], out: &mut [f32]) {
+    use super::ADC_ZERO;
+    use core::arch::x86_64::*;
+
+    let length = raw.len().min(out.len());
+    let blocks = length / 16;
+
+    let zero = _mm_set1_ps(ADC_ZERO);
+    let scale = _mm_set1_ps(header.volt_scale);
+    let offset = _mm_set1_ps(header.volt_offset);
+    // Inversion flips sign bit like scalar negation does
+    let sign = _mm_set1_ps(if header.inverted { -0.0 } else { 0.0 });
+    let nil = _mm_setzero_si128();
+
+    for block in 0..blocks {
+        let bytes = _mm_loadu_si128(raw.as_ptr().add(block * 16) as *const __m128i);
+        let low = _mm_unpacklo_epi8(bytes, nil);
+        let high = _mm_unpackhi_epi8(bytes, nil);
+        let words = [
+            _mm_unpacklo_epi16(low, nil),
+            _mm_unpackhi_epi16(low, nil),
+            _mm_unpacklo_epi16(high, nil),
+            _mm_unpackhi_epi16(high, nil),
+        ];
+
+        for (part, &words) in words.iter().enumerate() {
+            // Same operations order as scalar conversion
+            let value = _mm_cvtepi32_ps(words);
+            let value = _mm_sub_ps(_mm_mul_ps(_mm_sub_ps(zero, value), scale), offset);
+            let value = _mm_xor_ps(value, sign);
+            _mm_storeu_ps(out.as_mut_ptr().add(block * 16 + part * 4), value);
+        }
+    }
+
+    volts_scalar(
+        header,
+        &raw[blocks * 16..length],
+        &mut out[blocks * 16..length],
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse;
+    use std::fs::read;
+
+    #[test]
+    fn same_as_scalar() {
+        let i = read("test/ds1052e_2ch.wfm").unwrap();
+        let mut header = parse(&i).unwrap().header.ch1;
+        let raw = (0..1003).map(|index| (index * 7) as u8).collect::<Vec<_>>();
+
+        for &inverted in &[false, true] {
+            header.inverted = inverted;
+            let mut out = vec![0.0; raw.len()];
+            volts(&header, &raw, &mut out);
+
+            for (&out, &raw) in out.iter().zip(&raw) {
+                assert_eq!(out.to_bits(), header.volts(raw).to_bits());
+            }
+        }
+    }
+}
@@ -0,0 +1,9 @@
+/*!
+
+Protocol decoders
+
+*/
+pub mod parallel;
+#[cfg(feature = "sigrok")]
+pub mod sigrok;
+pub mod uart;
@@ -0,0 +1,186 @@
+/*!
+
+Parallel bus decoder
+
+*/
+use crate::{analysis::edge::Slope, Logic, LogicAnalyzerHeader};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Logic analyzer channels group
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Group {
+    /// Channels D0..D7
+    Low,
+    /// Channels D8..D15
+    High,
+}
+
+/// Bus lines mapping
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "Lines"))]
+pub struct Bus {
+    /// Logic lines of bus bits starting from least significant
+    lines: Vec<u8>,
+}
+
+/// Unchecked lines mapping of deserialized bus
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct Lines {
+    lines: Vec<u8>,
+}
+
+#[cfg(feature = "serde")]
+impl core::convert::TryFrom<Lines> for Bus {
+    type Error = crate::Error;
+
+    fn try_from(Lines { lines }: Lines) -> crate::Result<Self> {
+        let count = lines.len();
+        Self::new(lines).ok_or_else(|| crate::Error::Unsupported(format!("bus of {} lines", count)))
+    }
+}
+
+impl Bus {
+    /// Bus with explicit lines mapping
+    ///
+    /// Returns `None` when there are more than 16 lines or some line does not
+    /// exist.
+    pub fn new(lines: impl Into<Vec<u8>>) -> Option<Self> {
+        let lines = lines.into();
+        if lines.len() > Logic::LINES as usize || lines.iter().any(|&line| line >= Logic::LINES) {
+            return None;
+        }
+        Some(Self { lines })
+    }
+
+    /// Bus of enabled channels in group
+    pub fn group(header: &LogicAnalyzerHeader, group: Group) -> Self {
+        let lines = match group {
+            Group::Low => 0..8,
+            Group::High => 8..16,
+        };
+
+        Self {
+            lines: lines
+                .filter(|line| header.enabled_channels >> line & 1 != 0)
+                .collect(),
+        }
+    }
+
+    /// Logic lines of bus bits starting from least significant
+    pub fn lines(&self) -> &[u8] {
+        &self.lines
+    }
+
+    /// Extract bus value from logic sample
+    pub fn value(&self, sample: u16) -> u16 {
+        self.lines
+            .iter()
+            .enumerate()
+            .fold(0, |value, (bit, &line)| value | (sample >> line & 1) << bit)
+    }
+}
+
+/// Sampling mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Clock {
+    /// Emit value when it changes
+    Change,
+    /// Sample value on clock line edge
+    Edge { line: u8, slope: Slope },
+}
+
+/// Bus value
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Word {
+    /// Sample index
+    pub index: usize,
+    /// Time in seconds relative to trigger
+    pub time: f32,
+    pub value: u16,
+}
+
+/// Decode bus values from logic analyzer samples
+pub fn decode(logic: &Logic, bus: &Bus, clock: Clock) -> Vec<Word> {
+    let word = |index: usize| Word {
+        index,
+        time: logic.time(index),
+        value: bus.value(logic.samples[index]),
+    };
+
+    match clock {
+        Clock::Change => {
+            let mut words: Vec<Word> = Vec::new();
+            for index in 0..logic.len() {
+                let word = word(index);
+                if words.last().is_none_or(|last| last.value != word.value) {
+                    words.push(word);
+                }
+            }
+            words
+        }
+        Clock::Edge { line, slope } => (1..logic.len())
+            .filter(
+                |&index| match (logic.bit(index - 1, line), logic.bit(index, line)) {
+                    (Some(before), Some(after)) => before != after && slope.matches(!before),
+                    _ => false,
+                },
+            )
+            .map(word)
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn clocked() {
+        // Clock on D15, data on D0..D3
+        let logic = Logic {
+            samples: vec![0x0000, 0x8001, 0x0002, 0x8002, 0x0005, 0x8005],
+            sample_rate_hz: 1.0,
+            time_offset: 0.0,
+        };
+        let bus = Bus::new([0, 1, 2, 3]).unwrap();
+
+        let rising = decode(
+            &logic,
+            &bus,
+            Clock::Edge {
+                line: 15,
+                slope: Slope::Rising,
+            },
+        );
+        assert_eq!(
+            rising.iter().map(|word| word.value).collect::<Vec<_>>(),
+            [1, 2, 5]
+        );
+        assert_eq!(rising[1].index, 3);
+
+        let changes = decode(&logic, &bus, Clock::Change);
+        assert_eq!(
+            changes.iter().map(|word| word.value).collect::<Vec<_>>(),
+            [0, 1, 2, 5]
+        );
+
+        assert_eq!(Bus::new([0, 16]), None);
+        assert_eq!(Bus::new([0; 17]), None);
+        assert_eq!(Bus::new([15; 16]).unwrap().value(0x8000), 0xffff);
+
+        #[cfg(feature = "json")]
+        {
+            let json = serde_json::to_string(&bus).unwrap();
+            assert_eq!(json, r#"{"lines":[0,1,2,3]}"#);
+            assert_eq!(serde_json::from_str::<Bus>(&json).unwrap(), bus);
+            assert!(serde_json::from_str::<Bus>(r#"{"lines":[16]}"#).is_err());
+        }
+    }
+}
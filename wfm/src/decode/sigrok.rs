@@ -0,0 +1,219 @@
+/*!
+
+Bridge to sigrok protocol decoders
+
+Logic samples are piped to `sigrok-cli` in its `binary` input format and
+annotations printed by stacked decoders are read back. Logic lines are
+named by their numbers, so decoder channels are assigned like
+`uart:baudrate=115200:rx=0`.
+
+*/
+use crate::Logic;
+use std::{
+    ffi::OsString,
+    io::{self, Write},
+    path::PathBuf,
+    process::{Command, Stdio},
+    thread,
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Annotation printed by decoder
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Event {
+    /// Time of first sample in seconds relative to trigger
+    pub start: f32,
+    /// Time of last sample in seconds relative to trigger
+    pub end: f32,
+    /// Decoder instance, like `uart-1`
+    pub decoder: String,
+    pub text: String,
+}
+
+/// Configured `sigrok-cli` invocation
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Sigrok {
+    /// Path to `sigrok-cli` executable
+    pub program: PathBuf,
+    /// Decoder specifications from bottom of stack
+    pub decoders: Vec<String>,
+    /// Annotation filters, all annotations are printed when empty
+    pub annotations: Vec<String>,
+}
+
+impl Default for Sigrok {
+    fn default() -> Self {
+        Self {
+            program: "sigrok-cli".into(),
+            decoders: Vec::new(),
+            annotations: Vec::new(),
+        }
+    }
+}
+
+impl Sigrok {
+    /// Invocation of `sigrok-cli` from `PATH`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use executable at path
+    pub fn program(mut self, program: impl Into<PathBuf>) -> Self {
+        self.program = program.into();
+        self
+    }
+
+    /// Stack decoder with options, like `uart:baudrate=9600:rx=0`
+    pub fn decoder(mut self, spec: impl Into<String>) -> Self {
+        self.decoders.push(spec.into());
+        self
+    }
+
+    /// Print only given annotations, like `uart=rx-data`
+    pub fn annotation(mut self, spec: impl Into<String>) -> Self {
+        self.annotations.push(spec.into());
+        self
+    }
+
+    /// Command line arguments for decoding logic from standard input
+    pub fn arguments(&self, logic: &Logic) -> Vec<OsString> {
+        let mut arguments = vec![
+            "-I".into(),
+            format!(
+                "binary:numchannels=16:samplerate={}",
+                logic.sample_rate_hz.round() as u64
+            )
+            .into(),
+            "-i".into(),
+            "-".into(),
+            "--protocol-decoder-samplenum".into(),
+        ];
+        // Stack and filters are comma separated lists
+        if !self.decoders.is_empty() {
+            arguments.push("-P".into());
+            arguments.push(self.decoders.join(",").into());
+        }
+        if !self.annotations.is_empty() {
+            arguments.push("-A".into());
+            arguments.push(self.annotations.join(",").into());
+        }
+        arguments
+    }
+
+    /// Run decoders on logic
+    pub fn decode(&self, logic: &Logic) -> io::Result<Vec<Event>> {
+        let mut child = Command::new(&self.program)
+            .args(self.arguments(logic))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|error| {
+                io::Error::new(
+                    error.kind(),
+                    format!("{}: {}", self.program.display(), error),
+                )
+            })?;
+
+        // Feed input while output is collected to avoid blocking on full pipes
+        let mut stdin = child.stdin.take().expect("Standard input is piped");
+        let input = binary(logic);
+        let writer = thread::spawn(move || stdin.write_all(&input));
+
+        let output = child.wait_with_output()?;
+        // Decoders may exit before consuming all input
+        let _ = writer.join();
+
+        if !output.status.success() {
+            return Err(io::Error::other(format!(
+                "{} failed: {}",
+                self.program.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        Ok(parse_events(
+            logic,
+            &String::from_utf8_lossy(&output.stdout),
+        ))
+    }
+}
+
+/// Logic samples in sigrok `binary` input format
+///
+/// Each sample is 16-bit little-endian word with line 0 in lowest bit.
+pub fn binary(logic: &Logic) -> Vec<u8> {
+    logic
+        .samples
+        .iter()
+        .flat_map(|sample| sample.to_le_bytes().to_vec())
+        .collect()
+}
+
+/// Parse `start-end decoder: text` lines of `sigrok-cli` output
+fn parse_events(logic: &Logic, output: &str) -> Vec<Event> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (samples, rest) = line.split_once(' ')?;
+            let (start, end) = samples.split_once('-')?;
+            let (decoder, text) = rest.split_once(": ")?;
+
+            Some(Event {
+                start: logic.time(start.parse().ok()?),
+                end: logic.time(end.parse().ok()?),
+                decoder: decoder.into(),
+                text: text.into(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bridge() {
+        let logic = Logic {
+            samples: vec![0x0001, 0x8002],
+            sample_rate_hz: 1.0e6,
+            time_offset: -1.0e-6,
+        };
+        assert_eq!(binary(&logic), [0x01, 0x00, 0x02, 0x80]);
+
+        let sigrok = Sigrok::new()
+            .decoder("uart:baudrate=9600:rx=0")
+            .decoder("modbus")
+            .annotation("uart=rx-data");
+        assert_eq!(
+            sigrok.arguments(&logic),
+            [
+                "-I",
+                "binary:numchannels=16:samplerate=1000000",
+                "-i",
+                "-",
+                "--protocol-decoder-samplenum",
+                "-P",
+                "uart:baudrate=9600:rx=0,modbus",
+                "-A",
+                "uart=rx-data",
+            ]
+        );
+
+        let events = parse_events(&logic, "0-1 uart-1: 48\nNo data\n");
+        assert_eq!(
+            events,
+            [Event {
+                start: -1.0e-6,
+                end: 0.0,
+                decoder: "uart-1".into(),
+                text: "48".into(),
+            }]
+        );
+    }
+}
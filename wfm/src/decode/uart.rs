@@ -0,0 +1,331 @@
+/*!
+
+UART decoder
+
+Frames are detected by start bit edge, bits are sampled in the middle of bit
+periods, least significant bit first.
+
+*/
+use crate::{Logic, Threshold, Trace};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Parity mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Parity {
+    None,
+    Even,
+    Odd,
+}
+
+/// Number of stop bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum StopBits {
+    One,
+    OneAndHalf,
+    Two,
+}
+
+impl StopBits {
+    /// Length in bits
+    pub fn bits(&self) -> f64 {
+        match self {
+            StopBits::One => 1.0,
+            StopBits::OneAndHalf => 1.5,
+            StopBits::Two => 2.0,
+        }
+    }
+}
+
+/// Decoder configuration
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Config {
+    pub baud_rate: f32,
+    /// Number of data bits (5..=9)
+    pub data_bits: u8,
+    pub parity: Parity,
+    pub stop_bits: StopBits,
+    /// Idle line level is low (like non-inverted RS-232 levels)
+    pub inverted: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            baud_rate: 9600.0,
+            data_bits: 8,
+            parity: Parity::None,
+            stop_bits: StopBits::One,
+            inverted: false,
+        }
+    }
+}
+
+impl Config {
+    /// Supported numbers of data bits
+    pub const DATA_BITS: core::ops::RangeInclusive<u8> = 5..=9;
+
+    /// Frame length in bits including start and stop bits
+    pub fn frame_bits(&self) -> f64 {
+        let parity = if self.parity == Parity::None {
+            0.0
+        } else {
+            1.0
+        };
+
+        1.0 + self.data_bits as f64 + parity + self.stop_bits.bits()
+    }
+}
+
+/// Decoded frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Frame {
+    /// Time of start bit edge in seconds relative to trigger
+    pub start: f32,
+    /// Time of stop bits end in seconds relative to trigger
+    pub end: f32,
+    pub data: u16,
+    pub parity_error: bool,
+    /// Stop bit isn't at idle level
+    pub framing_error: bool,
+}
+
+/// Decode UART frames from logic analyzer line
+///
+/// Returns `None` when there is no such line, number of data bits is not
+/// supported or baud rate is not positive.
+pub fn decode(logic: &Logic, line: u8, config: Config) -> Option<Decoder<'_>> {
+    if line >= Logic::LINES || !Config::DATA_BITS.contains(&config.data_bits) {
+        return None;
+    }
+    let samples_per_bit = logic.sample_rate_hz as f64 / config.baud_rate as f64;
+    if !(config.baud_rate.is_finite() && config.baud_rate > 0.0 && samples_per_bit.is_finite()) {
+        return None;
+    }
+    Some(Decoder {
+        logic,
+        line,
+        config,
+        samples_per_bit,
+        position: 0,
+    })
+}
+
+/// Decode UART frames from analog trace using threshold
+///
+/// Returns `None` when configuration is not supported like by [`decode`].
+pub fn decode_trace(trace: &Trace, threshold: Threshold, config: Config) -> Option<Vec<Frame>> {
+    let logic = trace.to_logic(threshold, 0)?;
+    Some(decode(&logic, 0, config)?.collect())
+}
+
+/// Frames iterator
+pub struct Decoder<'a> {
+    logic: &'a Logic,
+    line: u8,
+    config: Config,
+    samples_per_bit: f64,
+    position: usize,
+}
+
+impl<'a> Decoder<'a> {
+    /// Line is at idle level
+    fn idle(&self, index: usize) -> bool {
+        // Line is checked when decoder is created
+        self.logic.bit(index, self.line) != Some(self.config.inverted)
+    }
+
+    /// Index of sample at given number of bit periods after frame start
+    fn offset(&self, start: usize, bits: f64) -> usize {
+        // Long bit periods saturate beyond record
+        start.saturating_add((bits * self.samples_per_bit) as usize)
+    }
+
+    /// Sample bit at given number of bit periods after frame start
+    fn sample(&self, start: usize, bits: f64) -> Option<bool> {
+        let index = self.offset(start, bits);
+
+        if index < self.logic.len() {
+            Some(self.idle(index))
+        } else {
+            None
+        }
+    }
+
+    /// Find next transition from idle to active level
+    fn find_start(&mut self) -> Option<usize> {
+        let length = self.logic.len();
+
+        while self.position < length && !self.idle(self.position) {
+            self.position += 1;
+        }
+        while self.position < length && self.idle(self.position) {
+            self.position += 1;
+        }
+
+        if self.position < length {
+            Some(self.position)
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a> Iterator for Decoder<'a> {
+    type Item = Frame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Config {
+            data_bits,
+            parity,
+            stop_bits,
+            ..
+        } = self.config;
+
+        loop {
+            let start = self.find_start()?;
+
+            // Ignore glitches shorter than half of bit
+            if self.sample(start, 0.5)? {
+                continue;
+            }
+
+            let mut data = 0u16;
+            for bit in 0..data_bits {
+                if self.sample(start, 1.5 + bit as f64)? {
+                    data |= 1 << bit;
+                }
+            }
+
+            let mut bit = 1.5 + data_bits as f64;
+            let parity_error = if parity == Parity::None {
+                false
+            } else {
+                let ones = data.count_ones() + self.sample(start, bit)? as u32;
+                bit += 1.0;
+                ones.is_multiple_of(2) != (parity == Parity::Even)
+            };
+
+            let mut framing_error = !self.sample(start, bit)?;
+            if stop_bits == StopBits::Two {
+                bit += 1.0;
+                framing_error |= !self.sample(start, bit)?;
+            }
+
+            // Next start bit can't begin before the middle of last stop bit
+            self.position = self.offset(start, bit);
+
+            let end = self.offset(start, self.config.frame_bits());
+
+            return Some(Frame {
+                start: self.logic.time(start),
+                end: self.logic.time(end),
+                data,
+                parity_error,
+                framing_error,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Encode frames with 10 samples per bit
+    fn encode(bytes: &[u8], config: Config) -> Logic {
+        let bit = |samples: &mut Vec<u16>, state: bool| {
+            samples.extend(core::iter::repeat_n((state != config.inverted) as u16, 10))
+        };
+        let mut samples = Vec::new();
+
+        bit(&mut samples, true);
+        for &byte in bytes {
+            bit(&mut samples, false);
+            for index in 0..config.data_bits {
+                bit(&mut samples, byte >> index & 1 != 0);
+            }
+            match config.parity {
+                Parity::None => (),
+                Parity::Even => bit(&mut samples, byte.count_ones() % 2 != 0),
+                Parity::Odd => bit(&mut samples, byte.count_ones() % 2 == 0),
+            }
+            bit(&mut samples, true);
+            bit(&mut samples, true);
+        }
+
+        Logic {
+            samples,
+            sample_rate_hz: 10.0 * config.baud_rate,
+            time_offset: 0.0,
+        }
+    }
+
+    #[test]
+    fn bytes() {
+        let config = Config {
+            parity: Parity::Even,
+            ..Default::default()
+        };
+        let frames = decode(&encode(b"Hi!", config), 0, config)
+            .unwrap()
+            .collect::<Vec<_>>();
+
+        assert_eq!(frames.len(), 3);
+        assert_eq!(
+            frames
+                .iter()
+                .map(|frame| frame.data as u8)
+                .collect::<Vec<_>>(),
+            b"Hi!"
+        );
+        assert!(frames
+            .iter()
+            .all(|frame| !frame.parity_error && !frame.framing_error));
+        assert!((frames[0].start - 10.0 / 96000.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn errors() {
+        let config = Config {
+            parity: Parity::Odd,
+            inverted: true,
+            ..Default::default()
+        };
+        let mut logic = encode(b"\x55", config);
+        // Break stop bit
+        for sample in &mut logic.samples[110..120] {
+            *sample ^= 1;
+        }
+        let frames = decode(&logic, 0, config).unwrap().collect::<Vec<_>>();
+        assert!(decode(&logic, Logic::LINES, config).is_none());
+        for &data_bits in [4, 10, 255].iter() {
+            let config = Config {
+                data_bits,
+                ..config
+            };
+            assert!(decode(&logic, 0, config).is_none());
+        }
+        for &baud_rate in [0.0, -9600.0, f32::NAN, f32::INFINITY].iter() {
+            let config = Config {
+                baud_rate,
+                ..config
+            };
+            assert!(decode(&logic, 0, config).is_none());
+        }
+        let slow = Config {
+            baud_rate: 1.0e-30,
+            ..config
+        };
+        assert_eq!(decode(&logic, 0, slow).unwrap().count(), 0);
+
+        assert_eq!(frames[0].data, 0x55);
+        assert!(!frames[0].parity_error);
+        assert!(frames[0].framing_error);
+    }
+}
@@ -0,0 +1,97 @@
+/*!
+
+Error handling
+
+*/
+use super::{Channel, Unit};
+use nom::{error::ErrorKind, Needed};
+
+/// Result with library error
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// Library error
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum Error {
+    /// File does not start with known magic
+    #[error("Bad magic {found:02x?}, not a waveform file")]
+    BadMagic { found: Vec<u8> },
+    /// File ends before data is complete
+    #[error("Truncated {context} at offset {offset}")]
+    Truncated {
+        context: &'static str,
+        offset: usize,
+        needed: Option<usize>,
+    },
+    /// Unexpected value in file
+    #[error("Invalid {context} at offset {offset}")]
+    Invalid {
+        context: &'static str,
+        offset: usize,
+    },
+    /// Header claims more data than allowed
+    #[error("Data size {size} exceeds limit {limit}")]
+    TooLarge { size: usize, limit: usize },
+    /// Reading of input failed
+    #[error("I/O error: {0}")]
+    Io(std::io::ErrorKind),
+    /// File has format which cannot be handled
+    #[error("Unsupported {0}")]
+    Unsupported(String),
+    /// Requested channel is not enabled in capture
+    #[error("Channel {0:?} is disabled")]
+    ChannelDisabled(Channel),
+    /// Records have different sample rates
+    #[error("Sample rate mismatch: {0} Hz != {1} Hz")]
+    SampleRateMismatch(f32, f32),
+    /// Records have different lengths
+    #[error("Length mismatch: {0} != {1}")]
+    LengthMismatch(usize, usize),
+    /// Records have different units
+    #[error("Unit mismatch: {0:?} != {1:?}")]
+    UnitMismatch(Unit, Unit),
+    /// Channel has different vertical scaling in records
+    #[error("Scaling mismatch of {0:?}")]
+    ScaleMismatch(Channel),
+}
+
+impl Error {
+    /// Offset in input where error occurred
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            Error::BadMagic { .. } => Some(0),
+            Error::Truncated { offset, .. } | Error::Invalid { offset, .. } => Some(*offset),
+            _ => None,
+        }
+    }
+
+    /// Convert parser error
+    ///
+    /// Offset is counted from start of input which has given length.
+    pub(crate) fn from_nom(
+        error: nom::Err<nom::error::Error<&[u8]>>,
+        context: &'static str,
+        base: usize,
+        length: usize,
+    ) -> Self {
+        match error {
+            nom::Err::Incomplete(needed) => Error::Truncated {
+                context,
+                offset: base + length,
+                needed: match needed {
+                    Needed::Size(size) => Some(size.get()),
+                    Needed::Unknown => None,
+                },
+            },
+            nom::Err::Error(error) | nom::Err::Failure(error) => {
+                let offset = base + length - error.input.len();
+                if error.code == ErrorKind::Tag && offset == 0 {
+                    Error::BadMagic {
+                        found: error.input[..4.min(error.input.len())].to_vec(),
+                    }
+                } else {
+                    Error::Invalid { context, offset }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,262 @@
+/*!
+
+Export of scaled samples
+
+*/
+use crate::{
+    progress::{Reporter, Stage},
+    Channel, Progress, Trace, WaveformData,
+};
+use core::ops::Range;
+use std::io::{Result, Write};
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+mod cfile;
+mod density;
+mod labels;
+mod ltspice;
+mod tdms;
+
+pub use cfile::*;
+pub use density::*;
+pub use labels::*;
+pub use ltspice::*;
+pub use tdms::*;
+
+#[cfg(feature = "json")]
+use crate::{annotations::Annotation, WaveformHeader};
+#[cfg(feature = "json")]
+use serde::Serialize;
+
+/// Enabled channels with scaled samples
+fn traces(data: &WaveformData) -> Vec<(Channel, Trace)> {
+    [Channel::Ch1, Channel::Ch2]
+        .iter()
+        .filter_map(|&channel| Some((channel, data.trace(channel)?)))
+        .collect()
+}
+
+/// Error of records which can not be written together
+fn invalid_input(error: crate::Error) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, error)
+}
+
+/// Number of rows between progress reports
+const REPORT_ROWS: usize = 1 << 14;
+
+/// Write enabled channels as comma separated values
+///
+/// First column is time of first channel samples in seconds relative to
+/// trigger, following columns are samples of channels in units.
+pub fn write_csv(data: &WaveformData, writer: impl Write) -> Result<()> {
+    csv(data, writer, Reporter::new(None, 0))
+}
+
+/// Write enabled channels as comma separated values reporting progress in rows
+pub fn write_csv_with(
+    data: &WaveformData,
+    writer: impl Write,
+    progress: impl FnMut(Progress),
+) -> Result<()> {
+    csv(data, writer, Reporter::new(Some(Box::new(progress)), 0))
+}
+
+fn csv(data: &WaveformData, mut writer: impl Write, mut reporter: Reporter) -> Result<()> {
+    let traces = traces(data);
+
+    write!(writer, "Time (s)")?;
+    for (channel, trace) in &traces {
+        write!(writer, ",{:?} ({:?})", channel, trace.unit)?;
+    }
+    writeln!(writer)?;
+
+    let length = traces.iter().map(|(_, trace)| trace.len()).max();
+    if traces.is_empty() {
+        return Ok(());
+    }
+
+    let length = length.unwrap_or(0);
+    reporter.total = length;
+
+    let blocks = (0..length)
+        .step_by(REPORT_ROWS)
+        .map(|start| start..(start + REPORT_ROWS).min(length))
+        .collect::<Vec<_>>();
+
+    // Blocks are formatted in groups concurrently and written in order
+    #[cfg(feature = "rayon")]
+    let group = rayon::current_num_threads();
+    #[cfg(not(feature = "rayon"))]
+    let group = 1;
+
+    for blocks in blocks.chunks(group) {
+        #[cfg(feature = "rayon")]
+        let texts = blocks
+            .par_iter()
+            .map(|rows| csv_rows(&traces, rows.clone()))
+            .collect::<Vec<_>>();
+        #[cfg(not(feature = "rayon"))]
+        let texts = blocks
+            .iter()
+            .map(|rows| csv_rows(&traces, rows.clone()))
+            .collect::<Vec<_>>();
+
+        for (rows, text) in blocks.iter().zip(texts) {
+            writer.write_all(text.as_bytes())?;
+            reporter.report(Stage::Export, rows.end, length);
+        }
+    }
+
+    Ok(())
+}
+
+/// Format rows of comma separated values
+fn csv_rows(traces: &[(Channel, Trace)], rows: Range<usize>) -> String {
+    use core::fmt::Write;
+
+    let time = &traces[0].1;
+    let mut text = String::new();
+
+    for index in rows {
+        let _ = write!(text, "{:e}", time.time(index));
+        for (_, trace) in traces {
+            match trace.samples.get(index) {
+                Some(value) => {
+                    let _ = write!(text, ",{}", value);
+                }
+                None => text.push(','),
+            }
+        }
+        text.push('\n');
+    }
+
+    text
+}
+
+/// Enabled channels as comma separated values
+pub fn to_csv(data: &WaveformData) -> String {
+    let mut csv = Vec::new();
+    write_csv(data, &mut csv).expect("Writing to memory does not fail");
+    String::from_utf8(csv).expect("CSV is valid UTF-8")
+}
+
+/// Header with scaled samples of enabled channels
+#[cfg(feature = "json")]
+#[derive(Serialize)]
+struct Json<'a> {
+    header: &'a WaveformHeader,
+    ch1: Option<Trace>,
+    ch2: Option<Trace>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    annotations: &'a [Annotation],
+}
+
+/// Write header and scaled samples as JSON
+#[cfg(feature = "json")]
+pub fn write_json(data: &WaveformData, writer: impl Write) -> serde_json::Result<()> {
+    write_json_annotated(data, &[], writer)
+}
+
+/// Write header, scaled samples and annotations as JSON
+#[cfg(feature = "json")]
+pub fn write_json_annotated(
+    data: &WaveformData,
+    annotations: &[Annotation],
+    writer: impl Write,
+) -> serde_json::Result<()> {
+    serde_json::to_writer(
+        writer,
+        &Json {
+            header: &data.header,
+            ch1: data.trace(Channel::Ch1),
+            ch2: data.trace(Channel::Ch2),
+            annotations,
+        },
+    )
+}
+
+/// Header and scaled samples as JSON
+#[cfg(feature = "json")]
+pub fn to_json(data: &WaveformData) -> String {
+    let mut json = Vec::new();
+    write_json(data, &mut json).expect("Serializing to memory does not fail");
+    String::from_utf8(json).expect("JSON is valid UTF-8")
+}
+
+/// Output format of conversion
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Format {
+    Csv,
+    #[cfg(feature = "json")]
+    Json,
+    /// LTspice binary raw file
+    Raw,
+}
+
+impl Format {
+    /// File name extension
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            #[cfg(feature = "json")]
+            Format::Json => "json",
+            Format::Raw => "raw",
+        }
+    }
+
+    /// Write waveform in format
+    pub fn write(&self, data: &WaveformData, writer: impl Write) -> Result<()> {
+        match self {
+            Format::Csv => write_csv(data, writer),
+            #[cfg(feature = "json")]
+            Format::Json => Ok(write_json(data, writer)?),
+            Format::Raw => write_ltspice_raw(data, writer),
+        }
+    }
+}
+
+impl core::str::FromStr for Format {
+    type Err = crate::Error;
+
+    fn from_str(name: &str) -> crate::Result<Self> {
+        match name {
+            "csv" => Ok(Format::Csv),
+            #[cfg(feature = "json")]
+            "json" => Ok(Format::Json),
+            "raw" => Ok(Format::Raw),
+            _ => Err(crate::Error::Unsupported(format!("format {}", name))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse;
+    use std::fs::read;
+
+    #[test]
+    fn ds1052e_2ch() {
+        let i = read("test/ds1052e_2ch.wfm").unwrap();
+        let r = parse(&i).unwrap();
+        let csv = to_csv(&r);
+        let mut lines = csv.lines();
+
+        assert_eq!(lines.next(), Some("Time (s),Ch1 (V),Ch2 (V)"));
+        let row = lines.next().unwrap().split(',').collect::<Vec<_>>();
+        assert_eq!(row.len(), 3);
+        assert_eq!(
+            row[0].parse::<f32>().unwrap(),
+            r.trace(Channel::Ch1).unwrap().time_offset
+        );
+        assert_eq!(lines.count(), r.data.ch1.len() - 1);
+
+        let mut p = Vec::new();
+        write_csv_with(&r, std::io::sink(), |progress| p.push(progress)).unwrap();
+        assert_eq!(p.len(), r.data.ch1.len().div_ceil(REPORT_ROWS));
+        assert!(p.last().unwrap().is_stage_complete());
+    }
+}
@@ -0,0 +1,100 @@
+/*!
+
+Raw float samples export
+
+Samples are written as little-endian 32-bit floats like GNU Radio file sinks
+store them, two traces are interleaved as in-phase and quadrature parts of
+complex samples. SigMF metadata written alongside tells sample format and
+rate to tools like inspectrum.
+
+*/
+use super::invalid_input;
+use crate::{Error, Trace};
+use std::io::{self, Write};
+
+/// Write trace samples as `.f32` raw file
+pub fn write_raw(trace: &Trace, mut writer: impl Write) -> io::Result<()> {
+    for sample in &trace.samples {
+        writer.write_all(&sample.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// Write traces as in-phase and quadrature parts of `.cfile` complex samples
+///
+/// Traces should have same sample rate and length.
+pub fn write_iq(i: &Trace, q: &Trace, mut writer: impl Write) -> io::Result<()> {
+    if i.sample_rate_hz != q.sample_rate_hz {
+        return Err(invalid_input(Error::SampleRateMismatch(
+            i.sample_rate_hz,
+            q.sample_rate_hz,
+        )));
+    }
+    if i.len() != q.len() {
+        return Err(invalid_input(Error::LengthMismatch(i.len(), q.len())));
+    }
+
+    for (i, q) in i.samples.iter().zip(q.samples.iter()) {
+        writer.write_all(&i.to_le_bytes())?;
+        writer.write_all(&q.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// Write SigMF metadata of raw file written from trace
+///
+/// Complex is set for files written by [`write_iq`].
+pub fn write_sigmf_meta(trace: &Trace, complex: bool, mut writer: impl Write) -> io::Result<()> {
+    writeln!(
+        writer,
+        r#"{{"global":{{"core:datatype":"{}","core:sample_rate":{},"core:version":"1.0.0","core:description":"{:?} samples, first at {:e} s relative to trigger"}},"captures":[{{"core:sample_start":0}}],"annotations":[]}}"#,
+        if complex { "cf32_le" } else { "rf32_le" },
+        trace.sample_rate_hz,
+        trace.unit,
+        trace.time_offset,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{parse, Channel};
+    use std::fs::read;
+
+    #[test]
+    fn ds1052e_2ch() {
+        let i = read("test/ds1052e_2ch.wfm").unwrap();
+        let r = parse(&i).unwrap();
+        let ch1 = r.trace(Channel::Ch1).unwrap();
+        let ch2 = r.trace(Channel::Ch2).unwrap();
+
+        let mut raw = Vec::new();
+        write_raw(&ch1, &mut raw).unwrap();
+        assert_eq!(raw.len(), 4 * ch1.len());
+        assert_eq!(raw[4..8], ch1.samples[1].to_le_bytes());
+
+        let mut iq = Vec::new();
+        write_iq(&ch1, &ch2, &mut iq).unwrap();
+        assert_eq!(iq.len(), 8 * ch1.len());
+        assert_eq!(iq[4..8], ch2.samples[0].to_le_bytes());
+        let error = write_iq(
+            &ch1,
+            &Trace {
+                samples: vec![0.0],
+                ..ch2
+            },
+            io::sink(),
+        )
+        .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(
+            error.to_string(),
+            Error::LengthMismatch(ch1.len(), 1).to_string()
+        );
+
+        let mut meta = Vec::new();
+        write_sigmf_meta(&ch1, true, &mut meta).unwrap();
+        let meta = String::from_utf8(meta).unwrap();
+        assert!(meta.contains(r#""core:datatype":"cf32_le","core:sample_rate":100000000,"#));
+    }
+}
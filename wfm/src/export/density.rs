@@ -0,0 +1,110 @@
+/*!
+
+Density map export
+
+Maps are written with highest row first, as images are shown. CSV holds
+counts with values of cell centers in first row and column. PNG colors
+counts on logarithmic scale from black through red and yellow to white, so
+rarely hit cells remain visible.
+
+*/
+use crate::analysis::xy::Density;
+use std::io::{Result, Write};
+
+/// Center of cell along axis
+fn center(range: &core::ops::Range<f32>, size: usize, index: usize) -> f32 {
+    range.start + (index as f32 + 0.5) * (range.end - range.start) / size as f32
+}
+
+/// Write counts of density map as CSV
+pub fn write_density_csv(density: &Density, mut writer: impl Write) -> Result<()> {
+    write!(writer, "y\\x")?;
+    for column in 0..density.width {
+        write!(
+            writer,
+            ",{}",
+            center(&density.x_range, density.width, column)
+        )?;
+    }
+    writeln!(writer)?;
+
+    for row in (0..density.height).rev() {
+        write!(writer, "{}", center(&density.y_range, density.height, row))?;
+        for column in 0..density.width {
+            write!(writer, ",{}", density.count(column, row))?;
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// Color of count relative to maximum
+fn heat(count: u32, max: u32) -> [u8; 3] {
+    if count == 0 {
+        return [0; 3];
+    }
+    let level = ((1.0 + count as f32).ln() / (1.0 + max as f32).ln()).min(1.0);
+    let channel = |offset: f32| ((3.0 * level - offset).clamp(0.0, 1.0) * 255.0) as u8;
+    [channel(0.0), channel(1.0), channel(2.0)]
+}
+
+/// RGB pixels of density map, highest row first
+pub fn density_pixels(density: &Density) -> Vec<u8> {
+    let max = density.max();
+    (0..density.height)
+        .rev()
+        .flat_map(|row| (0..density.width).map(move |column| (column, row)))
+        .flat_map(|(column, row)| heat(density.count(column, row), max).to_vec())
+        .collect()
+}
+
+/// Write density map as PNG image with pixel per cell
+#[cfg(feature = "png")]
+pub fn write_density_png(density: &Density, writer: impl Write) -> Result<()> {
+    use std::io::Error;
+
+    let error = |error| match error {
+        png::EncodingError::IoError(error) => error,
+        error => Error::other(error),
+    };
+    let mut encoder = png::Encoder::new(writer, density.width as u32, density.height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header().map_err(error)?;
+    writer
+        .write_image_data(&density_pixels(density))
+        .map_err(error)?;
+    writer.finish().map_err(error)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn map() {
+        let mut density = Density::new(2, 2, 0.0..2.0, 0.0..1.0);
+        density.add(&[(0.5, 0.25), (1.5, 0.75), (1.5, 0.75)]);
+
+        let mut csv = Vec::new();
+        write_density_csv(&density, &mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "y\\x,0.5,1.5\n0.75,0,2\n0.25,1,0\n"
+        );
+
+        let pixels = density_pixels(&density);
+        assert_eq!(pixels.len(), 12);
+        // Top right cell is hottest, top left is empty
+        assert_eq!(pixels[3..6], [255, 255, 255]);
+        assert_eq!(pixels[..3], [0, 0, 0]);
+
+        #[cfg(feature = "png")]
+        {
+            let mut png = Vec::new();
+            write_density_png(&density, &mut png).unwrap();
+            assert_eq!(png[1..4], *b"PNG");
+        }
+    }
+}
@@ -0,0 +1,144 @@
+/*!
+
+Label track export
+
+Decoded events are written as tab separated `start`, `end` and `label`
+rows in seconds, as Audacity label tracks are stored.
+
+*/
+use crate::{
+    analysis::edge::Edge,
+    decode::{parallel::Word, uart::Frame},
+};
+use std::io::{Result, Write};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Labeled time span
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Label {
+    /// Start time in seconds relative to trigger
+    pub start: f64,
+    /// End time in seconds relative to trigger, same as start for points
+    pub end: f64,
+    pub text: String,
+}
+
+impl Label {
+    /// Label of time span
+    pub fn span(start: f64, end: f64, text: impl Into<String>) -> Self {
+        Self {
+            start,
+            end,
+            text: text.into(),
+        }
+    }
+
+    /// Label of time point
+    pub fn point(time: f64, text: impl Into<String>) -> Self {
+        Self::span(time, time, text)
+    }
+}
+
+impl From<&Frame> for Label {
+    fn from(frame: &Frame) -> Self {
+        let mut text = format!("0x{:02x}", frame.data);
+        if frame.parity_error {
+            text.push_str(" parity error");
+        }
+        if frame.framing_error {
+            text.push_str(" framing error");
+        }
+        Self::span(frame.start as f64, frame.end as f64, text)
+    }
+}
+
+impl From<&Word> for Label {
+    fn from(word: &Word) -> Self {
+        Self::point(word.time as f64, format!("0x{:04x}", word.value))
+    }
+}
+
+impl From<&Edge> for Label {
+    fn from(edge: &Edge) -> Self {
+        Self::point(edge.time, if edge.rising { "rising" } else { "falling" })
+    }
+}
+
+#[cfg(feature = "sigrok")]
+impl From<&crate::decode::sigrok::Event> for Label {
+    fn from(event: &crate::decode::sigrok::Event) -> Self {
+        Self::span(
+            event.start as f64,
+            event.end as f64,
+            format!("{}: {}", event.decoder, event.text),
+        )
+    }
+}
+
+/// Write labels with times relative to origin
+///
+/// Audio tools place tracks at zero time, so origin should be time of first
+/// sample of exported trace to align labels with it.
+pub fn write_labels<L: Into<Label>>(
+    labels: impl IntoIterator<Item = L>,
+    origin: f64,
+    mut writer: impl Write,
+) -> Result<()> {
+    for label in labels {
+        let label = label.into();
+        // Keep one label per row
+        let text = label.text.replace(['\t', '\n'], " ");
+        writeln!(
+            writer,
+            "{:.9}\t{:.9}\t{}",
+            label.start - origin,
+            label.end - origin,
+            text
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn uart_frames() {
+        let frames = [
+            Frame {
+                start: -1.0e-3,
+                end: 0.0,
+                data: 0x48,
+                parity_error: false,
+                framing_error: true,
+            },
+            Frame {
+                start: 0.0,
+                end: 1.0e-3,
+                data: 0x69,
+                parity_error: false,
+                framing_error: false,
+            },
+        ];
+        let mut output = Vec::new();
+        write_labels(frames.iter(), frames[0].start as f64, &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "0.000000000\t0.001000000\t0x48 framing error\n\
+             0.001000000\t0.002000000\t0x69\n"
+        );
+        assert_eq!(
+            Label::from(&Frame {
+                data: 0x1ff,
+                ..frames[1]
+            })
+            .text,
+            "0x1ff"
+        );
+    }
+}
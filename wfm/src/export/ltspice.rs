@@ -0,0 +1,124 @@
+/*!
+
+LTspice raw export
+
+Enabled channels are written as transient analysis in binary `.raw` format
+with header in UTF-16 as LTspice writes it, so captures can be added to
+simulation plots. Sign of time values flags compressed points in this
+format, so time starts at zero for first sample instead of trigger.
+
+*/
+use super::{invalid_input, traces};
+use crate::{Channel, Error, Unit, WaveformData};
+use std::io::{Result, Write};
+
+/// Variable name and type of channel
+fn variable(channel: Channel, unit: Unit) -> String {
+    let number = channel as u8 + 1;
+    match unit {
+        Unit::A => format!("I(ch{})\tdevice_current", number),
+        _ => format!("V(ch{})\tvoltage", number),
+    }
+}
+
+/// Write enabled channels as LTspice binary raw file
+///
+/// Channels should have same sample rate and length.
+pub fn write_ltspice_raw(data: &WaveformData, mut writer: impl Write) -> Result<()> {
+    let traces = traces(data);
+    let (_, first) = traces
+        .first()
+        .ok_or_else(|| invalid_input(Error::ChannelDisabled(Channel::Ch1)))?;
+    for (_, trace) in &traces[1..] {
+        if trace.sample_rate_hz != first.sample_rate_hz {
+            return Err(invalid_input(Error::SampleRateMismatch(
+                first.sample_rate_hz,
+                trace.sample_rate_hz,
+            )));
+        }
+        if trace.len() != first.len() {
+            return Err(invalid_input(Error::LengthMismatch(
+                first.len(),
+                trace.len(),
+            )));
+        }
+    }
+
+    let mut header = format!(
+        "Title: {} capture\n\
+         Plotname: Transient Analysis\n\
+         Flags: real forward\n\
+         No. Variables: {}\n\
+         No. Points: {}\n\
+         Offset: 0.0000000000000000e+000\n\
+         Command: rigol-wfm\n\
+         Variables:\n\
+         \t0\ttime\ttime\n",
+        data.header.model.name(),
+        traces.len() + 1,
+        first.len()
+    );
+    for (index, (channel, trace)) in traces.iter().enumerate() {
+        header.push_str(&format!(
+            "\t{}\t{}\n",
+            index + 1,
+            variable(*channel, trace.unit)
+        ));
+    }
+    header.push_str("Binary:\n");
+
+    let header = header
+        .encode_utf16()
+        .flat_map(|unit| unit.to_le_bytes().to_vec())
+        .collect::<Vec<_>>();
+    writer.write_all(&header)?;
+
+    // Time is double, other variables are single floats
+    let time_step = 1.0 / first.sample_rate_hz as f64;
+    for index in 0..first.len() {
+        writer.write_all(&(index as f64 * time_step).to_le_bytes())?;
+        for (_, trace) in &traces {
+            writer.write_all(&trace.samples[index].to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse;
+    use std::fs::read;
+
+    #[test]
+    fn ds1052e_2ch() {
+        let i = read("test/ds1052e_2ch.wfm").unwrap();
+        let r = parse(&i).unwrap();
+        let ch2 = r.trace(Channel::Ch2).unwrap();
+
+        let mut raw = Vec::new();
+        write_ltspice_raw(&r, &mut raw).unwrap();
+
+        let marker = "Binary:\n"
+            .encode_utf16()
+            .flat_map(|unit| unit.to_le_bytes().to_vec())
+            .collect::<Vec<_>>();
+        let start = raw
+            .windows(marker.len())
+            .position(|window| window == &marker[..])
+            .unwrap()
+            + marker.len();
+        let header = raw[..start]
+            .chunks(2)
+            .map(|unit| unit[0] as char)
+            .collect::<String>();
+        assert!(header.contains("No. Variables: 3\nNo. Points: 524284\n"));
+        assert!(header.contains("\t2\tV(ch2)\tvoltage\n"));
+
+        // Point is time and two samples
+        assert_eq!(raw.len() - start, 16 * ch2.len());
+        let second = start + 16;
+        assert_eq!(raw[second..second + 8], 1.0e-8f64.to_le_bytes());
+        assert_eq!(raw[second + 12..second + 16], ch2.samples[1].to_le_bytes());
+    }
+}
@@ -0,0 +1,235 @@
+/*!
+
+NI TDMS export
+
+Captures are written to single segment, each one as group with header
+settings in properties and channel per enabled analog trace. Channels are
+LabVIEW waveforms with `wf_increment` and `wf_start_offset` properties, so
+time axis is restored by tools reading them.
+
+*/
+use crate::{Channel, WaveformData};
+use std::io::{Result, Write};
+
+/// Lead-in tag of segment
+const TAG: &[u8; 4] = b"TDSm";
+
+/// Segment contains metadata
+const TOC_META_DATA: u32 = 1 << 1;
+/// Segment contains new object list
+const TOC_NEW_OBJ_LIST: u32 = 1 << 2;
+/// Segment contains raw data
+const TOC_RAW_DATA: u32 = 1 << 3;
+
+/// File format version 2.0
+const VERSION: u32 = 4713;
+
+/// Object without raw data in segment
+const NO_RAW_DATA: u32 = 0xFFFF_FFFF;
+
+/// Data types
+const TYPE_I32: u32 = 3;
+const TYPE_SINGLE: u32 = 9;
+const TYPE_DOUBLE: u32 = 10;
+const TYPE_STRING: u32 = 0x20;
+const TYPE_BOOLEAN: u32 = 0x21;
+
+/// Property value
+enum Value {
+    I32(i32),
+    Double(f64),
+    String(String),
+    Boolean(bool),
+}
+
+/// Segment metadata being built
+#[derive(Default)]
+struct Metadata {
+    objects: u32,
+    bytes: Vec<u8>,
+}
+
+impl Metadata {
+    fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn string(&mut self, value: &str) {
+        self.u32(value.len() as u32);
+        self.bytes.extend_from_slice(value.as_bytes());
+    }
+
+    /// Add object with optional number of single float values
+    fn object(&mut self, path: &str, values: Option<usize>, properties: &[(&str, Value)]) {
+        self.objects += 1;
+        self.string(path);
+
+        match values {
+            Some(values) => {
+                // Length of index, type, dimension and number of values
+                self.u32(20);
+                self.u32(TYPE_SINGLE);
+                self.u32(1);
+                self.bytes.extend_from_slice(&(values as u64).to_le_bytes());
+            }
+            None => self.u32(NO_RAW_DATA),
+        }
+
+        self.u32(properties.len() as u32);
+        for (name, value) in properties {
+            self.string(name);
+            match value {
+                Value::I32(value) => {
+                    self.u32(TYPE_I32);
+                    self.bytes.extend_from_slice(&value.to_le_bytes());
+                }
+                Value::Double(value) => {
+                    self.u32(TYPE_DOUBLE);
+                    self.bytes.extend_from_slice(&value.to_le_bytes());
+                }
+                Value::String(value) => {
+                    self.u32(TYPE_STRING);
+                    self.string(value);
+                }
+                Value::Boolean(value) => {
+                    self.u32(TYPE_BOOLEAN);
+                    self.bytes.push(*value as u8);
+                }
+            }
+        }
+    }
+}
+
+/// Object path component with quotes escaped
+fn name(name: &str) -> String {
+    format!("'{}'", name.replace('\'', "''"))
+}
+
+/// Write captures as TDMS file, each in group of given name
+pub fn write_tdms<'a>(
+    captures: impl IntoIterator<Item = (&'a str, &'a WaveformData)>,
+    mut writer: impl Write,
+) -> Result<()> {
+    let mut metadata = Metadata::default();
+    let mut traces = Vec::new();
+    metadata.object("/", None, &[]);
+
+    for (group, data) in captures {
+        let summary = data.summary();
+        let group = format!("/{}", name(group));
+        let trigger = &summary.trigger;
+        metadata.object(
+            &group,
+            None,
+            &[
+                ("model", Value::String(summary.model.name().into())),
+                (
+                    "sample_rate_hz",
+                    Value::Double(summary.sample_rate_hz as f64),
+                ),
+                ("time_scale", Value::Double(summary.time_scale as f64)),
+                ("time_offset", Value::Double(summary.time_offset as f64)),
+                ("trigger_mode", Value::String(format!("{:?}", trigger.mode))),
+                (
+                    "trigger_source",
+                    Value::String(format!("{:?}", trigger.source)),
+                ),
+                (
+                    "trigger_coupling",
+                    Value::String(format!("{:?}", trigger.coupling)),
+                ),
+                ("trigger_level", Value::Double(trigger.level as f64)),
+            ],
+        );
+
+        for channel in &summary.channels {
+            let trace = match data.trace(channel.channel) {
+                Some(trace) => trace,
+                None => continue,
+            };
+            let channel_name = match channel.channel {
+                Channel::Ch1 => "CH1",
+                Channel::Ch2 => "CH2",
+            };
+            metadata.object(
+                &format!("{}/{}", group, name(channel_name)),
+                Some(trace.len()),
+                &[
+                    ("wf_increment", Value::Double(trace.time_step() as f64)),
+                    ("wf_start_offset", Value::Double(trace.time_offset as f64)),
+                    ("wf_samples", Value::I32(trace.len() as i32)),
+                    ("unit_string", Value::String(format!("{:?}", trace.unit))),
+                    ("scale", Value::Double(channel.scale as f64)),
+                    ("center", Value::Double(channel.center as f64)),
+                    ("probe", Value::Double(channel.probe as f64)),
+                    ("inverted", Value::Boolean(channel.inverted)),
+                ],
+            );
+            traces.push(trace);
+        }
+    }
+
+    let raw_size = traces
+        .iter()
+        .map(|trace| 4 * trace.len() as u64)
+        .sum::<u64>();
+    let metadata_size = 4 + metadata.bytes.len() as u64;
+
+    writer.write_all(TAG)?;
+    writer.write_all(&(TOC_META_DATA | TOC_NEW_OBJ_LIST | TOC_RAW_DATA).to_le_bytes())?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    writer.write_all(&(metadata_size + raw_size).to_le_bytes())?;
+    writer.write_all(&metadata_size.to_le_bytes())?;
+
+    writer.write_all(&metadata.objects.to_le_bytes())?;
+    writer.write_all(&metadata.bytes)?;
+
+    for trace in &traces {
+        for sample in &trace.samples {
+            writer.write_all(&sample.to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse;
+    use std::fs::read;
+
+    fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes([
+            bytes[offset],
+            bytes[offset + 1],
+            bytes[offset + 2],
+            bytes[offset + 3],
+        ])
+    }
+
+    #[test]
+    fn ds1052e_2ch() {
+        let i = read("test/ds1052e_2ch.wfm").unwrap();
+        let r = parse(&i).unwrap();
+        let ch1 = r.trace(Channel::Ch1).unwrap();
+
+        let mut tdms = Vec::new();
+        write_tdms(vec![("it's", &r)], &mut tdms).unwrap();
+
+        assert_eq!(&tdms[..4], TAG);
+        assert_eq!(u32_at(&tdms, 4), 0b1110);
+        let next = u32_at(&tdms, 12) as usize;
+        let raw = u32_at(&tdms, 20) as usize;
+        assert_eq!(28 + next, tdms.len());
+        assert_eq!(next - raw, 8 * ch1.len());
+
+        // Root, group and two channels
+        assert_eq!(u32_at(&tdms, 28), 4);
+        let metadata = String::from_utf8_lossy(&tdms[28..28 + raw]);
+        assert!(metadata.contains("/'it''s'/'CH2'"));
+        assert!(metadata.contains("wf_increment"));
+
+        let first = 28 + raw;
+        assert_eq!(tdms[first..first + 4], ch1.samples[0].to_le_bytes());
+    }
+}
@@ -0,0 +1,276 @@
+/*!
+
+C interface
+
+Declarations are in `include/rigol_wfm.h`. Shared library is built with
+`cargo rustc -p rigol-wfm --lib --features ffi --crate-type cdylib`.
+
+*/
+use crate::{parse, Channel, ChannelHeader, WaveformData};
+use core::{convert::TryFrom, ptr, slice};
+use std::{
+    ffi::CString,
+    os::raw::{c_char, c_int},
+};
+
+/// Parsed waveform handle
+pub struct RigolWfm(WaveformData);
+
+impl RigolWfm {
+    fn channel(&self, channel: c_int) -> Option<(Channel, &ChannelHeader)> {
+        let channel = Channel::try_from(u8::try_from(channel).ok()?).ok()?;
+        let header = match channel {
+            Channel::Ch1 => &self.0.header.ch1,
+            Channel::Ch2 => &self.0.header.ch2,
+        };
+        Some((channel, header))
+    }
+
+    fn raw(&self, channel: Channel) -> &[u8] {
+        self.0.data.valid(channel)
+    }
+}
+
+/// Parse waveform file contents
+///
+/// Returns null on failure and stores error message to `error` when it is not
+/// null. Message should be released using [`rigol_wfm_string_free`].
+///
+/// # Safety
+///
+/// `data` should point to `length` readable bytes, `error` should be null or
+/// point to writable pointer.
+#[no_mangle]
+pub unsafe extern "C" fn rigol_wfm_parse(
+    data: *const u8,
+    length: usize,
+    error: *mut *mut c_char,
+) -> *mut RigolWfm {
+    if !error.is_null() {
+        *error = ptr::null_mut();
+    }
+    if data.is_null() {
+        return ptr::null_mut();
+    }
+
+    match parse(slice::from_raw_parts(data, length)) {
+        Ok(data) => Box::into_raw(Box::new(RigolWfm(data))),
+        Err(message) => {
+            if !error.is_null() {
+                *error = CString::new(message.to_string())
+                    .map(CString::into_raw)
+                    .unwrap_or(ptr::null_mut());
+            }
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Release waveform
+///
+/// # Safety
+///
+/// `wfm` should be null or returned by [`rigol_wfm_parse`] and not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn rigol_wfm_free(wfm: *mut RigolWfm) {
+    if !wfm.is_null() {
+        drop(Box::from_raw(wfm));
+    }
+}
+
+/// Release error message
+///
+/// # Safety
+///
+/// `string` should be null or returned by this library and not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn rigol_wfm_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+macro_rules! getters {
+    ( $( $(#[$meta:meta])* $name:ident -> $type:ty: |$wfm:ident| $body:expr, )* ) => {
+        $(
+            $(#[$meta])*
+            ///
+            /// # Safety
+            ///
+            /// `wfm` should be valid waveform handle.
+            #[no_mangle]
+            pub unsafe extern "C" fn $name(wfm: *const RigolWfm) -> $type {
+                let $wfm = &(*wfm).0;
+                $body
+            }
+        )*
+    };
+}
+
+getters! {
+    /// Active channel
+    rigol_wfm_active_channel -> c_int: |wfm| wfm.header.active_channel as c_int,
+    /// Sample rate in Hz
+    rigol_wfm_sample_rate -> f32: |wfm| wfm.header.time.sample_rate_hz,
+    /// Horizontal scale in seconds per division
+    rigol_wfm_time_scale -> f32: |wfm| wfm.header.time.scale(),
+    /// Horizontal offset in seconds
+    rigol_wfm_time_offset -> f32: |wfm| wfm.header.time.offset(),
+}
+
+/// Channel is enabled (0 for CH1, 1 for CH2)
+///
+/// # Safety
+///
+/// `wfm` should be valid waveform handle.
+#[no_mangle]
+pub unsafe extern "C" fn rigol_wfm_channel_enabled(wfm: *const RigolWfm, channel: c_int) -> bool {
+    (*wfm)
+        .channel(channel)
+        .map(|(_, header)| header.enabled)
+        .unwrap_or(false)
+}
+
+/// Vertical scale of channel in units per ADC step, NaN for invalid channel
+///
+/// # Safety
+///
+/// `wfm` should be valid waveform handle.
+#[no_mangle]
+pub unsafe extern "C" fn rigol_wfm_volt_scale(wfm: *const RigolWfm, channel: c_int) -> f32 {
+    (*wfm)
+        .channel(channel)
+        .map(|(_, header)| header.volt_scale)
+        .unwrap_or(f32::NAN)
+}
+
+/// Vertical offset of channel in units, NaN for invalid channel
+///
+/// # Safety
+///
+/// `wfm` should be valid waveform handle.
+#[no_mangle]
+pub unsafe extern "C" fn rigol_wfm_volt_offset(wfm: *const RigolWfm, channel: c_int) -> f32 {
+    (*wfm)
+        .channel(channel)
+        .map(|(_, header)| header.volt_offset)
+        .unwrap_or(f32::NAN)
+}
+
+/// Raw ADC samples of channel
+///
+/// Stores number of samples to `length` and returns pointer which is valid
+/// until waveform is released. Returns null for invalid channel.
+///
+/// # Safety
+///
+/// `wfm` should be valid waveform handle, `length` should point to writable
+/// value.
+#[no_mangle]
+pub unsafe extern "C" fn rigol_wfm_raw_samples(
+    wfm: *const RigolWfm,
+    channel: c_int,
+    length: *mut usize,
+) -> *const u8 {
+    match (*wfm).channel(channel) {
+        Some((channel, _)) => {
+            let raw = (*wfm).raw(channel);
+            *length = raw.len();
+            raw.as_ptr()
+        }
+        None => {
+            *length = 0;
+            ptr::null()
+        }
+    }
+}
+
+/// Copy scaled samples of channel to buffer
+///
+/// Returns total number of samples, so call with null buffer can be used to
+/// get required capacity. At most `capacity` samples are copied.
+///
+/// # Safety
+///
+/// `wfm` should be valid waveform handle, `buffer` should be null or point to
+/// `capacity` writable values.
+#[no_mangle]
+pub unsafe extern "C" fn rigol_wfm_samples(
+    wfm: *const RigolWfm,
+    channel: c_int,
+    buffer: *mut f32,
+    capacity: usize,
+) -> usize {
+    let (channel, header) = match (*wfm).channel(channel) {
+        Some(channel) => channel,
+        None => return 0,
+    };
+    if !header.enabled {
+        return 0;
+    }
+    let raw = (*wfm).raw(channel);
+
+    if !buffer.is_null() {
+        let buffer = slice::from_raw_parts_mut(buffer, capacity.min(raw.len()));
+        for (target, &raw) in buffer.iter_mut().zip(raw) {
+            *target = header.volts(raw);
+        }
+    }
+    raw.len()
+}
+
+/// Time of first sample of channel in seconds relative to trigger, NaN when
+/// channel is disabled
+///
+/// # Safety
+///
+/// `wfm` should be valid waveform handle.
+#[no_mangle]
+pub unsafe extern "C" fn rigol_wfm_start_time(wfm: *const RigolWfm, channel: c_int) -> f32 {
+    (*wfm)
+        .channel(channel)
+        .and_then(|(channel, _)| (*wfm).0.trace(channel))
+        .map(|trace| trace.time_offset)
+        .unwrap_or(f32::NAN)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::{ffi::CStr, fs::read};
+
+    #[test]
+    fn ds1052e_2ch() {
+        let i = read("test/ds1052e_2ch.wfm").unwrap();
+
+        unsafe {
+            let mut error = ptr::null_mut();
+            let wfm = rigol_wfm_parse(i.as_ptr(), i.len(), &mut error);
+            assert!(!wfm.is_null());
+            assert!(error.is_null());
+
+            assert!(rigol_wfm_channel_enabled(wfm, 1));
+            assert!(!rigol_wfm_channel_enabled(wfm, 2));
+
+            let mut length = 0;
+            let raw = rigol_wfm_raw_samples(wfm, 0, &mut length);
+            assert_eq!(length, 524284);
+            assert_eq!(*raw, i[276]);
+
+            let total = rigol_wfm_samples(wfm, 1, ptr::null_mut(), 0);
+            let mut samples = vec![0.0; total];
+            rigol_wfm_samples(wfm, 1, samples.as_mut_ptr(), samples.len());
+            assert_eq!(samples, (*wfm).0.trace(Channel::Ch2).unwrap().samples);
+
+            rigol_wfm_free(wfm);
+
+            let wfm = rigol_wfm_parse(i.as_ptr(), 100, &mut error);
+            assert!(wfm.is_null());
+            assert!(CStr::from_ptr(error)
+                .to_str()
+                .unwrap()
+                .starts_with("Truncated header"));
+            rigol_wfm_string_free(error);
+        }
+    }
+}
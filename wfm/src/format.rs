@@ -0,0 +1,142 @@
+/*!
+
+File formats abstraction
+
+*/
+use super::{Ds1000e, Error, RawData, Result, WaveformData, WaveformHeader};
+use std::io::Write;
+
+/// Waveform file format
+///
+/// Implement it to handle formats which this crate does not support and add
+/// to [`Registry`].
+pub trait WfmFormat: Send + Sync {
+    /// Human readable name
+    fn name(&self) -> &'static str;
+
+    /// Input looks like file of this format
+    fn sniff(&self, input: &[u8]) -> bool;
+
+    /// Parse header from whole input
+    fn parse_header(&self, input: &[u8]) -> Result<WaveformHeader>;
+
+    /// Parse samples from whole input
+    fn parse_data(&self, header: &WaveformHeader, input: &[u8]) -> Result<RawData>;
+
+    /// Write waveform in this format
+    fn write(&self, _data: &WaveformData, _writer: &mut dyn Write) -> Result<()> {
+        Err(Error::Unsupported(format!(
+            "writing of {} format",
+            self.name()
+        )))
+    }
+
+    /// Parse whole input
+    fn parse(&self, input: &[u8]) -> Result<WaveformData> {
+        let header = self.parse_header(input)?;
+        let data = self.parse_data(&header, input)?;
+        Ok(WaveformData { header, data })
+    }
+}
+
+/// Set of known formats
+pub struct Registry {
+    formats: Vec<Box<dyn WfmFormat>>,
+}
+
+impl Default for Registry {
+    /// Registry with formats supported by crate
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register(Ds1000e);
+        registry
+    }
+}
+
+impl Registry {
+    /// Registry with formats supported by crate
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry without formats
+    pub fn empty() -> Self {
+        Self {
+            formats: Vec::new(),
+        }
+    }
+
+    /// Add format, formats are tried in order of registration
+    pub fn register(&mut self, format: impl WfmFormat + 'static) {
+        self.formats.push(Box::new(format));
+    }
+
+    /// Registered formats
+    pub fn formats(&self) -> impl Iterator<Item = &dyn WfmFormat> {
+        self.formats.iter().map(|format| format.as_ref())
+    }
+
+    /// Find format of input
+    pub fn detect(&self, input: &[u8]) -> Option<&dyn WfmFormat> {
+        self.formats().find(|format| format.sniff(input))
+    }
+
+    /// Parse input of any registered format
+    pub fn parse(&self, input: &[u8]) -> Result<WaveformData> {
+        self.detect(input)
+            .ok_or_else(|| Error::Unsupported("file format".into()))?
+            .parse(input)
+    }
+}
+
+/// Parse input of any format supported by crate
+pub fn parse_any(input: &[u8]) -> Result<WaveformData> {
+    Registry::default().parse(input)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs::read;
+
+    struct Empty;
+
+    impl WfmFormat for Empty {
+        fn name(&self) -> &'static str {
+            "Empty"
+        }
+
+        fn sniff(&self, input: &[u8]) -> bool {
+            input.is_empty()
+        }
+
+        fn parse_header(&self, _input: &[u8]) -> Result<WaveformHeader> {
+            Err(Error::Unsupported("empty file".into()))
+        }
+
+        fn parse_data(&self, _header: &WaveformHeader, _input: &[u8]) -> Result<RawData> {
+            Ok(RawData::default())
+        }
+    }
+
+    #[test]
+    fn registry() {
+        let i = read("test/ds1052e_2ch.wfm").unwrap();
+        let r = parse_any(&i).unwrap();
+        assert_eq!(r.data.ch1.len(), 524284);
+
+        let mut g = Registry::new();
+        assert_eq!(g.detect(&i).unwrap().name(), "Rigol DS1000E");
+        assert_eq!(
+            g.parse(&[]).unwrap_err(),
+            Error::Unsupported("file format".into())
+        );
+        g.register(Empty);
+        assert_eq!(g.detect(&[]).unwrap().name(), "Empty");
+        assert!(g.detect(&[]).unwrap().write(&r, &mut Vec::new()).is_err());
+
+        let mut o = Vec::new();
+        g.detect(&i).unwrap().write(&r, &mut o).unwrap();
+        assert_eq!(o.len(), i.len());
+    }
+}
@@ -0,0 +1,200 @@
+/*!
+
+Random waveforms for fuzzing and property tests
+
+Generated waveforms are consistent, so writing and parsing them back gives
+the same values.
+
+*/
+use super::{
+    write, ChannelHeader, LogicAnalyzerHeader, LogicLength, Model, RawData, TimeHeader,
+    TriggerHeader, Unit, WaveformData, WaveformHeader,
+};
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+/// Maximum number of points of generated channels
+pub const MAX_POINTS: u32 = 1 << 12;
+
+/// Probe attenuations supported by instrument
+const PROBES: [f32; 7] = [1.0, 5.0, 10.0, 50.0, 100.0, 500.0, 1000.0];
+
+/// Finite float
+fn float(u: &mut Unstructured) -> Result<f32> {
+    let value = f32::arbitrary(u)?;
+    Ok(if value.is_finite() { value } else { 0.0 })
+}
+
+impl<'a> Arbitrary<'a> for ChannelHeader {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(ChannelHeader {
+            scale_display: u.arbitrary()?,
+            shift_display: u.arbitrary()?,
+            probe_value: *u.choose(&PROBES)?,
+            invert_display: u.arbitrary()?,
+            scale_measured: u.arbitrary()?,
+            shift_measured: u.arbitrary()?,
+            inverted: u.arbitrary()?,
+            enabled: u.arbitrary()?,
+            volt_per_division: 0.0,
+            volt_scale: 0.0,
+            volt_offset: 0.0,
+            unit: Unit::V,
+        }
+        .scaled())
+    }
+}
+
+impl<'a> Arbitrary<'a> for TimeHeader {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(TimeHeader {
+            scale_display: u.arbitrary()?,
+            offset_display: u.arbitrary()?,
+            sample_rate_hz: float(u)?,
+            scale_measured: u.arbitrary()?,
+            offset_measured: u.arbitrary()?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for TriggerHeader {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(TriggerHeader {
+            mode: u.arbitrary()?,
+            source: u.arbitrary()?,
+            coupling: u.arbitrary()?,
+            sweep: u.arbitrary()?,
+            sens: float(u)?,
+            holdoff: float(u)?,
+            level: float(u)?,
+            direct: u.arbitrary()?,
+            pulse_type: u.arbitrary()?,
+            pulse_width: float(u)?,
+            slope_type: u.arbitrary()?,
+            lower: float(u)?,
+            slope_width: float(u)?,
+            video_pol: u.arbitrary()?,
+            video_sync: u.arbitrary()?,
+            video_std: u.arbitrary()?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for LogicAnalyzerHeader {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(LogicAnalyzerHeader {
+            enabled: u.arbitrary()?,
+            active_channel: u.arbitrary()?,
+            enabled_channels: u.arbitrary()?,
+            position: u.arbitrary()?,
+            group8to15size: u.arbitrary()?,
+            group0to7size: u.arbitrary()?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for WaveformHeader {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let ch1 = ChannelHeader::arbitrary(u)?;
+        let ch2 = ChannelHeader::arbitrary(u)?;
+        let logic = LogicAnalyzerHeader::arbitrary(u)?;
+
+        let roll_stop = if u.arbitrary()? {
+            u.int_in_range(1..=MAX_POINTS)?
+        } else {
+            0
+        };
+        let ch1_points = u.int_in_range(0..=MAX_POINTS)?;
+        // Zero means same as first channel when it is enabled
+        let ch2_points = u.int_in_range(if ch1.enabled { 1 } else { 0 }..=MAX_POINTS)?;
+        let logic_length = LogicLength::written(logic.enabled, ch1_points);
+
+        Ok(WaveformHeader {
+            model: if logic.enabled {
+                Model::Ds1000D
+            } else {
+                Model::Ds1000E
+            },
+            adc_mode: u.arbitrary()?,
+            roll_stop,
+            active_channel: u.arbitrary()?,
+            ch1,
+            ch2,
+            time: u.arbitrary()?,
+            time2: u.arbitrary()?,
+            trigger1: u.arbitrary()?,
+            trigger2: u.arbitrary()?,
+            logic,
+            logic_sample_rate_hz: float(u)?,
+            trigger_mode: u.arbitrary()?,
+            ch1_points,
+            ch1_skip: if roll_stop == 0 { 0 } else { roll_stop + 2 },
+            ch2_points,
+            logic_length,
+            raw: None,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for WaveformData {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let header = WaveformHeader::arbitrary(u)?;
+
+        fn samples<'a, T: Arbitrary<'a>>(
+            u: &mut Unstructured<'a>,
+            enabled: bool,
+            points: u32,
+        ) -> Result<Vec<T>> {
+            if enabled {
+                (0..points).map(|_| T::arbitrary(u)).collect()
+            } else {
+                Ok(Vec::new())
+            }
+        }
+
+        let data = RawData {
+            ch1: samples(u, header.ch1.enabled, header.ch1_points)?.into(),
+            ch2: samples(u, header.ch2.enabled, header.ch2_points)?.into(),
+            logic: samples(u, header.logic.enabled, header.ch1_points)?.into(),
+            invalid: 0,
+        };
+
+        Ok(WaveformData { header, data })
+    }
+}
+
+/// Contents of valid file with random waveform
+pub fn arbitrary_bytes(u: &mut Unstructured) -> Result<Vec<u8>> {
+    let data = WaveformData::arbitrary(u)?;
+    let mut bytes = Vec::new();
+    write(&data, &mut bytes).expect("Writing to memory does not fail");
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn round_trip() {
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        for _ in 0..256 {
+            let input = (0..1024)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    state as u8
+                })
+                .collect::<Vec<_>>();
+
+            let data = WaveformData::arbitrary(&mut Unstructured::new(&input)).unwrap();
+            let mut bytes = Vec::new();
+            write(&data, &mut bytes).unwrap();
+            assert_eq!(parse(&bytes).unwrap(), data);
+
+            let bytes = arbitrary_bytes(&mut Unstructured::new(&input)).unwrap();
+            assert!(parse(&bytes).is_ok());
+        }
+    }
+}
@@ -0,0 +1,330 @@
+/*!
+
+Import of samples from other tools
+
+Columns of time and values in comma, semicolon or tab separated text are
+converted to capture with synthetic header, so other data can be measured,
+exported and plotted like instrument captures. Numbers may have decimal
+comma unless it separates fields. Values are quantized to 8-bit ADC codes
+with range of each channel mapped to 8 divisions on screen, so precision is
+limited as of instrument captures.
+
+*/
+use crate::{Channel, Error, RawData, Result, Unit, WaveformData, WaveformHeader};
+use std::{io::Read, str::FromStr};
+
+/// Number of horizontal divisions on screen
+const COLUMNS: f32 = 12.0;
+
+/// Number of vertical divisions covered by range of values
+const ROWS: f32 = 8.0;
+
+/// Samples of single column
+struct Column {
+    unit: Unit,
+    values: Vec<f32>,
+}
+
+/// Unit in column title like `CH1 (V)`
+fn unit(title: &str) -> Unit {
+    match title
+        .trim()
+        .rsplit('(')
+        .next()
+        .map(|unit| unit.trim_end_matches(')'))
+    {
+        Some("A") => Unit::A,
+        Some("W") => Unit::W,
+        Some("U") => Unit::U,
+        _ => Unit::V,
+    }
+}
+
+/// First line which is not comment
+fn first_line(text: &str) -> &str {
+    text.lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .unwrap_or("")
+}
+
+/// Field separator used in line
+///
+/// Comma is taken last, as it is decimal separator in files separated by
+/// others.
+fn separator(line: &str) -> char {
+    ['\t', ';', ',']
+        .iter()
+        .copied()
+        .find(|&separator| line.contains(separator))
+        .unwrap_or(',')
+}
+
+/// Parse number with decimal point or comma unless comma separates fields
+fn number<T: FromStr>(field: &str, separator: char) -> Option<T> {
+    if separator == ',' {
+        field.parse().ok()
+    } else {
+        field.replace(',', ".").parse().ok()
+    }
+}
+
+/// Read columns of times and values
+///
+/// Leading lines which do not start with number are titles, lines starting
+/// with `#` are comments. Separator is detected from first line.
+fn columns(text: &str, selected: &[usize]) -> Result<(Vec<f64>, Vec<Column>)> {
+    let separator = separator(first_line(text));
+    let mut times = Vec::new();
+    let mut columns = selected
+        .iter()
+        .map(|_| Column {
+            unit: Unit::V,
+            values: Vec::new(),
+        })
+        .collect::<Vec<_>>();
+    let mut offset = 0;
+
+    for line in text.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields = line.split(separator).map(str::trim).collect::<Vec<_>>();
+        let time = match number::<f64>(fields[0], separator) {
+            Some(time) => time,
+            None if times.is_empty() => {
+                for (column, &index) in columns.iter_mut().zip(selected) {
+                    column.unit = fields.get(index + 1).map_or(Unit::V, |title| unit(title));
+                }
+                continue;
+            }
+            None => {
+                return Err(Error::Invalid {
+                    context: "time",
+                    offset: start,
+                })
+            }
+        };
+
+        times.push(time);
+        for (column, &index) in columns.iter_mut().zip(selected) {
+            let value = fields
+                .get(index + 1)
+                .and_then(|field| number::<f32>(field, separator))
+                .ok_or(Error::Invalid {
+                    context: "value",
+                    offset: start,
+                })?;
+            column.values.push(value);
+        }
+    }
+
+    Ok((times, columns))
+}
+
+/// Volts per division and offset which map values to screen
+fn vertical(values: &[f32]) -> (f32, f32) {
+    let (min, max) = values
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), &value| {
+            (min.min(value), max.max(value))
+        });
+    let center = 0.5 * (min + max);
+    // Offset is stored in 16-bit ADC steps, 25 steps per division
+    let scale = ((max - min) / ROWS)
+        .max(25.0 * center.abs() / i16::MAX as f32)
+        .max(1.0e-6);
+    // Scale is stored in whole microvolts, rounded up to keep range on screen
+    ((1.0e6 * scale).ceil() / 1.0e6, -center)
+}
+
+/// Import capture from text with time in first column
+///
+/// Following one or two columns become first and second channel.
+pub fn from_csv(text: &str) -> Result<WaveformData> {
+    let first = first_line(text);
+    let values = first.split(separator(first)).count().saturating_sub(1);
+    let selected = (0..values.min(2)).collect::<Vec<_>>();
+    from_csv_columns(text, &selected)
+}
+
+/// Import capture from text with time in first column and selected columns
+/// of values
+///
+/// Values columns are counted from zero after time column, first one becomes
+/// first channel and second one, when given, becomes second channel.
+pub fn from_csv_columns(text: &str, selected: &[usize]) -> Result<WaveformData> {
+    if selected.is_empty() || selected.len() > 2 {
+        return Err(Error::Unsupported(format!(
+            "{} columns of values",
+            selected.len()
+        )));
+    }
+
+    let (times, columns) = columns(text, selected)?;
+    if times.len() < 2 {
+        return Err(Error::Truncated {
+            context: "samples",
+            offset: text.len(),
+            needed: None,
+        });
+    }
+
+    // Time step is taken from whole record, every sample should be within
+    // half of it from uniform grid
+    let length = times.len();
+    let step = (times[length - 1] - times[0]) / (length - 1) as f64;
+    if !step.is_finite() || step <= 0.0 {
+        return Err(Error::Invalid {
+            context: "sample interval",
+            offset: 0,
+        });
+    }
+    if let Some(index) = times
+        .iter()
+        .enumerate()
+        .position(|(index, &time)| (time - times[0] - index as f64 * step).abs() > 0.5 * step)
+    {
+        return Err(Error::Unsupported(format!(
+            "non-uniform sampling at sample {}",
+            index
+        )));
+    }
+
+    let sample_rate_hz = (1.0 / step) as f32;
+    let duration = length as f64 * step;
+    // Middle of record is in the middle of screen
+    let mut builder = WaveformHeader::builder()
+        .sample_rate(sample_rate_hz)
+        .timebase(
+            (duration / COLUMNS as f64) as f32,
+            (times[0] + 0.5 * duration) as f32,
+        );
+    let channels = [Channel::Ch1, Channel::Ch2];
+    for (&channel, column) in channels.iter().zip(&columns) {
+        let (scale, offset) = vertical(&column.values);
+        builder = builder.channel(channel, scale, offset);
+    }
+
+    let header = builder.build();
+    let mut raw = [Vec::new(), Vec::new()];
+    for ((&channel, column), raw) in channels.iter().zip(&columns).zip(&mut raw) {
+        let header = match channel {
+            Channel::Ch1 => &header.ch1,
+            Channel::Ch2 => &header.ch2,
+        };
+        *raw = column
+            .values
+            .iter()
+            .map(|&value| header.raw(value))
+            .collect();
+    }
+
+    let [ch1, ch2] = raw;
+    let mut data = builder.build_with(RawData {
+        ch1: ch1.into(),
+        ch2: ch2.into(),
+        ..Default::default()
+    });
+    for (&channel, column) in channels.iter().zip(&columns) {
+        match channel {
+            Channel::Ch1 => data.header.ch1.unit = column.unit,
+            Channel::Ch2 => data.header.ch2.unit = column.unit,
+        }
+    }
+    Ok(data)
+}
+
+/// Read and import capture from text
+pub fn read_csv(mut reader: impl Read) -> Result<WaveformData> {
+    let mut text = String::new();
+    reader
+        .read_to_string(&mut text)
+        .map_err(|error| Error::Io(error.kind()))?;
+    from_csv(&text)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{export::to_csv, parse};
+    use std::fs::read;
+
+    #[test]
+    fn ds1052e_2ch() {
+        let i = read("test/ds1052e_2ch.wfm").unwrap();
+        let r = parse(&i).unwrap();
+        let csv = to_csv(&r);
+        let imported = from_csv(&csv).unwrap();
+
+        for &channel in [Channel::Ch1, Channel::Ch2].iter() {
+            let expected = r.trace(channel).unwrap();
+            let trace = imported.trace(channel).unwrap();
+            assert_eq!(trace.len(), expected.len());
+            assert_eq!(trace.sample_rate_hz, expected.sample_rate_hz);
+            assert!((trace.time_offset - expected.time_offset).abs() < 1.0e-8);
+            // Values are requantized over their range
+            let (min, max) = expected
+                .samples
+                .iter()
+                .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), &v| {
+                    (min.min(v), max.max(v))
+                });
+            let tolerance = (max - min) / 200.0;
+            for (value, expected) in trace.samples.iter().zip(&expected.samples) {
+                assert!((value - expected).abs() <= tolerance);
+            }
+        }
+    }
+
+    #[test]
+    fn select() {
+        let csv = "# exported\ntime;a (A);b;c (V)\n0;1;9;-1\n0.001;2;9;1\n0.002;3;9;-1\n";
+        let data = from_csv_columns(csv, &[2, 0]).unwrap();
+        assert_eq!(data.header.ch1.unit, Unit::V);
+        assert_eq!(data.header.ch2.unit, Unit::A);
+
+        let ch1 = data.trace(Channel::Ch1).unwrap();
+        assert_eq!(ch1.samples, vec![-1.0, 1.0, -1.0]);
+        assert!((ch1.sample_rate_hz - 1.0e3).abs() < 1.0e-2);
+        assert!(ch1.time_offset.abs() < 1.0e-7);
+        let ch2 = data.trace(Channel::Ch2).unwrap();
+        assert!((ch2.samples[1] - 2.0).abs() < 1.0e-5);
+
+        assert!(matches!(
+            from_csv("0,1\n1,1\n2,1\n3,1\n10,1\n"),
+            Err(Error::Unsupported(_))
+        ));
+        assert_eq!(
+            from_csv("0,1\n1,x\n"),
+            Err(Error::Invalid {
+                context: "value",
+                offset: 4
+            })
+        );
+    }
+
+    #[test]
+    fn decimal_comma() {
+        let csv = "time;CH1 (V)\n0,000;1,5\n0,001;2,5\n0,002;-1\n";
+        let ch1 = from_csv(csv).unwrap().trace(Channel::Ch1).unwrap();
+        assert_eq!(ch1.len(), 3);
+        for (value, expected) in ch1.samples.iter().zip(&[1.5, 2.5, -1.0]) {
+            assert!((value - expected).abs() < 0.02);
+        }
+        assert!((ch1.sample_rate_hz - 1.0e3).abs() < 1.0e-2);
+
+        // Separator is taken from first line
+        assert!(matches!(
+            from_csv("0\t1,5\n1;2\n"),
+            Err(Error::Invalid {
+                context: "time",
+                ..
+            })
+        ));
+    }
+}
@@ -1,3 +1,46 @@
+mod builder;
+mod compare;
+mod concat;
+mod convert;
+mod error;
+mod format;
+mod logic;
+mod model;
 mod parser;
+mod probe;
+mod progress;
+mod shared;
+mod summary;
+mod trace;
+mod viewport;
 
+pub mod analysis;
+pub mod annotations;
+pub mod batch;
+pub mod decode;
+pub mod export;
+pub mod import;
+#[cfg(feature = "serde")]
+pub mod schema;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "arbitrary")]
+pub mod fuzz;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use builder::*;
+pub use compare::*;
+pub use concat::*;
+pub use error::*;
+pub use format::*;
+pub use logic::*;
+pub use model::*;
 pub use parser::*;
+pub use probe::*;
+pub use progress::*;
+pub use shared::*;
+pub use summary::*;
+pub use trace::*;
+pub use viewport::*;
@@ -0,0 +1,239 @@
+/*!
+
+Logic analyzer samples
+
+*/
+use super::{analysis::edge, Channel, Trace, WaveformData};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Digital samples of up to 16 lines
+///
+/// Each sample holds states of lines as bits, same as logic analyzer stores it.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Logic {
+    pub samples: Vec<u16>,
+    pub sample_rate_hz: f32,
+    /// Time of first sample relative to trigger in seconds
+    pub time_offset: f32,
+}
+
+impl Logic {
+    /// Number of lines in sample
+    pub const LINES: u8 = 16;
+
+    /// Number of samples
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Logic has no samples
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Time between samples in seconds
+    pub fn time_step(&self) -> f32 {
+        1.0 / self.sample_rate_hz
+    }
+
+    /// Time of sample in seconds relative to trigger
+    pub fn time(&self, index: usize) -> f32 {
+        self.time_offset + index as f32 * self.time_step()
+    }
+
+    /// State of line at sample
+    ///
+    /// Returns `None` when there is no such line.
+    pub fn bit(&self, index: usize, line: u8) -> Option<bool> {
+        if line < Self::LINES {
+            Some(self.samples[index] >> line & 1 != 0)
+        } else {
+            None
+        }
+    }
+}
+
+/// Analog to digital conversion threshold
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Threshold {
+    /// Absolute level and hysteresis in units
+    Volts { level: f32, hysteresis: f32 },
+    /// Level and hysteresis in percents of range between minimum and maximum
+    Percent { level: f32, hysteresis: f32 },
+}
+
+impl Default for Threshold {
+    fn default() -> Self {
+        Threshold::Percent {
+            level: 50.0,
+            hysteresis: 10.0,
+        }
+    }
+}
+
+impl Threshold {
+    /// Detection levels for trace
+    ///
+    /// Hysteresis is the full width of band centered on level.
+    pub fn levels(&self, trace: &Trace) -> edge::Levels {
+        match *self {
+            Threshold::Volts { level, hysteresis } => edge::Levels::new(level, hysteresis),
+            Threshold::Percent { level, hysteresis } => {
+                let (min, max) = trace
+                    .samples
+                    .iter()
+                    .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), &value| {
+                        (min.min(value), max.max(value))
+                    });
+                let range = if max > min { max - min } else { 0.0 };
+
+                edge::Levels::new(min + range * level / 100.0, range * hysteresis / 100.0)
+            }
+        }
+    }
+}
+
+impl Logic {
+    /// Combine lines of other logic with same number of samples
+    pub fn merge(&mut self, other: &Logic) {
+        for (sample, other) in self.samples.iter_mut().zip(other.samples.iter()) {
+            *sample |= other;
+        }
+    }
+}
+
+impl Trace {
+    /// Convert trace to logic with level on given line
+    ///
+    /// Returns `None` when there is no such line.
+    pub fn to_logic(&self, threshold: Threshold, line: u8) -> Option<Logic> {
+        if line >= Logic::LINES {
+            return None;
+        }
+        let levels = threshold.levels(self);
+        let mut samples = vec![0; self.len()];
+
+        let edges = edge::find_samples(&self.samples, levels);
+        let bit = |state: bool| (state as u16) << line;
+
+        // Initial state holds until first edge
+        let mut state = match (edges.first(), self.samples.first()) {
+            (Some(edge), _) => bit(!edge.rising),
+            (None, Some(&value)) => bit(value >= levels.threshold),
+            _ => 0,
+        };
+        let mut index = 0;
+
+        for edge in edges {
+            let end = (edge.position.floor() as usize + 1).min(samples.len());
+            samples[index..end]
+                .iter_mut()
+                .for_each(|sample| *sample = state);
+            state = bit(edge.rising);
+            index = end;
+        }
+        samples[index..]
+            .iter_mut()
+            .for_each(|sample| *sample = state);
+
+        Some(Logic {
+            samples,
+            sample_rate_hz: self.sample_rate_hz,
+            time_offset: self.time_offset,
+        })
+    }
+}
+
+impl WaveformData {
+    /// Get logic analyzer samples
+    ///
+    /// Returns `None` when logic analyzer is disabled or has no samples.
+    pub fn logic(&self) -> Option<Logic> {
+        if !self.header.logic.enabled || self.data.logic.is_empty() {
+            return None;
+        }
+
+        let sample_rate_hz = if self.header.logic_sample_rate_hz > 0.0 {
+            self.header.logic_sample_rate_hz
+        } else {
+            self.header.time.sample_rate_hz
+        };
+        // The middle of record corresponds to the middle of screen
+        let time_offset =
+            self.header.time.offset() - self.data.logic.len() as f32 / sample_rate_hz / 2.0;
+
+        Some(Logic {
+            samples: self.data.logic.to_vec(),
+            sample_rate_hz,
+            time_offset,
+        })
+    }
+
+    /// Convert enabled analog channels to logic
+    ///
+    /// First channel goes to line 0, second channel goes to line 1.
+    /// Returns `None` when no analog channels are enabled.
+    pub fn analog_logic(&self, threshold: Threshold) -> Option<Logic> {
+        let mut logic: Option<Logic> = None;
+
+        for (line, channel) in [Channel::Ch1, Channel::Ch2].iter().enumerate() {
+            if let Some(trace) = self.trace(*channel) {
+                let other = trace.to_logic(threshold, line as u8)?;
+                match &mut logic {
+                    Some(logic) => logic.merge(&other),
+                    None => logic = Some(other),
+                }
+            }
+        }
+
+        logic
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse;
+    use std::fs::read;
+
+    #[test]
+    fn ds1052e_2ch() {
+        let i = read("test/ds1052e_2ch.wfm").unwrap();
+        let r = parse(&i).unwrap();
+        let l = r.analog_logic(Threshold::default()).unwrap();
+        let ch2 = r.trace(Channel::Ch2).unwrap();
+        let volts = Threshold::Volts {
+            level: 2.5,
+            hysteresis: 0.5,
+        };
+
+        assert_eq!(l.len(), r.data.ch1.len());
+        assert_eq!(l.samples.iter().max(), Some(&0b11));
+        assert_eq!(
+            ch2.to_logic(Threshold::default(), 1).unwrap().samples,
+            l.samples
+                .iter()
+                .map(|sample| sample & 0b10)
+                .collect::<Vec<_>>()
+        );
+
+        // Absolute level close to middle gives nearly the same result
+        let mismatches = ch2
+            .to_logic(volts, 0)
+            .unwrap()
+            .samples
+            .iter()
+            .zip(l.samples.iter())
+            .filter(|(a, b)| **a != **b >> 1)
+            .count();
+        assert!(mismatches < 100);
+
+        assert!(ch2.to_logic(volts, Logic::LINES).is_none());
+        assert_eq!(l.bit(0, 15), Some(false));
+        assert_eq!(l.bit(0, 16), None);
+    }
+}
@@ -0,0 +1,102 @@
+/*!
+
+Instrument models
+
+*/
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Instrument model which waveform comes from
+///
+/// Files do not store exact model, so series is detected. DS1000D models are
+/// recognized only when logic analyzer is enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum Model {
+    /// DS1000E series (DS1052E, DS1102E)
+    Ds1000E,
+    /// DS1000D series with logic analyzer (DS1052D, DS1102D)
+    Ds1000D,
+}
+
+impl Model {
+    /// Name of series
+    pub fn name(&self) -> &'static str {
+        match self {
+            Model::Ds1000E => "DS1000E",
+            Model::Ds1000D => "DS1000D",
+        }
+    }
+
+    /// Number of analog channels
+    pub fn analog_channels(&self) -> u8 {
+        2
+    }
+
+    /// Number of logic analyzer channels
+    pub fn logic_channels(&self) -> u8 {
+        match self {
+            Model::Ds1000E => 0,
+            Model::Ds1000D => 16,
+        }
+    }
+
+    /// Instrument has logic analyzer
+    pub fn has_logic_analyzer(&self) -> bool {
+        self.logic_channels() > 0
+    }
+
+    /// Maximum real time sample rate of analog channel in Hz
+    pub fn max_sample_rate(&self) -> f32 {
+        1.0e9
+    }
+
+    /// Maximum sample rate of logic analyzer in Hz
+    pub fn max_logic_sample_rate(&self) -> Option<f32> {
+        match self {
+            Model::Ds1000E => None,
+            Model::Ds1000D => Some(200.0e6),
+        }
+    }
+
+    /// Resolution of analog channels
+    pub fn adc_bits(&self) -> u8 {
+        8
+    }
+
+    /// Lowest and highest codes of analog channels
+    ///
+    /// Samples at these codes are saturated.
+    pub fn adc_rails(&self) -> (u8, u8) {
+        (0, 255)
+    }
+
+    /// Maximum record length of single channel in points
+    pub fn max_points(&self) -> usize {
+        1 << 20
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse;
+    use std::fs::read;
+
+    #[test]
+    fn ds1052e_2ch() {
+        let i = read("test/ds1052e_2ch.wfm").unwrap();
+        let r = parse(&i).unwrap();
+        let m = r.header.model;
+
+        assert_eq!(m, Model::Ds1000E);
+        assert_eq!(m.analog_channels(), 2);
+        assert!(!m.has_logic_analyzer());
+        assert_eq!(m.adc_bits(), 8);
+        assert!(r.data.ch1.len() <= m.max_points());
+        assert!(r.header.time.sample_rate_hz <= m.max_sample_rate());
+        assert!(Model::Ds1000D.has_logic_analyzer());
+    }
+}
@@ -1,20 +1,114 @@
 mod ds1000e;
 
+pub use ds1000e::{parse, parse_reader, parse_reader_with, parse_ref, parse_with, write, Ds1000e};
+
+use super::{
+    progress::{Callback, Progress},
+    Model, Shared,
+};
+
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+/// Parsing options
+#[derive(Default)]
+pub struct ParseOptions<'a> {
+    pub(crate) progress: Option<Callback<'a>>,
+    pub(crate) max_bytes: Option<usize>,
+    pub(crate) raw_header: bool,
+    pub(crate) invalid_samples: InvalidSamples,
+    pub(crate) logic_points: Option<u32>,
+}
+
+/// Handling of samples which do not hold acquired data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum InvalidSamples {
+    /// Leave them out of records
+    #[default]
+    Drop,
+    /// Keep them at the end of analog channel records
+    ///
+    /// Their number is set to [`RawData::invalid`].
+    Keep,
+}
+
+impl<'a> ParseOptions<'a> {
+    /// Default options
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set progress callback
+    pub fn progress(mut self, callback: impl FnMut(Progress) + 'a) -> Self {
+        self.progress = Some(Box::new(callback));
+        self
+    }
+
+    /// Limit size of data which header claims
+    ///
+    /// Files exceeding the limit are rejected with [`Error::TooLarge`](crate::Error::TooLarge)
+    /// before any samples are read, which is useful for untrusted inputs.
+    pub fn max_bytes(mut self, limit: usize) -> Self {
+        self.max_bytes = Some(limit);
+        self
+    }
+
+    /// Retain raw header bytes in [`WaveformHeader::raw`]
+    ///
+    /// Fields which are not parsed are written back from them, so unmodified
+    /// waveforms are rewritten byte-exact.
+    pub fn raw_header(mut self, retain: bool) -> Self {
+        self.raw_header = retain;
+        self
+    }
+
+    /// Set handling of roll stop padding in rolling mode
+    pub fn invalid_samples(mut self, mode: InvalidSamples) -> Self {
+        self.invalid_samples = mode;
+        self
+    }
+
+    /// Set length of logic analyzer record instead of detecting it
+    pub fn logic_points(mut self, points: u32) -> Self {
+        self.logic_points = Some(points);
+        self
+    }
+}
+
 /// Waveform data
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct WaveformData {
     pub header: WaveformHeader,
     pub data: RawData,
 }
 
+/// Waveform data borrowing samples from input
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct WaveformDataRef<'a> {
+    pub header: WaveformHeader,
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    pub data: RawDataRef<'a>,
+}
+
+impl<'a> WaveformDataRef<'a> {
+    /// Copy samples to owned waveform
+    pub fn to_owned(&self) -> WaveformData {
+        WaveformData {
+            header: self.header.clone(),
+            data: self.data.to_owned(),
+        }
+    }
+}
+
 /// Waveform header
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct WaveformHeader {
+    /// Detected source instrument
+    pub model: Model,
     pub adc_mode: u8,
     pub roll_stop: u32,
     pub active_channel: u8,
@@ -25,13 +119,66 @@ pub struct WaveformHeader {
     pub trigger1: TriggerHeader,
     pub trigger2: TriggerHeader,
     pub logic: LogicAnalyzerHeader,
+    pub logic_sample_rate_hz: f32,
+    pub trigger_mode: TriggerMode,
     pub ch1_points: u32,
     pub ch1_skip: u32,
     pub ch2_points: u32,
+    /// Length of logic analyzer record
+    pub logic_length: LogicLength,
+    /// Raw header bytes when retained while parsing
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub raw: Option<Vec<u8>>,
+}
+
+/// Length of logic analyzer record and the way it was determined
+///
+/// Header does not store it, so it is detected from size of data following
+/// analog channels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum LogicLength {
+    /// Set by user
+    Explicit(u32),
+    /// Computed from size of remaining data
+    Detected(u32),
+    /// Assumed same as first channel record, which may be wrong
+    Assumed(u32),
+}
+
+impl LogicLength {
+    /// Number of samples
+    pub fn points(&self) -> u32 {
+        match *self {
+            LogicLength::Explicit(points)
+            | LogicLength::Detected(points)
+            | LogicLength::Assumed(points) => points,
+        }
+    }
+
+    /// Length was not determined from data
+    pub fn is_assumed(&self) -> bool {
+        matches!(self, LogicLength::Assumed(_))
+    }
+
+    /// Length which parser detects from written file with given number of points
+    ///
+    /// Empty record can not be detected, so first channel length is assumed,
+    /// which is also zero then.
+    pub(crate) fn written(enabled: bool, points: u32) -> Self {
+        match (enabled, points) {
+            (false, _) => LogicLength::Detected(0),
+            (true, 0) => LogicLength::Assumed(0),
+            (true, points) => LogicLength::Detected(points),
+        }
+    }
 }
 
 /// Channel header
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ChannelHeader {
     pub scale_display: i32,
@@ -48,8 +195,23 @@ pub struct ChannelHeader {
     pub unit: Unit,
 }
 
+impl ChannelHeader {
+    /// Compute scaling values from measured ones
+    pub(crate) fn scaled(mut self) -> Self {
+        let scale_measured = self.scale_measured as f32;
+        let shift_measured = self.shift_measured as f32;
+
+        self.volt_per_division =
+            (scale_measured * self.probe_value).copysign(if self.inverted { -1.0 } else { 1.0 });
+
+        self.volt_scale = 1.0e-6 * scale_measured * self.probe_value / 25.0;
+        self.volt_offset = shift_measured * self.volt_scale;
+        self
+    }
+}
+
 /// Time header
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TimeHeader {
     pub scale_display: i64,
@@ -60,7 +222,7 @@ pub struct TimeHeader {
 }
 
 /// Trigger header
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TriggerHeader {
     pub mode: TriggerMode,
@@ -82,7 +244,7 @@ pub struct TriggerHeader {
 }
 
 /// Logic Analyzer header
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct LogicAnalyzerHeader {
     pub enabled: bool,
@@ -94,12 +256,126 @@ pub struct LogicAnalyzerHeader {
 }
 
 /// Raw data
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct RawData {
-    pub ch1: Vec<u8>,
-    pub ch2: Vec<u8>,
-    pub logic: Vec<u16>,
+    pub ch1: Shared<u8>,
+    pub ch2: Shared<u8>,
+    pub logic: Shared<u16>,
+    /// Number of kept invalid samples at the end of analog channel records
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub invalid: usize,
+}
+
+impl RawData {
+    /// Samples of channel record holding acquired data
+    pub fn valid(&self, channel: Channel) -> &[u8] {
+        let raw = match channel {
+            Channel::Ch1 => &self.ch1,
+            Channel::Ch2 => &self.ch2,
+        };
+        &raw[..raw.len().saturating_sub(self.invalid)]
+    }
+
+    /// Validity of each sample of channel record
+    ///
+    /// Mask is derived from number of invalid samples, as they are always at
+    /// the end of record.
+    pub fn mask(&self, channel: Channel) -> Vec<bool> {
+        let valid = self.valid(channel).len();
+        let length = match channel {
+            Channel::Ch1 => self.ch1.len(),
+            Channel::Ch2 => self.ch2.len(),
+        };
+        (0..length).map(|index| index < valid).collect()
+    }
+}
+
+/// Reason of leaving samples out of record
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SkipReason {
+    /// Padding after roll stop in rolling mode
+    RollStop,
+    /// Marker between datasets
+    Sentinel,
+}
+
+/// Samples of channel dataset which are not in record
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Skip {
+    pub channel: Channel,
+    pub samples: usize,
+    pub reason: SkipReason,
+}
+
+impl WaveformData {
+    /// Samples of enabled channels which parser left out
+    pub fn skipped(&self) -> Vec<Skip> {
+        let header = &self.header;
+        let mut skipped = Vec::new();
+
+        for (&channel, enabled) in [Channel::Ch1, Channel::Ch2]
+            .iter()
+            .zip([header.ch1.enabled, header.ch2.enabled].iter())
+        {
+            if !enabled {
+                continue;
+            }
+            if header.ch1_skip > 0 && self.data.invalid == 0 {
+                skipped.push(Skip {
+                    channel,
+                    samples: header.ch1_skip as usize,
+                    reason: SkipReason::RollStop,
+                });
+            }
+            skipped.push(Skip {
+                channel,
+                samples: 4,
+                reason: SkipReason::Sentinel,
+            });
+        }
+        skipped
+    }
+}
+
+/// Raw data borrowed from input
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RawDataRef<'a> {
+    pub ch1: &'a [u8],
+    pub ch2: &'a [u8],
+    /// Logic analyzer samples as little-endian words
+    pub logic: &'a [u8],
+}
+
+impl<'a> RawDataRef<'a> {
+    /// Logic analyzer samples
+    pub fn logic(&self) -> impl Iterator<Item = u16> + 'a {
+        self.logic
+            .chunks_exact(2)
+            .map(|word| u16::from_le_bytes([word[0], word[1]]))
+    }
+
+    /// Copy samples to owned data
+    pub fn to_owned(&self) -> RawData {
+        RawData {
+            ch1: self.ch1.into(),
+            ch2: self.ch2.into(),
+            logic: self.logic().collect(),
+            invalid: 0,
+        }
+    }
+}
+
+/// Analog channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[repr(u8)]
+pub enum Channel {
+    Ch1 = 0,
+    Ch2 = 1,
 }
 
 /// Bandwidth
@@ -117,6 +393,7 @@ pub enum Bandwidth {
 /// Coupling
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[repr(u8)]
 pub enum Coupling {
     Dc = 0,
@@ -138,6 +415,7 @@ pub enum Filter {
 /// Source
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[repr(u8)]
 pub enum Source {
     Ch1 = 0,
@@ -151,6 +429,7 @@ pub enum Source {
 /// Trigger mode
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[repr(u8)]
 pub enum TriggerMode {
     Edge = 0,
@@ -163,12 +442,13 @@ pub enum TriggerMode {
 }
 
 /// Unit
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[repr(u8)]
 pub enum Unit {
     W = 0,
     A = 1,
+    #[default]
     V = 2,
     U = 3,
 }
@@ -181,7 +461,7 @@ macro_rules! try_from_num {
 
                 fn try_from(raw: u8) -> Result<Self, Self::Error> {
                     if raw <= $max {
-                        Ok(unsafe { core::mem::transmute(raw) })
+                        Ok(unsafe { core::mem::transmute::<u8, $type>(raw) })
                     } else {
                         Err(())
                     }
@@ -192,6 +472,7 @@ macro_rules! try_from_num {
 }
 
 try_from_num! {
+    Channel: 1,
     Bandwidth: 4,
     Coupling: 2,
     Filter: 3,
@@ -1,170 +1,464 @@
-/**
+/*!
 
 Rigol DS1102E oscilloscope waveform file format parser
 
 */
-use core::convert::TryInto;
+use crate::{
+    progress::{Reporter, Stage},
+    Error, Model, Result, WfmFormat,
+};
+use core::{
+    convert::{TryFrom, TryInto},
+    ops::Range,
+};
 use nom::{
-    cond, count, dbg_basic, map, map_opt, named, named_args,
+    bytes::streaming::{tag, take},
+    combinator::{cond, map, map_opt},
     number::streaming::{
         le_f32 as f32, le_i16 as i16, le_i32 as i32, le_i64 as i64, le_u16 as u16, le_u32 as u32,
         u8,
     },
-    tag, take, tuple,
+    sequence::tuple,
+    IResult,
 };
+use std::io::{Read, Write};
 
 use super::{
-    ChannelHeader, LogicAnalyzerHeader, RawData, TimeHeader, TriggerHeader, TriggerMode, Unit,
-    WaveformData, WaveformHeader,
+    Channel, ChannelHeader, InvalidSamples, LogicAnalyzerHeader, LogicLength, ParseOptions,
+    RawData, RawDataRef, TimeHeader, TriggerHeader, TriggerMode, Unit, WaveformData,
+    WaveformDataRef, WaveformHeader,
 };
 
-pub fn parse(input: &[u8]) -> Result<WaveformData, String> {
-    let header = waveform_header(input)
-        .map_err(|error| format!("Unable to parse header at: {}", error))?
+/// Size of file header
+const HEADER_SIZE: usize = 276;
+
+/// Size of blocks for incremental reading
+const BLOCK_SIZE: usize = 1 << 16;
+
+/// Parse waveform copying samples
+pub fn parse(input: &[u8]) -> Result<WaveformData> {
+    parse_ref(input).map(|data| data.to_owned())
+}
+
+/// Parse waveform copying samples with options
+pub fn parse_with(input: &[u8], mut options: ParseOptions) -> Result<WaveformData> {
+    let mut reporter = Reporter::new(options.progress.take(), input.len());
+    let keep = options.invalid_samples == InvalidSamples::Keep;
+    let WaveformDataRef { mut header, data } =
+        traced(borrow_data(input, keep, options.logic_points))?;
+    if options.raw_header {
+        header.raw = Some(input[..HEADER_SIZE].to_vec());
+    }
+    let stages = stages(&header);
+    reporter.total = traced(options.check_size(&stages))?;
+
+    let mut copied = RawData::default();
+    for (stage, size) in stages {
+        match stage {
+            Stage::Channel(Channel::Ch1) => copied.ch1 = data.ch1.into(),
+            Stage::Channel(Channel::Ch2) => copied.ch2 = data.ch2.into(),
+            Stage::Logic => copied.logic = data.logic().collect(),
+            _ => (),
+        }
+        reporter.report(stage, size, size);
+    }
+
+    if keep {
+        copied.invalid = header.ch1_skip as usize;
+    }
+
+    Ok(WaveformData {
+        header,
+        data: copied,
+    })
+}
+
+/// Parse waveform borrowing samples from input
+pub fn parse_ref(input: &[u8]) -> Result<WaveformDataRef<'_>> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("parse", length = input.len()).entered();
+
+    traced(borrow_data(input, false, None))
+}
+
+/// Borrow samples keeping roll stop padding when requested
+fn borrow_data(input: &[u8], keep: bool, logic_points: Option<u32>) -> Result<WaveformDataRef<'_>> {
+    let mut header = waveform_header(input)
+        .map_err(|error| Error::from_nom(error, "header", 0, input.len()))?
         .1;
+    if let Some(points) = logic_points {
+        header.logic_length = LogicLength::Explicit(points);
+    }
 
-    let data = raw_data(&input[276..], &header)
-        .map_err(|error| format!("Unable to parse raw data: {}", error))?
+    let data = &input[HEADER_SIZE..];
+    let data = raw_data(data, &mut header, keep)
+        .map_err(|error| Error::from_nom(error, "raw data", HEADER_SIZE, data.len()))?
         .1;
 
-    Ok(WaveformData { header, data })
+    Ok(WaveformDataRef { header, data })
 }
 
-named!(
-    waveform_header<WaveformHeader>,
-    map_opt!(
-        tuple!(
-            tag!([0xa5u8, 0xa5, 0x00, 0x00]), // magic
-            take!(12),                        // padding
-            u8,                               // ADC mode
-            take!(3),                         // padding
-            u32,                              // roll_stop
-            take!(4),                         // unused
-            u32,                              // ch1 points
-            u8,                               // active channel
-            take!(1),                         // padding
-            channel_header,                   // channel 1 header
-            channel_header,                   // channel 2 header
-            u8,                               // time offset
-            take!(1),                         // padding
-            time_header,                      // time header
-            logic_analyzer_header,            // logic analyzer header
-            u8,                               // trigger mode
-            trigger_header,                   // trigger 1 header
-            trigger_header,                   // trigger 2 header
-            take!(6),                         // padding
-            u32,                              // ch2 points
-            time_header,                      // time 2 header
-            f32                               // logic sample rate
-        ),
-        |(
-            _magic,
-            _,
-            adc_mode,
-            _,
-            roll_stop,
-            _,
-            ch1_points,
-            active_channel,
-            _,
-            ch1,
-            ch2,
-            time_offset,
-            _,
-            time,
-            logic,
-            trigger_mode,
-            trigger1,
-            trigger2,
-            _,
-            ch2_points,
-            time2,
-            logic_sample_rate,
-        ): (
-            &[u8],
-            &[u8],
-            u8,
-            &[u8],
-            u32,
-            &[u8],
-            u32,
-            u8,
-            &[u8],
-            ChannelHeader,
-            ChannelHeader,
-            u8,
-            &[u8],
-            TimeHeader,
-            LogicAnalyzerHeader,
-            u8,
-            TriggerHeader,
-            TriggerHeader,
-            &[u8],
-            u32,
-            TimeHeader,
-            f32
-        )| {
-            // In rolling mode, change the number of valid samples and skip invalid points
-            let (ch1_points, ch1_skip) = if roll_stop == 0 {
-                (ch1_points - 4, 0)
-            } else {
-                (ch1_points - roll_stop - 6, roll_stop + 2)
-            };
+/// Stages of file with sizes in bytes
+fn stages(header: &WaveformHeader) -> Vec<(Stage, usize)> {
+    // Sentinel between datasets follows roll stop padding
+    let padding = header.ch1_skip as usize + 4;
 
-            let sample_rate_hz = time.sample_rate_hz;
-            let seconds_per_point = 1.0 / sample_rate_hz;
+    let mut stages = vec![(Stage::Header, HEADER_SIZE)];
+    if header.ch1.enabled {
+        stages.push((
+            Stage::Channel(Channel::Ch1),
+            header.ch1_points as usize + padding,
+        ));
+    }
+    if header.ch2.enabled {
+        stages.push((
+            Stage::Channel(Channel::Ch2),
+            header.ch2_points as usize + padding,
+        ));
+    }
+    if header.logic.enabled {
+        stages.push((Stage::Logic, header.logic_length.points() as usize * 2));
+    }
+    stages
+}
 
-            // Use ch1_points when ch2_points is not written
-            let ch2_points = if ch1.enabled && ch2_points == 0 {
-                ch1_points
-            } else {
-                ch2_points
-            };
+/// Determine logic analyzer record length from size of remaining data
+fn detect_logic(header: &WaveformHeader, remaining: usize) -> LogicLength {
+    match header.logic_length {
+        LogicLength::Explicit(points) => LogicLength::Explicit(points),
+        _ if remaining > 0 && remaining.is_multiple_of(2) && remaining / 2 <= u32::MAX as usize => {
+            LogicLength::Detected((remaining / 2) as u32)
+        }
+        _ => {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                remaining,
+                points = header.ch1_points,
+                "logic analyzer length not detected, assuming first channel length"
+            );
+            LogicLength::Assumed(header.ch1_points)
+        }
+    }
+}
 
-            // In rolling mode, skip invalid samples
-            let ch1_volt_length = ch1_points - roll_stop;
-            let ch2_volt_length = ch2_points - roll_stop;
+impl<'a> ParseOptions<'a> {
+    /// Total size of stages checked against limit
+    fn check_size(&self, stages: &[(Stage, usize)]) -> Result<usize> {
+        let size = stages.iter().map(|(_, size)| size).sum();
 
-            let ch1_time_scale = 1.0e-12 * time.scale_measured as f32;
-            let ch1_time_offset = 1.0e-12 * time.offset_measured as f32;
+        match self.max_bytes {
+            Some(limit) if size > limit => Err(Error::TooLarge { size, limit }),
+            _ => Ok(size),
+        }
+    }
+}
 
-            let trigger_mode: TriggerMode = (trigger_mode as u8).try_into().ok()?;
+/// Reader which keeps track of offset in input
+struct Input<'a, R> {
+    reader: R,
+    offset: usize,
+    reporter: Reporter<'a>,
+}
 
-            let ch2_time_scale = if trigger_mode == TriggerMode::Alt {
-                1.0e-12 * time2.scale_measured as f32
-            } else {
-                ch1_time_scale
-            };
-            let ch2_time_offset = if trigger_mode == TriggerMode::Alt {
-                1.0e-12 * time2.offset_measured as f32
-            } else {
-                ch1_time_offset
-            };
+impl<'a, R: Read> Input<'a, R> {
+    /// Read up to given number of bytes block by block
+    ///
+    /// Buffer grows while data arrives so sizes from corrupted headers do not
+    /// cause allocation up front.
+    fn fetch(&mut self, length: usize, stage: Stage) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
 
-            Some(WaveformHeader {
-                adc_mode,
-                roll_stop,
-                active_channel,
-                ch1,
-                ch2,
-                time,
-                time2,
-                trigger1,
-                trigger2,
-                logic,
-                ch1_points,
-                ch1_skip,
-                ch2_points,
-                //seconds_per_point,
-            })
+        while data.len() < length {
+            let block = (length - data.len()).min(BLOCK_SIZE);
+            let read = (&mut self.reader)
+                .take(block as u64)
+                .read_to_end(&mut data)
+                .map_err(|error| Error::Io(error.kind()))?;
+            self.offset += read;
+            self.reporter.report(stage, data.len(), length);
+
+            if read < block {
+                break;
+            }
+        }
+        Ok(data)
+    }
+
+    /// Read rest of input up to limit reporting against expected length
+    fn rest(&mut self, limit: usize, expected: usize, stage: Stage) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+
+        while data.len() < limit {
+            let block = (limit - data.len()).min(BLOCK_SIZE);
+            let read = (&mut self.reader)
+                .take(block as u64)
+                .read_to_end(&mut data)
+                .map_err(|error| Error::Io(error.kind()))?;
+            self.offset += read;
+
+            if read < block {
+                break;
+            }
+            // Stage completes when input ends
+            self.reporter
+                .report(stage, data.len(), expected.max(data.len() + 1));
         }
-    )
-);
+        self.reporter.total += data.len();
+        self.reporter.total -= expected.min(self.reporter.total);
+        self.reporter.report(stage, data.len(), data.len());
+        Ok(data)
+    }
 
-named!(
-    channel_header<ChannelHeader>,
-    map!(
-        tuple!(
+    /// Read exactly given number of bytes
+    fn read(&mut self, length: usize, stage: Stage) -> Result<Vec<u8>> {
+        let data = self.fetch(length, stage)?;
+
+        if data.len() < length {
+            return Err(Error::Truncated {
+                context: "raw data",
+                offset: self.offset,
+                needed: Some(length - data.len()),
+            });
+        }
+        Ok(data)
+    }
+}
+
+/// Parse waveform from reader
+///
+/// Header is read first, then sample blocks of enabled channels are read one
+/// by one, so input does not need to be loaded in memory up front.
+pub fn parse_reader(reader: impl Read) -> Result<WaveformData> {
+    parse_reader_with(reader, ParseOptions::default())
+}
+
+/// Parse waveform from reader with options
+pub fn parse_reader_with(reader: impl Read, options: ParseOptions) -> Result<WaveformData> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("parse_reader").entered();
+
+    traced(read_data(reader, options))
+}
+
+fn read_data(reader: impl Read, mut options: ParseOptions) -> Result<WaveformData> {
+    let mut input = Input {
+        reader,
+        offset: 0,
+        reporter: Reporter::new(options.progress.take(), HEADER_SIZE),
+    };
+
+    let raw = input.fetch(HEADER_SIZE, Stage::Header)?;
+    let mut header = waveform_header(&raw)
+        .map_err(|error| Error::from_nom(error, "header", 0, raw.len()))?
+        .1;
+    if options.raw_header {
+        header.raw = Some(raw);
+    }
+    if let Some(points) = options.logic_points {
+        header.logic_length = LogicLength::Explicit(points);
+    }
+
+    let stages = stages(&header);
+    // Size of logic analyzer record is known only after reading it
+    let detect = header.logic.enabled && !matches!(header.logic_length, LogicLength::Explicit(_));
+    let known = stages
+        .iter()
+        .filter(|(stage, _)| !detect || *stage != Stage::Logic)
+        .copied()
+        .collect::<Vec<_>>();
+    options.check_size(&known)?;
+    input.reporter.total = stages.iter().map(|(_, size)| size).sum();
+
+    let mut data = RawData::default();
+    if options.invalid_samples == InvalidSamples::Keep {
+        data.invalid = header.ch1_skip as usize;
+    }
+    for (stage, size) in stages {
+        #[cfg(feature = "tracing")]
+        tracing::trace!(?stage, size, offset = input.offset, "reading");
+
+        match stage {
+            Stage::Channel(Channel::Ch1) => {
+                let mut raw = input.read(size, stage)?;
+                raw.truncate(header.ch1_points as usize + data.invalid);
+                data.ch1 = raw.into();
+            }
+            Stage::Channel(Channel::Ch2) => {
+                let mut raw = input.read(size, stage)?;
+                raw.truncate(header.ch2_points as usize + data.invalid);
+                data.ch2 = raw.into();
+            }
+            Stage::Logic => {
+                let raw = if let LogicLength::Explicit(_) = header.logic_length {
+                    input.read(size, stage)?
+                } else {
+                    // Remaining data, one byte beyond size limit tells it is exceeded
+                    let budget = options.max_bytes.map_or(usize::MAX, |limit| {
+                        limit
+                            .saturating_add(size)
+                            .saturating_sub(input.reporter.total)
+                            .saturating_add(1)
+                    });
+                    let raw = input.rest(budget, size, stage)?;
+                    match options.max_bytes {
+                        Some(limit) if input.reporter.total > limit => {
+                            return Err(Error::TooLarge {
+                                size: input.reporter.total,
+                                limit,
+                            })
+                        }
+                        _ => (),
+                    }
+                    header.logic_length = detect_logic(&header, raw.len());
+                    raw
+                };
+                let size = header.logic_length.points() as usize * 2;
+                if raw.len() < size {
+                    return Err(Error::Truncated {
+                        context: "raw data",
+                        offset: input.offset,
+                        needed: Some(size - raw.len()),
+                    });
+                }
+                data.logic = raw[..size]
+                    .chunks_exact(2)
+                    .map(|word| u16::from_le_bytes([word[0], word[1]]))
+                    .collect()
+            }
+            _ => (),
+        }
+    }
+
+    Ok(WaveformData { header, data })
+}
+
+/// Log failure with its location
+fn traced<T>(result: Result<T>) -> Result<T> {
+    #[cfg(feature = "tracing")]
+    if let Err(error) = &result {
+        tracing::warn!(offset = error.offset(), %error, "parsing failed");
+    }
+    result
+}
+
+/// Log header fields
+#[cfg(feature = "tracing")]
+fn trace_header(header: &WaveformHeader) {
+    tracing::debug!(
+        model = header.model.name(),
+        adc_mode = header.adc_mode,
+        roll_stop = header.roll_stop,
+        active_channel = header.active_channel,
+        ch1_enabled = header.ch1.enabled,
+        ch1_points = header.ch1_points,
+        ch1_volt_scale = header.ch1.volt_scale,
+        ch2_enabled = header.ch2.enabled,
+        ch2_points = header.ch2_points,
+        ch2_volt_scale = header.ch2.volt_scale,
+        sample_rate_hz = header.time.sample_rate_hz,
+        time_offset = header.time.offset_measured,
+        trigger_mode = ?header.trigger_mode,
+        logic_enabled = header.logic.enabled,
+        "parsed header"
+    );
+}
+
+/// Enumeration stored in single byte
+fn enumeration<T: TryFrom<u8>>(input: &[u8]) -> IResult<&[u8], T> {
+    map_opt(u8, |value| value.try_into().ok())(input)
+}
+
+fn waveform_header(input: &[u8]) -> IResult<&[u8], WaveformHeader> {
+    let (input, _magic) = tag([0xa5u8, 0xa5, 0x00, 0x00])(input)?;
+    let (input, _) = take(12usize)(input)?; // padding
+    let (input, adc_mode) = u8(input)?;
+    let (input, _) = take(3usize)(input)?; // padding
+    let (input, roll_stop) = u32(input)?;
+    let (input, _) = take(4usize)(input)?; // unused
+    let points = input;
+    let (input, ch1_points) = u32(input)?;
+    let (input, active_channel) = u8(input)?;
+    let (input, _) = take(1usize)(input)?; // padding
+    let (input, ch1) = channel_header(input)?;
+    let (input, ch2) = channel_header(input)?;
+    let (input, _time_offset) = u8(input)?;
+    let (input, _) = take(1usize)(input)?; // padding
+    let (input, time) = time_header(input)?;
+    let (input, logic) = logic_analyzer_header(input)?;
+    let (input, trigger_mode) = enumeration::<TriggerMode>(input)?;
+    let (input, trigger1) = trigger_header(input)?;
+    let (input, trigger2) = trigger_header(input)?;
+    let (input, _) = take(6usize)(input)?; // padding
+    let (input, ch2_points) = u32(input)?;
+    let (input, time2) = time_header(input)?;
+    let (input, logic_sample_rate_hz) = f32(input)?;
+
+    // In rolling mode, change the number of valid samples and skip invalid points
+    let (ch1_points, ch1_skip) = if roll_stop == 0 {
+        (ch1_points.checked_sub(4), 0)
+    } else {
+        (
+            ch1_points
+                .checked_sub(roll_stop)
+                .and_then(|points| points.checked_sub(6)),
+            roll_stop.saturating_add(2),
+        )
+    };
+    let ch1_points = ch1_points.ok_or_else(|| {
+        nom::Err::Failure(nom::error::Error::new(
+            points,
+            nom::error::ErrorKind::Verify,
+        ))
+    })?;
+
+    // Use ch1_points when ch2_points is not written
+    let ch2_points = if ch1.enabled && ch2_points == 0 {
+        ch1_points
+    } else {
+        ch2_points
+    };
+
+    // Refined when data is parsed
+    let logic_length = if logic.enabled {
+        LogicLength::Assumed(ch1_points)
+    } else {
+        LogicLength::Detected(0)
+    };
+
+    let header = WaveformHeader {
+        model: if logic.enabled {
+            Model::Ds1000D
+        } else {
+            Model::Ds1000E
+        },
+        adc_mode,
+        roll_stop,
+        active_channel,
+        ch1,
+        ch2,
+        time,
+        time2,
+        trigger1,
+        trigger2,
+        logic,
+        logic_sample_rate_hz,
+        trigger_mode,
+        ch1_points,
+        ch1_skip,
+        ch2_points,
+        logic_length,
+        raw: None,
+    };
+
+    #[cfg(feature = "tracing")]
+    trace_header(&header);
+
+    Ok((input, header))
+}
+
+fn channel_header(input: &[u8]) -> IResult<&[u8], ChannelHeader> {
+    map(
+        tuple((
             u16, // unknown
             i32, // scale display
             i16, // shift display
@@ -176,8 +470,8 @@ named!(
             u8,  // inverted
             u8,  // unknown
             i32, // scale measured
-            i16  // shift measured
-        ),
+            i16, // shift measured
+        )),
         |(
             _,
             scale_display,
@@ -192,20 +486,6 @@ named!(
             scale_measured,
             shift_measured,
         )| {
-            let inverted = inverted != 0;
-            let enabled = enabled != 0;
-
-            let scale_measured_float = scale_measured as f32;
-            let shift_measured_float = shift_measured as f32;
-
-            let volt_per_division =
-                (scale_measured_float * probe_value).copysign(if inverted { -1.0 } else { 1.0 });
-
-            let volt_scale = 1.0e-6 * scale_measured_float * probe_value / 25.0;
-            let volt_offset = shift_measured_float * volt_scale;
-
-            let unit = Unit::V;
-
             ChannelHeader {
                 scale_display,
                 shift_display,
@@ -213,27 +493,27 @@ named!(
                 invert_display,
                 scale_measured,
                 shift_measured,
-                inverted,
-                enabled,
-                volt_per_division,
-                volt_scale,
-                volt_offset,
-                unit,
+                inverted: inverted != 0,
+                enabled: enabled != 0,
+                volt_per_division: 0.0,
+                volt_scale: 0.0,
+                volt_offset: 0.0,
+                unit: Unit::V,
             }
-        }
-    )
-);
+            .scaled()
+        },
+    )(input)
+}
 
-named!(
-    time_header<TimeHeader>,
-    map!(
-        tuple!(
+fn time_header(input: &[u8]) -> IResult<&[u8], TimeHeader> {
+    map(
+        tuple((
             i64, // scale display
             i64, // offset display
             f32, // sample rate Hz
             i64, // scale measured
-            i64  // offset measured
-        ),
+            i64, // offset measured
+        )),
         |(scale_display, offset_display, sample_rate_hz, scale_measured, offset_measured)| {
             TimeHeader {
                 scale_display,
@@ -242,34 +522,33 @@ named!(
                 scale_measured,
                 offset_measured,
             }
-        }
-    )
-);
-
-named!(
-    trigger_header<TriggerHeader>,
-    map_opt!(
-        tuple!(
-            u8,       // mode
-            u8,       // source
-            u8,       // coupling
-            u8,       // sweep
-            take!(1), // padding
-            f32,      // sens
-            f32,      // holdoff
-            f32,      // level
-            u8,       // direct
-            u8,       // pulse type
-            take!(2), // padding
-            f32,      // pulse width
-            u8,       // slope type
-            take!(3), // padding
-            f32,      // lower
-            f32,      // slope width
-            u8,       // video pol
-            u8,       // video sync
-            u8        // video std
-        ),
+        },
+    )(input)
+}
+
+fn trigger_header(input: &[u8]) -> IResult<&[u8], TriggerHeader> {
+    map(
+        tuple((
+            enumeration,  // mode
+            enumeration,  // source
+            enumeration,  // coupling
+            u8,           // sweep
+            take(1usize), // padding
+            f32,          // sens
+            f32,          // holdoff
+            f32,          // level
+            u8,           // direct
+            u8,           // pulse type
+            take(2usize), // padding
+            f32,          // pulse width
+            u8,           // slope type
+            take(3usize), // padding
+            f32,          // lower
+            f32,          // slope width
+            u8,           // video pol
+            u8,           // video sync
+            u8,           // video std
+        )),
         |(
             mode,
             source,
@@ -290,46 +569,45 @@ named!(
             video_pol,
             video_sync,
             video_std,
+        )| TriggerHeader {
+            mode,
+            source,
+            coupling,
+            sweep,
+            sens,
+            holdoff,
+            level,
+            direct: direct != 0,
+            pulse_type,
+            pulse_width,
+            slope_type,
+            lower,
+            slope_width,
+            video_pol,
+            video_sync,
+            video_std,
+        },
+    )(input)
+}
+
+fn logic_analyzer_header(input: &[u8]) -> IResult<&[u8], LogicAnalyzerHeader> {
+    map(
+        tuple((
+            u8,            // enabled
+            u8,            // active channel (0..16)
+            u16,           // enabled channels
+            take(16usize), // position
+            u8,            // group 8..15 size
+            u8,            // group 0..7 size
+        )),
+        |(enabled, active_channel, enabled_channels, position, group8to15size, group0to7size): (
+            u8,
+            u8,
+            u16,
+            &[u8],
+            u8,
+            u8,
         )| {
-            let mode = (mode as u8).try_into().ok()?;
-            let source = (source as u8).try_into().ok()?;
-            let coupling = (coupling as u8).try_into().ok()?;
-            let direct = direct != 0;
-
-            Some(TriggerHeader {
-                mode,
-                source,
-                coupling,
-                sweep,
-                sens,
-                holdoff,
-                level,
-                direct,
-                pulse_type,
-                pulse_width,
-                slope_type,
-                lower,
-                slope_width,
-                video_pol,
-                video_sync,
-                video_std,
-            })
-        }
-    )
-);
-
-named!(
-    logic_analyzer_header<LogicAnalyzerHeader>,
-    map!(
-        tuple!(
-            u8,        // enabled
-            u8,        // active channel (0..16)
-            u16,       // enabled channels
-            take!(16), // position
-            u8,        // group 8..15 size
-            u8         // group 0..7 size
-        ),
-        |(enabled, active_channel, enabled_channels, position, group8to15size, group0to7size)| {
             let enabled = enabled & 0b1 != 0;
             let position = position.try_into().unwrap();
 
@@ -341,47 +619,319 @@ named!(
                 group8to15size,
                 group0to7size,
             }
+        },
+    )(input)
+}
+
+fn raw_data<'a>(
+    input: &'a [u8],
+    header: &mut WaveformHeader,
+    keep: bool,
+) -> IResult<&'a [u8], RawDataRef<'a>> {
+    // Kept padding follows samples, so it is taken as part of them
+    let (kept, skip) = if keep {
+        (header.ch1_skip, 0)
+    } else {
+        (0, header.ch1_skip)
+    };
+    // Corrupted number of second channel points may overflow with padding
+    let ch2_points = if header.ch2.enabled {
+        header.ch2_points.checked_add(kept).ok_or_else(|| {
+            nom::Err::Failure(nom::error::Error::new(input, nom::error::ErrorKind::Verify))
+        })?
+    } else {
+        0
+    };
+    let (input, ch1) = cond(
+        header.ch1.enabled,
+        tuple((
+            take(header.ch1_points + kept), // channel 1 points
+            take(skip),                     // roll stop padding 1
+            take(4usize),                   // sentinel between datasets
+        )),
+    )(input)?;
+    let (input, ch2) = cond(
+        header.ch2.enabled,
+        tuple((
+            take(ch2_points), // channel 2 points
+            take(skip),       // roll stop padding 2
+            take(4usize),     // sentinel between datasets
+        )),
+    )(input)?;
+    if header.logic.enabled {
+        header.logic_length = detect_logic(header, input.len());
+    }
+    let (input, logic) = cond(
+        header.logic.enabled,
+        take(header.logic_length.points() as usize * 2),
+    )(input)?;
+
+    Ok((
+        input,
+        RawDataRef {
+            ch1: ch1
+                .map(|(smps, _, _): (&[u8], &[u8], &[u8])| smps)
+                .unwrap_or_default(),
+            ch2: ch2
+                .map(|(smps, _, _): (&[u8], &[u8], &[u8])| smps)
+                .unwrap_or_default(),
+            logic: logic.unwrap_or_default(),
+        },
+    ))
+}
+
+/// Little-endian output buffer
+#[derive(Default)]
+struct Output<'a> {
+    data: Vec<u8>,
+    /// Raw header to take unknown bytes from
+    template: &'a [u8],
+    /// Locations of unknown bytes
+    unknown: Vec<Range<usize>>,
+}
+
+impl<'a> Output<'a> {
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
+    }
+
+    /// Unknown bytes copied from template or zeros
+    fn padding(&mut self, length: usize) {
+        let range = self.data.len()..self.data.len() + length;
+        match self.template.get(range.clone()) {
+            Some(bytes) => self.data.extend_from_slice(bytes),
+            None => self.data.resize(range.end, 0),
         }
-    )
-);
-
-named_args!(
-    raw_data<'a>(header: &'a WaveformHeader)<RawData>,
-    map!(
-        tuple!(
-            cond!(header.ch1.enabled,
-                  tuple!(
-                      take!(header.ch1_points), // channel 1 points
-                      take!(header.ch1_skip), // roll stop padding 1
-                      take!(4) // sentinel between datasets
-                  )
-            ),
-            cond!(header.ch2.enabled,
-                  tuple!(
-                      take!(header.ch2_points), // channel 2 points
-                      take!(header.ch1_skip), // roll stop padding 2
-                      take!(4) // sentinel between datasets
-                  )
-            ),
-            // Not clear where the LA length is stored assume same as ch1_points
-            cond!(header.logic.enabled,
-                  count!(u16, header.ch1_points as usize)
-            )
-        ), |(
-            ch1,
-            ch2,
-            logic,
-        )| RawData {
-            ch1: ch1.map(|(smps, _, _)| smps.into()).unwrap_or_default(),
-            ch2: ch2.map(|(smps, _, _)| smps.into()).unwrap_or_default(),
-            logic: logic.map(|smps| smps.into()).unwrap_or_default(),
+        self.unknown.push(range);
+    }
+
+    fn u8(&mut self, value: u8) {
+        self.data.push(value);
+    }
+}
+
+macro_rules! output {
+    ( $( $name:ident: $type:ty, )* ) => {
+        impl<'a> Output<'a> {
+            $(
+                fn $name(&mut self, value: $type) {
+                    self.bytes(&value.to_le_bytes());
+                }
+            )*
         }
-    )
-);
+    };
+}
+
+output! {
+    u16: u16,
+    u32: u32,
+    i16: i16,
+    i32: i32,
+    i64: i64,
+    f32: f32,
+}
+
+impl<'a> Output<'a> {
+    fn waveform_header(&mut self, header: &WaveformHeader) {
+        // Stored number of points includes trailing invalid points
+        let ch1_points = if header.roll_stop == 0 {
+            header.ch1_points + 4
+        } else {
+            header.ch1_points + header.roll_stop + 6
+        };
+
+        self.bytes(&[0xa5, 0xa5, 0x00, 0x00]);
+        self.padding(12);
+        self.u8(header.adc_mode);
+        self.padding(3);
+        self.u32(header.roll_stop);
+        self.padding(4);
+        self.u32(ch1_points);
+        self.u8(header.active_channel);
+        self.padding(1);
+        self.channel_header(&header.ch1);
+        self.channel_header(&header.ch2);
+        self.padding(2); // time offset and padding
+        self.time_header(&header.time);
+        self.logic_analyzer_header(&header.logic);
+        self.u8(header.trigger_mode as u8);
+        self.trigger_header(&header.trigger1);
+        self.trigger_header(&header.trigger2);
+        self.padding(6);
+        // Instrument stores zero when second channel has the same length
+        self.u32(
+            if header.ch1.enabled && header.ch2_points == header.ch1_points {
+                0
+            } else {
+                header.ch2_points
+            },
+        );
+        self.time_header(&header.time2);
+        self.f32(header.logic_sample_rate_hz);
+        self.padding(HEADER_SIZE - self.data.len());
+    }
+
+    fn channel_header(&mut self, header: &ChannelHeader) {
+        self.padding(2);
+        self.i32(header.scale_display);
+        self.i16(header.shift_display);
+        self.padding(2);
+        self.f32(header.probe_value);
+        self.u8(header.invert_display);
+        self.u8(header.enabled as u8);
+        self.u8(header.inverted as u8);
+        self.padding(1);
+        self.i32(header.scale_measured);
+        self.i16(header.shift_measured);
+    }
+
+    fn time_header(&mut self, header: &TimeHeader) {
+        self.i64(header.scale_display);
+        self.i64(header.offset_display);
+        self.f32(header.sample_rate_hz);
+        self.i64(header.scale_measured);
+        self.i64(header.offset_measured);
+    }
+
+    fn trigger_header(&mut self, header: &TriggerHeader) {
+        self.u8(header.mode as u8);
+        self.u8(header.source as u8);
+        self.u8(header.coupling as u8);
+        self.u8(header.sweep);
+        self.padding(1);
+        self.f32(header.sens);
+        self.f32(header.holdoff);
+        self.f32(header.level);
+        self.u8(header.direct as u8);
+        self.u8(header.pulse_type);
+        self.padding(2);
+        self.f32(header.pulse_width);
+        self.u8(header.slope_type);
+        self.padding(3);
+        self.f32(header.lower);
+        self.f32(header.slope_width);
+        self.u8(header.video_pol);
+        self.u8(header.video_sync);
+        self.u8(header.video_std);
+    }
+
+    fn logic_analyzer_header(&mut self, header: &LogicAnalyzerHeader) {
+        self.u8(header.enabled as u8);
+        self.u8(header.active_channel);
+        self.u16(header.enabled_channels);
+        self.bytes(&header.position);
+        self.u8(header.group8to15size);
+        self.u8(header.group0to7size);
+    }
+
+    /// Samples followed by roll stop padding and sentinel
+    ///
+    /// Kept invalid samples are written as padding.
+    fn block(&mut self, samples: &[u8], invalid: usize, skip: u32) {
+        let (samples, kept) = samples.split_at(samples.len().saturating_sub(invalid));
+        self.bytes(samples);
+        if kept.len() == skip as usize {
+            self.bytes(kept);
+        } else {
+            self.padding(skip as usize);
+        }
+        // Sentinel repeats last sample
+        let last = samples.last().copied().unwrap_or(0);
+        self.bytes(&[last; 4]);
+    }
+}
+
+/// Write waveform in DS1000E file format
+///
+/// Fields which are not parsed are taken from raw header when it is retained
+/// and written as zeros otherwise.
+pub fn write(data: &WaveformData, mut writer: impl Write) -> Result<()> {
+    let header = &data.header;
+    let mut output = Output {
+        data: Vec::with_capacity(HEADER_SIZE),
+        template: header.raw.as_deref().unwrap_or_default(),
+        ..Output::default()
+    };
+    output.waveform_header(header);
+
+    if header.ch1.enabled {
+        output.block(&data.data.ch1, data.data.invalid, header.ch1_skip);
+    }
+    if header.ch2.enabled {
+        output.block(&data.data.ch2, data.data.invalid, header.ch1_skip);
+    }
+    if header.logic.enabled {
+        for &word in &data.data.logic {
+            output.u16(word);
+        }
+    }
+
+    writer
+        .write_all(&output.data)
+        .map_err(|error| Error::Io(error.kind()))
+}
+
+impl WaveformHeader {
+    /// Offsets and contents of unknown and padding fields of raw header
+    ///
+    /// Empty when raw header is not retained.
+    pub fn unknown_fields(&self) -> Vec<(usize, &[u8])> {
+        let raw = match &self.raw {
+            Some(raw) => raw,
+            None => return Vec::new(),
+        };
+
+        let mut output = Output::default();
+        output.waveform_header(self);
+        output
+            .unknown
+            .into_iter()
+            .filter_map(|range| Some((range.start, raw.get(range)?)))
+            .collect()
+    }
+}
+
+/// Rigol DS1000E/D series format
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Ds1000e;
+
+impl WfmFormat for Ds1000e {
+    fn name(&self) -> &'static str {
+        "Rigol DS1000E"
+    }
+
+    fn sniff(&self, input: &[u8]) -> bool {
+        input.starts_with(&[0xa5, 0xa5, 0x00, 0x00])
+    }
+
+    fn parse_header(&self, input: &[u8]) -> Result<WaveformHeader> {
+        Ok(waveform_header(input)
+            .map_err(|error| Error::from_nom(error, "header", 0, input.len()))?
+            .1)
+    }
+
+    fn parse_data(&self, header: &WaveformHeader, input: &[u8]) -> Result<RawData> {
+        let data = input.get(HEADER_SIZE..).unwrap_or_default();
+        Ok(raw_data(data, &mut header.clone(), false)
+            .map_err(|error| Error::from_nom(error, "raw data", HEADER_SIZE, data.len()))?
+            .1
+            .to_owned())
+    }
+
+    /// Parse whole input keeping detected logic analyzer record length
+    fn parse(&self, input: &[u8]) -> Result<WaveformData> {
+        parse(input)
+    }
+
+    fn write(&self, data: &WaveformData, writer: &mut dyn Write) -> Result<()> {
+        write(data, writer)
+    }
+}
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::{Skip, SkipReason};
     use std::fs::read;
 
     #[test]
@@ -395,4 +945,255 @@ mod test {
         assert_eq!(r.data.ch1.len(), 524284);
         //assert!(false);
     }
+
+    #[test]
+    fn round_trip() {
+        let i = read("test/ds1052e_2ch.wfm").unwrap();
+        let r = parse(&i).unwrap();
+        let mut o = Vec::new();
+        write(&r, &mut o).unwrap();
+
+        assert_eq!(o.len(), i.len());
+        // Only unknown fields differ
+        assert!(o.iter().zip(&i).filter(|(o, i)| o != i).count() < 16);
+        let w = parse(&o).unwrap();
+        assert_eq!(format!("{:?}", w.header), format!("{:?}", r.header));
+        assert_eq!(w.data.ch1, r.data.ch1);
+        assert_eq!(w.data.ch2, r.data.ch2);
+        assert!(r.header.unknown_fields().is_empty());
+
+        let r = parse_with(&i, ParseOptions::new().raw_header(true)).unwrap();
+        assert_eq!(r.header.raw.as_deref(), Some(&i[..HEADER_SIZE]));
+        let mut o = Vec::new();
+        write(&r, &mut o).unwrap();
+        assert_eq!(o, i);
+
+        let unknown = r.header.unknown_fields();
+        assert_eq!(unknown[0], (4, &i[4..16]));
+        assert_eq!(unknown.last().unwrap().1, &i[273..HEADER_SIZE]);
+    }
+
+    #[test]
+    fn rolling() {
+        let data = WaveformHeader::builder()
+            .channel(Channel::Ch1, 1.0, 0.0)
+            .channel(Channel::Ch2, 1.0, 0.0)
+            .roll_stop(10)
+            .build_with(RawData {
+                ch1: vec![100; 64].into(),
+                ch2: vec![150; 64].into(),
+                ..RawData::default()
+            });
+        let mut i = Vec::new();
+        write(&data, &mut i).unwrap();
+        // Padding of first channel
+        i[HEADER_SIZE + 64..HEADER_SIZE + 76].fill(0x55);
+
+        let dropped = parse(&i).unwrap();
+        assert_eq!(dropped.data.ch1.len(), 64);
+        assert_eq!(
+            dropped.skipped()[..2],
+            [
+                Skip {
+                    channel: Channel::Ch1,
+                    samples: 12,
+                    reason: SkipReason::RollStop,
+                },
+                Skip {
+                    channel: Channel::Ch1,
+                    samples: 4,
+                    reason: SkipReason::Sentinel,
+                },
+            ]
+        );
+
+        let options = || ParseOptions::new().invalid_samples(InvalidSamples::Keep);
+        let kept = parse_with(&i, options()).unwrap();
+        assert_eq!(kept.data.invalid, 12);
+        assert_eq!(kept.data.ch1.len(), 76);
+        assert_eq!(kept.data.ch1[64..], [0x55; 12]);
+        assert_eq!(kept.data.valid(Channel::Ch1), &dropped.data.ch1[..]);
+        assert_eq!(
+            kept.data.mask(Channel::Ch2).iter().filter(|&&v| v).count(),
+            64
+        );
+        assert_eq!(kept.skipped().len(), 2);
+        let (trace, expected) = (
+            kept.trace(Channel::Ch1).unwrap(),
+            dropped.trace(Channel::Ch1).unwrap(),
+        );
+        assert_eq!(trace.samples, expected.samples);
+        assert_eq!(trace.time_offset, expected.time_offset);
+        assert_eq!(parse_reader_with(&i[..], options()).unwrap(), kept);
+
+        // Kept samples are written back as padding
+        let mut o = Vec::new();
+        write(&kept, &mut o).unwrap();
+        assert_eq!(o, i);
+
+        // Corrupted number of second channel points
+        i[229..233].fill(0xff);
+        assert!(matches!(
+            parse_with(&i, options()),
+            Err(Error::Invalid { .. })
+        ));
+    }
+
+    #[test]
+    fn logic_length() {
+        let data = WaveformHeader::builder()
+            .channel(Channel::Ch1, 1.0, 0.0)
+            .logic(0xffff, 100.0e6)
+            .build_with(RawData {
+                ch1: vec![100; 64].into(),
+                logic: vec![0x1234; 32].into(),
+                ..RawData::default()
+            });
+        let mut i = Vec::new();
+        write(&data, &mut i).unwrap();
+
+        let r = parse(&i).unwrap();
+        assert_eq!(r.header.logic_length, LogicLength::Detected(32));
+        assert_eq!(r.data.logic, data.data.logic);
+        let s = parse_reader_with(&i[..], ParseOptions::new().max_bytes(1024)).unwrap();
+        assert_eq!(s, r);
+        let s = parse_reader_with(&i[..], ParseOptions::new().max_bytes(usize::MAX)).unwrap();
+        assert_eq!(s, r);
+        assert_eq!(crate::parse_any(&i).unwrap(), r);
+
+        let options = || ParseOptions::new().logic_points(16);
+        let e = parse_with(&i, options()).unwrap();
+        assert_eq!(e.header.logic_length, LogicLength::Explicit(16));
+        assert_eq!(e.data.logic.len(), 16);
+        assert_eq!(parse_reader_with(&i[..], options()).unwrap(), e);
+
+        // Odd size can not be detected
+        i.push(0);
+        assert!(matches!(parse(&i), Err(Error::Truncated { .. })));
+        assert!(matches!(
+            parse_reader_with(&i[..], ParseOptions::new().max_bytes(420)),
+            Err(Error::Truncated { .. })
+        ));
+        assert_eq!(
+            parse_reader_with(&i[..], ParseOptions::new().max_bytes(400)),
+            Err(Error::TooLarge {
+                size: 401,
+                limit: 400
+            })
+        );
+    }
+
+    #[test]
+    fn reader() {
+        let i = read("test/ds1052e_2ch.wfm").unwrap();
+        let r = parse(&i).unwrap();
+        let s = parse_reader(std::fs::File::open("test/ds1052e_2ch.wfm").unwrap()).unwrap();
+
+        assert_eq!(format!("{:?}", s.header), format!("{:?}", r.header));
+        assert_eq!(s.data.ch1, r.data.ch1);
+        assert_eq!(s.data.ch2, r.data.ch2);
+        assert_eq!(s.data.logic, r.data.logic);
+    }
+
+    #[test]
+    fn progress() {
+        let i = read("test/ds1052e_2ch.wfm").unwrap();
+        let mut p = Vec::new();
+        parse_reader_with(
+            &i[..],
+            ParseOptions::new().progress(|progress| p.push(progress)),
+        )
+        .unwrap();
+
+        assert_eq!(p[0].stage, Stage::Header);
+        assert!(p.windows(2).all(|pair| pair[0].done <= pair[1].done));
+        let last = p.last().unwrap();
+        assert_eq!(last.stage, Stage::Channel(Channel::Ch2));
+        assert_eq!(last.done, i.len());
+        assert_eq!(last.fraction(), 1.0);
+        // Blocks of channel and completion of each stage
+        assert!(p.len() > 16);
+        assert_eq!(p.iter().filter(|p| p.is_stage_complete()).count(), 3);
+
+        let mut c = Vec::new();
+        parse_with(
+            &i,
+            ParseOptions::new().progress(|progress| c.push(progress)),
+        )
+        .unwrap();
+        assert_eq!(c.len(), 3);
+        assert_eq!(c[2], *last);
+    }
+
+    #[test]
+    fn limit() {
+        let i = read("test/ds1052e_2ch.wfm").unwrap();
+        let o = || ParseOptions::new().max_bytes(1 << 20);
+
+        assert_eq!(
+            parse_reader_with(&i[..], o()).unwrap_err(),
+            Error::TooLarge {
+                size: i.len(),
+                limit: 1 << 20
+            }
+        );
+        assert!(parse_with(&i, o()).is_err());
+        assert!(parse_reader_with(&i[..], ParseOptions::new().max_bytes(i.len())).is_ok());
+
+        // Point count which is less than trailing sentinel
+        let mut h = i[..HEADER_SIZE].to_vec();
+        h[28..32].copy_from_slice(&2u32.to_le_bytes());
+        assert_eq!(
+            parse(&h).unwrap_err(),
+            Error::Invalid {
+                context: "header",
+                offset: 28
+            }
+        );
+    }
+
+    #[test]
+    fn borrowed() {
+        let i = read("test/ds1052e_2ch.wfm").unwrap();
+        let r = parse_ref(&i).unwrap();
+
+        assert_eq!(r.data.ch1.as_ptr(), i[HEADER_SIZE..].as_ptr());
+        assert_eq!(r.data.ch1.len(), 524284);
+        assert_eq!(r.to_owned().data.ch2, *r.data.ch2);
+    }
+
+    #[test]
+    fn errors() {
+        let i = read("test/ds1052e_2ch.wfm").unwrap();
+
+        assert_eq!(
+            parse(&i[..100]).unwrap_err(),
+            Error::Truncated {
+                context: "header",
+                offset: 100,
+                needed: Some(4)
+            }
+        );
+        assert!(matches!(
+            parse(&i[..1000]).unwrap_err(),
+            Error::Truncated {
+                context: "raw data",
+                ..
+            }
+        ));
+        assert_eq!(
+            parse_reader(&i[..1000]).unwrap_err(),
+            Error::Truncated {
+                context: "raw data",
+                offset: 1000,
+                needed: Some(524284 + 4 - 724)
+            }
+        );
+        assert_eq!(
+            parse(b"RIFF0000").unwrap_err(),
+            Error::BadMagic {
+                found: b"RIFF".to_vec()
+            }
+        );
+    }
 }
@@ -0,0 +1,104 @@
+/*!
+
+Probe attenuation
+
+*/
+use super::{Channel, ChannelHeader, WaveformData};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Probe attenuation ratio
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Probe {
+    X1,
+    X10,
+    X100,
+    /// Other ratio, like 5x or 1000x
+    Custom(f32),
+}
+
+impl Probe {
+    /// Probe of attenuation ratio
+    pub fn from_ratio(ratio: f32) -> Self {
+        match ratio {
+            1.0 => Probe::X1,
+            10.0 => Probe::X10,
+            100.0 => Probe::X100,
+            ratio => Probe::Custom(ratio),
+        }
+    }
+
+    /// Attenuation ratio
+    pub fn ratio(&self) -> f32 {
+        match self {
+            Probe::X1 => 1.0,
+            Probe::X10 => 10.0,
+            Probe::X100 => 100.0,
+            Probe::Custom(ratio) => *ratio,
+        }
+    }
+}
+
+impl From<f32> for Probe {
+    fn from(ratio: f32) -> Self {
+        Self::from_ratio(ratio)
+    }
+}
+
+impl ChannelHeader {
+    /// Probe which was set on instrument
+    pub fn probe(&self) -> Probe {
+        Probe::from_ratio(self.probe_value)
+    }
+
+    /// Same settings with other probe
+    ///
+    /// Scaling is recomputed, so samples are converted as if probe was set
+    /// properly on instrument.
+    pub fn with_probe(&self, probe: impl Into<Probe>) -> Self {
+        ChannelHeader {
+            probe_value: probe.into().ratio(),
+            ..self.clone()
+        }
+        .scaled()
+    }
+}
+
+impl WaveformData {
+    /// Correct probe of channel
+    ///
+    /// Raw samples are kept, traces are scaled with new ratio.
+    pub fn set_probe(&mut self, channel: Channel, probe: impl Into<Probe>) {
+        let header = match channel {
+            Channel::Ch1 => &mut self.header.ch1,
+            Channel::Ch2 => &mut self.header.ch2,
+        };
+        *header = header.with_probe(probe);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse;
+    use std::fs::read;
+
+    #[test]
+    fn ds1052e_2ch() {
+        let i = read("test/ds1052e_2ch.wfm").unwrap();
+        let mut r = parse(&i).unwrap();
+        assert_eq!(r.header.ch1.probe(), Probe::X1);
+        assert_eq!(r.header.ch1.with_probe(1.0), r.header.ch1);
+
+        let before = r.trace(Channel::Ch1).unwrap();
+        r.set_probe(Channel::Ch1, Probe::X10);
+        let after = r.trace(Channel::Ch1).unwrap();
+
+        assert_eq!(r.header.ch1.probe(), Probe::X10);
+        assert_eq!(r.header.ch1.volt_per_division, 10.0 * 5.0e6);
+        assert!((after.samples[0] - 10.0 * before.samples[0]).abs() < 1.0e-3);
+        assert_eq!(Probe::from(5.0), Probe::Custom(5.0));
+    }
+}
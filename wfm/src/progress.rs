@@ -0,0 +1,94 @@
+/*!
+
+Progress reporting
+
+*/
+use super::Channel;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Stage of processing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Stage {
+    Header,
+    /// Samples of analog channel
+    Channel(Channel),
+    /// Samples of logic analyzer
+    Logic,
+    /// Writing of exported rows
+    Export,
+}
+
+/// Processing progress
+///
+/// Amounts are bytes when parsing and rows when exporting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Progress {
+    pub stage: Stage,
+    /// Processed amount of current stage
+    pub stage_done: usize,
+    /// Total amount of current stage
+    pub stage_total: usize,
+    /// Processed amount overall
+    pub done: usize,
+    /// Total amount overall
+    pub total: usize,
+}
+
+impl Progress {
+    /// Overall completion (0..1)
+    pub fn fraction(&self) -> f32 {
+        if self.total > 0 {
+            self.done as f32 / self.total as f32
+        } else {
+            1.0
+        }
+    }
+
+    /// Current stage is complete
+    pub fn is_stage_complete(&self) -> bool {
+        self.stage_done >= self.stage_total
+    }
+}
+
+/// Progress callback
+pub type Callback<'a> = Box<dyn FnMut(Progress) + 'a>;
+
+/// Progress tracking over sequence of stages
+pub(crate) struct Reporter<'a> {
+    callback: Option<Callback<'a>>,
+    /// Amount of completed stages
+    base: usize,
+    pub total: usize,
+}
+
+impl<'a> Reporter<'a> {
+    pub fn new(callback: Option<Callback<'a>>, total: usize) -> Self {
+        Self {
+            callback,
+            base: 0,
+            total,
+        }
+    }
+
+    /// Report progress of stage
+    ///
+    /// Stage is considered completed when done amount reaches total.
+    pub fn report(&mut self, stage: Stage, done: usize, total: usize) {
+        if let Some(callback) = &mut self.callback {
+            callback(Progress {
+                stage,
+                stage_done: done,
+                stage_total: total,
+                done: self.base + done,
+                total: self.total,
+            });
+        }
+        if done >= total {
+            self.base += total;
+        }
+    }
+}
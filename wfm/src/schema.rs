@@ -0,0 +1,338 @@
+/*!
+
+Versioned serialization schema
+
+Types of this module are decoupled from the in-memory structures, so stored
+captures remain loadable when those change. Only measured values are stored,
+derived ones are computed while loading.
+
+Version history:
+
+- 0: unversioned output of serializing [`WaveformData`] directly, it lacks
+  trigger mode and logic analyzer sample rate
+- 1: current schema
+
+*/
+use super::{
+    annotations::Annotation, ChannelHeader, Coupling, Error, LogicAnalyzerHeader, LogicLength,
+    Model, RawData, Result, Shared, Source, TimeHeader, TriggerHeader, TriggerMode, Unit,
+    WaveformData, WaveformHeader,
+};
+use serde::{Deserialize, Serialize};
+
+/// Current schema version
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Serialized capture
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Archive {
+    /// Missing in unversioned data
+    #[serde(default)]
+    pub schema_version: u32,
+    pub header: HeaderV1,
+    pub data: SamplesV1,
+    /// Added in version 1
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<Annotation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HeaderV1 {
+    pub adc_mode: u8,
+    pub roll_stop: u32,
+    pub active_channel: u8,
+    pub ch1: ChannelV1,
+    pub ch2: ChannelV1,
+    pub time: TimeV1,
+    pub time2: TimeV1,
+    /// Added in version 1
+    #[serde(default)]
+    pub trigger_mode: Option<TriggerMode>,
+    pub trigger1: TriggerV1,
+    pub trigger2: TriggerV1,
+    pub logic: LogicV1,
+    /// Added in version 1
+    #[serde(default)]
+    pub logic_sample_rate_hz: Option<f32>,
+    pub ch1_points: u32,
+    pub ch1_skip: u32,
+    pub ch2_points: u32,
+    /// Added in version 1
+    #[serde(default)]
+    pub logic_points: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelV1 {
+    pub enabled: bool,
+    pub inverted: bool,
+    pub scale_display: i32,
+    pub shift_display: i16,
+    pub invert_display: u8,
+    pub probe_value: f32,
+    pub scale_measured: i32,
+    pub shift_measured: i16,
+    /// Missing in early version 1 archives, which were taken as volts
+    #[serde(default)]
+    pub unit: Unit,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimeV1 {
+    pub scale_display: i64,
+    pub offset_display: i64,
+    pub sample_rate_hz: f32,
+    pub scale_measured: i64,
+    pub offset_measured: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TriggerV1 {
+    pub mode: TriggerMode,
+    pub source: Source,
+    pub coupling: Coupling,
+    pub sweep: u8,
+    pub sens: f32,
+    pub holdoff: f32,
+    pub level: f32,
+    pub direct: bool,
+    pub pulse_type: u8,
+    pub pulse_width: f32,
+    pub slope_type: u8,
+    pub lower: f32,
+    pub slope_width: f32,
+    pub video_pol: u8,
+    pub video_sync: u8,
+    pub video_std: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogicV1 {
+    pub enabled: bool,
+    pub active_channel: u8,
+    pub enabled_channels: u16,
+    pub position: [u8; 16],
+    pub group8to15size: u8,
+    pub group0to7size: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SamplesV1 {
+    pub ch1: Shared<u8>,
+    pub ch2: Shared<u8>,
+    pub logic: Shared<u16>,
+    /// Added in version 1
+    #[serde(default)]
+    pub invalid: usize,
+}
+
+impl From<&ChannelHeader> for ChannelV1 {
+    fn from(header: &ChannelHeader) -> Self {
+        Self {
+            enabled: header.enabled,
+            inverted: header.inverted,
+            scale_display: header.scale_display,
+            shift_display: header.shift_display,
+            invert_display: header.invert_display,
+            probe_value: header.probe_value,
+            scale_measured: header.scale_measured,
+            shift_measured: header.shift_measured,
+            unit: header.unit,
+        }
+    }
+}
+
+impl From<ChannelV1> for ChannelHeader {
+    fn from(channel: ChannelV1) -> Self {
+        ChannelHeader {
+            scale_display: channel.scale_display,
+            shift_display: channel.shift_display,
+            probe_value: channel.probe_value,
+            invert_display: channel.invert_display,
+            scale_measured: channel.scale_measured,
+            shift_measured: channel.shift_measured,
+            inverted: channel.inverted,
+            enabled: channel.enabled,
+            volt_per_division: 0.0,
+            volt_scale: 0.0,
+            volt_offset: 0.0,
+            unit: channel.unit,
+        }
+        .scaled()
+    }
+}
+
+macro_rules! same_fields {
+    ( $( $from:ty => $to:ident { $( $field:ident, )* } )* ) => {
+        $(
+            impl From<&$from> for $to {
+                fn from(value: &$from) -> Self {
+                    Self { $( $field: value.$field.clone(), )* }
+                }
+            }
+
+            impl From<$to> for $from {
+                fn from(value: $to) -> Self {
+                    Self { $( $field: value.$field, )* }
+                }
+            }
+        )*
+    };
+}
+
+same_fields! {
+    TimeHeader => TimeV1 {
+        scale_display, offset_display, sample_rate_hz, scale_measured, offset_measured,
+    }
+    TriggerHeader => TriggerV1 {
+        mode, source, coupling, sweep, sens, holdoff, level, direct, pulse_type,
+        pulse_width, slope_type, lower, slope_width, video_pol, video_sync, video_std,
+    }
+    LogicAnalyzerHeader => LogicV1 {
+        enabled, active_channel, enabled_channels, position, group8to15size, group0to7size,
+    }
+    RawData => SamplesV1 {
+        ch1, ch2, logic, invalid,
+    }
+}
+
+impl From<&WaveformData> for Archive {
+    fn from(data: &WaveformData) -> Self {
+        let header = &data.header;
+
+        Archive {
+            schema_version: SCHEMA_VERSION,
+            header: HeaderV1 {
+                adc_mode: header.adc_mode,
+                roll_stop: header.roll_stop,
+                active_channel: header.active_channel,
+                ch1: (&header.ch1).into(),
+                ch2: (&header.ch2).into(),
+                time: (&header.time).into(),
+                time2: (&header.time2).into(),
+                trigger_mode: Some(header.trigger_mode),
+                trigger1: (&header.trigger1).into(),
+                trigger2: (&header.trigger2).into(),
+                logic: (&header.logic).into(),
+                logic_sample_rate_hz: Some(header.logic_sample_rate_hz),
+                ch1_points: header.ch1_points,
+                ch1_skip: header.ch1_skip,
+                ch2_points: header.ch2_points,
+                logic_points: if header.logic_length.is_assumed() {
+                    None
+                } else {
+                    Some(header.logic_length.points())
+                },
+            },
+            data: (&data.data).into(),
+            annotations: Vec::new(),
+        }
+    }
+}
+
+impl Archive {
+    /// Convert to waveform upgrading older versions
+    pub fn into_waveform(self) -> Result<WaveformData> {
+        if self.schema_version > SCHEMA_VERSION {
+            return Err(Error::Unsupported(format!(
+                "schema version {}",
+                self.schema_version
+            )));
+        }
+
+        let header = self.header;
+        let logic = LogicAnalyzerHeader::from(header.logic);
+        // Unknown for older archives
+        let logic_length = match header.logic_points {
+            Some(points) => LogicLength::Detected(points),
+            None if logic.enabled => LogicLength::Assumed(header.ch1_points),
+            None => LogicLength::Detected(0),
+        };
+
+        Ok(WaveformData {
+            header: WaveformHeader {
+                model: if logic.enabled {
+                    Model::Ds1000D
+                } else {
+                    Model::Ds1000E
+                },
+                adc_mode: header.adc_mode,
+                roll_stop: header.roll_stop,
+                active_channel: header.active_channel,
+                ch1: header.ch1.into(),
+                ch2: header.ch2.into(),
+                time: header.time.into(),
+                time2: header.time2.into(),
+                // Version 0 captures have only per trigger modes
+                trigger_mode: header.trigger_mode.unwrap_or(header.trigger1.mode),
+                trigger1: header.trigger1.into(),
+                trigger2: header.trigger2.into(),
+                logic,
+                // Zero means unknown rate
+                logic_sample_rate_hz: header.logic_sample_rate_hz.unwrap_or(0.0),
+                ch1_points: header.ch1_points,
+                ch1_skip: header.ch1_skip,
+                ch2_points: header.ch2_points,
+                logic_length,
+                raw: None,
+            },
+            data: self.data.into(),
+        })
+    }
+}
+
+impl WaveformData {
+    /// Versioned serializable representation
+    pub fn to_archive(&self) -> Archive {
+        self.into()
+    }
+}
+
+#[cfg(all(test, feature = "json"))]
+mod test {
+    use super::*;
+    use crate::parse;
+    use std::fs::read;
+
+    #[test]
+    fn versions() {
+        let i = read("test/ds1052e_2ch.wfm").unwrap();
+        let r = parse(&i).unwrap();
+
+        let json = serde_json::to_string(&r.to_archive()).unwrap();
+        assert!(json.starts_with(r#"{"schema_version":1,"#));
+        let a: Archive = serde_json::from_str(&json).unwrap();
+        assert_eq!(a.into_waveform().unwrap(), r);
+
+        let mut current = r.clone();
+        current.header.ch2.unit = Unit::A;
+        let json = serde_json::to_string(&current.to_archive()).unwrap();
+        let a: Archive = serde_json::from_str(&json).unwrap();
+        assert_eq!(a.into_waveform().unwrap().header.ch2.unit, Unit::A);
+        let mut early = serde_json::to_value(current.to_archive()).unwrap();
+        early["header"]["ch2"]
+            .as_object_mut()
+            .unwrap()
+            .remove("unit");
+        let a: Archive = serde_json::from_value(early).unwrap();
+        assert_eq!(a.into_waveform().unwrap().header.ch2.unit, Unit::V);
+
+        // Unversioned output without fields of later versions
+        let mut legacy = serde_json::to_value(&current).unwrap();
+        let header = legacy["header"].as_object_mut().unwrap();
+        header.remove("trigger_mode");
+        header.remove("logic_sample_rate_hz");
+        header.remove("model");
+        let a: Archive = serde_json::from_value(legacy).unwrap();
+        assert_eq!(a.schema_version, 0);
+        let w = a.into_waveform().unwrap();
+        assert_eq!(w.header.trigger_mode, r.header.trigger1.mode);
+        assert_eq!(w.header.ch1, r.header.ch1);
+        assert_eq!(w.header.ch2.unit, Unit::A);
+        assert_eq!(w.data, r.data);
+
+        let mut future = r.to_archive();
+        future.schema_version = SCHEMA_VERSION + 1;
+        assert!(future.into_waveform().is_err());
+    }
+}
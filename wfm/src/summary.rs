@@ -0,0 +1,216 @@
+/*!
+
+Human-readable summary of waveform
+
+*/
+use super::{Channel, Coupling, Model, Source, TriggerMode, Unit, WaveformData};
+use core::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Settings of enabled analog channel
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ChannelSummary {
+    pub channel: Channel,
+    /// Vertical scale in units per division
+    pub scale: f32,
+    /// Level of screen center in units
+    pub center: f32,
+    pub probe: f32,
+    pub inverted: bool,
+    pub unit: Unit,
+    pub points: usize,
+}
+
+/// Trigger settings
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TriggerSummary {
+    pub mode: TriggerMode,
+    pub source: Source,
+    pub coupling: Coupling,
+    pub level: f32,
+}
+
+/// Key facts of waveform
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Summary {
+    pub model: Model,
+    pub channels: Vec<ChannelSummary>,
+    pub sample_rate_hz: f32,
+    /// Duration of longest channel in seconds
+    pub duration: f32,
+    /// Horizontal scale in seconds per division
+    pub time_scale: f32,
+    /// Horizontal offset in seconds
+    pub time_offset: f32,
+    pub trigger: TriggerSummary,
+    /// Mask of enabled logic analyzer channels
+    pub logic_channels: Option<u16>,
+}
+
+impl WaveformData {
+    /// Summary of waveform
+    pub fn summary(&self) -> Summary {
+        let header = &self.header;
+
+        // Traces are not built to avoid scaling of samples
+        let channels = [Channel::Ch1, Channel::Ch2]
+            .iter()
+            .filter_map(|&channel| {
+                let settings = match channel {
+                    Channel::Ch1 => &header.ch1,
+                    Channel::Ch2 => &header.ch2,
+                };
+                let points = self.data.valid(channel).len();
+                if !settings.enabled || points == 0 {
+                    return None;
+                }
+                Some(ChannelSummary {
+                    channel,
+                    // Division is 25 ADC steps
+                    scale: 25.0 * settings.volt_scale,
+                    center: settings.center_volts(),
+                    probe: settings.probe_value,
+                    inverted: settings.inverted,
+                    unit: settings.unit,
+                    points,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let duration = channels
+            .iter()
+            .map(|summary| {
+                let time_step = 1.0 / self.channel_time(summary.channel).sample_rate_hz;
+                summary.points as f32 * time_step
+            })
+            .fold(0.0, f32::max);
+
+        Summary {
+            model: header.model,
+            channels,
+            sample_rate_hz: header.time.sample_rate_hz,
+            duration,
+            time_scale: header.time.scale(),
+            time_offset: header.time.offset(),
+            trigger: TriggerSummary {
+                mode: header.trigger_mode,
+                source: header.trigger1.source,
+                coupling: header.trigger1.coupling,
+                level: header.trigger1.level,
+            },
+            logic_channels: if header.logic.enabled {
+                Some(header.logic.enabled_channels)
+            } else {
+                None
+            },
+        }
+    }
+}
+
+/// Value with SI prefix
+struct Si<'a>(f32, &'a str);
+
+impl<'a> fmt::Display for Si<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const PREFIXES: [(f32, &str); 7] = [
+            (1.0e9, "G"),
+            (1.0e6, "M"),
+            (1.0e3, "k"),
+            (1.0, ""),
+            (1.0e-3, "m"),
+            (1.0e-6, "µ"),
+            (1.0e-9, "n"),
+        ];
+
+        let Si(value, unit) = *self;
+        // Avoid negative zero
+        let value = if value == 0.0 { 0.0 } else { value };
+        let (scale, prefix) = PREFIXES
+            .iter()
+            .copied()
+            .find(|&(scale, _)| value.abs() >= scale)
+            .filter(|_| value != 0.0)
+            .unwrap_or((1.0, ""));
+        // Round to significant digits to hide float noise
+        let value = format!("{:.4}", value / scale);
+        let value = value.trim_end_matches('0').trim_end_matches('.');
+
+        write!(f, "{} {}{}", value, prefix, unit)
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Model: {}", self.model.name())?;
+        writeln!(f, "Sample rate: {}", Si(self.sample_rate_hz, "Sa/s"))?;
+        writeln!(f, "Duration: {}", Si(self.duration, "s"))?;
+        writeln!(
+            f,
+            "Timebase: {}/div, offset {}",
+            Si(self.time_scale, "s"),
+            Si(self.time_offset, "s")
+        )?;
+        writeln!(
+            f,
+            "Trigger: {:?} on {:?}, {:?} coupling, level {}",
+            self.trigger.mode,
+            self.trigger.source,
+            self.trigger.coupling,
+            Si(self.trigger.level, "V")
+        )?;
+        for channel in &self.channels {
+            let unit = format!("{:?}", channel.unit);
+            write!(
+                f,
+                "{:?}: {}/div, center {}, probe {}x, {} points",
+                channel.channel,
+                Si(channel.scale, &unit),
+                Si(channel.center, &unit),
+                channel.probe,
+                channel.points
+            )?;
+            if channel.inverted {
+                write!(f, ", inverted")?;
+            }
+            writeln!(f)?;
+        }
+        if let Some(channels) = self.logic_channels {
+            writeln!(f, "Logic: channels {:#06x}", channels)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{parse, Channel};
+    use std::fs::read;
+
+    #[test]
+    fn ds1052e_2ch() {
+        let i = read("test/ds1052e_2ch.wfm").unwrap();
+        let r = parse(&i).unwrap();
+        let s = r.summary();
+
+        assert_eq!(s.channels.len(), 2);
+        let trace = r.trace(Channel::Ch2).unwrap();
+        assert_eq!(s.channels[1].points, trace.len());
+        assert_eq!(s.duration, trace.duration());
+        assert_eq!(s.channels[0].scale, 5.0);
+        assert_eq!(
+            s.to_string(),
+            "Model: DS1000E\n\
+             Sample rate: 100 MSa/s\n\
+             Duration: 5.2428 ms\n\
+             Timebase: 50 µs/div, offset 2.112 ms\n\
+             Trigger: Edge on Ch1, Dc coupling, level 3 V\n\
+             Ch1: 5 V/div, center 10.2 V, probe 1x, 524284 points\n\
+             Ch2: 5 V/div, center 0 V, probe 1x, 524284 points\n"
+        );
+    }
+}
@@ -0,0 +1,155 @@
+/*!
+
+Scaled channel samples
+
+*/
+use super::{convert, Channel, ChannelHeader, TimeHeader, TriggerMode, Unit, WaveformData};
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// ADC code which corresponds to the zero level on screen
+pub(crate) const ADC_ZERO: f32 = 125.0;
+
+/// Samples converted by single task
+#[cfg(feature = "rayon")]
+const CHUNK_SIZE: usize = 1 << 16;
+
+/// Scaled samples of single channel
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Trace {
+    /// Sample values in units
+    pub samples: Vec<f32>,
+    pub sample_rate_hz: f32,
+    /// Time of first sample relative to trigger in seconds
+    pub time_offset: f32,
+    pub unit: Unit,
+}
+
+impl Trace {
+    /// Number of samples
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Trace has no samples
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Time between samples in seconds
+    pub fn time_step(&self) -> f32 {
+        1.0 / self.sample_rate_hz
+    }
+
+    /// Time of sample in seconds relative to trigger
+    pub fn time(&self, index: usize) -> f32 {
+        self.time_offset + index as f32 * self.time_step()
+    }
+
+    /// Record length in seconds
+    pub fn duration(&self) -> f32 {
+        self.samples.len() as f32 * self.time_step()
+    }
+}
+
+impl ChannelHeader {
+    /// Convert raw ADC sample to units
+    pub fn volts(&self, raw: u8) -> f32 {
+        let volts = (ADC_ZERO - raw as f32) * self.volt_scale - self.volt_offset;
+
+        if self.inverted {
+            -volts
+        } else {
+            volts
+        }
+    }
+
+    /// Convert value in units to nearest raw ADC sample
+    pub fn raw(&self, volts: f32) -> u8 {
+        let volts = if self.inverted { -volts } else { volts };
+        let raw = ADC_ZERO - (volts + self.volt_offset) / self.volt_scale;
+
+        raw.round().clamp(0.0, 255.0) as u8
+    }
+
+    /// Convert raw ADC samples to units
+    pub fn volts_all(&self, raw: &[u8]) -> Vec<f32> {
+        let mut out = vec![0.0; raw.len()];
+
+        #[cfg(feature = "rayon")]
+        out.par_chunks_mut(CHUNK_SIZE)
+            .zip(raw.par_chunks(CHUNK_SIZE))
+            .for_each(|(out, raw)| convert::volts(self, raw, out));
+        #[cfg(not(feature = "rayon"))]
+        convert::volts(self, raw, &mut out);
+
+        out
+    }
+
+    /// Level of screen center in units
+    pub fn center_volts(&self) -> f32 {
+        let volts = -self.volt_offset;
+
+        if self.inverted {
+            -volts
+        } else {
+            volts
+        }
+    }
+}
+
+impl TimeHeader {
+    /// Horizontal offset in seconds
+    pub fn offset(&self) -> f32 {
+        1.0e-12 * self.offset_measured as f32
+    }
+
+    /// Horizontal scale in seconds per division
+    pub fn scale(&self) -> f32 {
+        1.0e-12 * self.scale_measured as f32
+    }
+}
+
+impl WaveformData {
+    /// Timebase of channel
+    pub(crate) fn channel_time(&self, channel: Channel) -> &TimeHeader {
+        // In alternate trigger mode second channel has own timebase
+        if channel == Channel::Ch2 && self.header.trigger_mode == TriggerMode::Alt {
+            &self.header.time2
+        } else {
+            &self.header.time
+        }
+    }
+
+    /// Get scaled samples of channel
+    ///
+    /// Returns `None` when channel is disabled or has no samples. Kept invalid
+    /// samples are left out.
+    pub fn trace(&self, channel: Channel) -> Option<Trace> {
+        let header = match channel {
+            Channel::Ch1 => &self.header.ch1,
+            Channel::Ch2 => &self.header.ch2,
+        };
+        let raw = self.data.valid(channel);
+
+        if !header.enabled || raw.is_empty() {
+            return None;
+        }
+
+        let time = self.channel_time(channel);
+        let sample_rate_hz = time.sample_rate_hz;
+        // The middle of valid samples corresponds to the middle of screen
+        let time_offset = time.offset() - raw.len() as f32 / sample_rate_hz / 2.0;
+
+        Some(Trace {
+            samples: header.volts_all(raw),
+            sample_rate_hz,
+            time_offset,
+            unit: header.unit,
+        })
+    }
+}
@@ -0,0 +1,159 @@
+/*!
+
+Viewport of traces
+
+Channel traces with vertical settings of instrument screen and visible time
+window which zooms and pans within them, shared by graphical and terminal
+front-ends.
+
+*/
+use super::{Channel, Trace, WaveformData};
+
+/// Minimum visible number of samples
+const MIN_SAMPLES: f32 = 10.0;
+
+/// Trace with vertical settings of screen
+#[derive(Debug, Clone)]
+pub struct ScreenTrace {
+    pub channel: Channel,
+    pub trace: Trace,
+    /// Vertical scale in units per division
+    pub scale: f32,
+    /// Level of screen center in units
+    pub center: f32,
+}
+
+impl WaveformData {
+    /// Enabled channels with vertical settings of screen
+    pub fn screen_traces(&self) -> Vec<ScreenTrace> {
+        [Channel::Ch1, Channel::Ch2]
+            .iter()
+            .filter_map(|&channel| {
+                let trace = self.trace(channel)?;
+                let header = match channel {
+                    Channel::Ch1 => &self.header.ch1,
+                    Channel::Ch2 => &self.header.ch2,
+                };
+                Some(ScreenTrace {
+                    channel,
+                    trace,
+                    // Division is 25 ADC steps
+                    scale: 25.0 * header.volt_scale,
+                    center: header.center_volts(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Visible time window in seconds relative to trigger
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub start: f32,
+    pub end: f32,
+    full: (f32, f32),
+    min_width: f32,
+}
+
+impl Viewport {
+    /// Window covering all traces
+    pub fn of<'a>(traces: impl Iterator<Item = &'a Trace>) -> Self {
+        let (start, end, step) = traces.fold(
+            (f32::INFINITY, f32::NEG_INFINITY, f32::INFINITY),
+            |(start, end, step), trace| {
+                (
+                    start.min(trace.time_offset),
+                    end.max(trace.time_offset + trace.duration()),
+                    step.min(trace.time_step()),
+                )
+            },
+        );
+        let (start, end) = if start < end {
+            (start, end)
+        } else {
+            (0.0, 1.0)
+        };
+
+        Self {
+            start,
+            end,
+            full: (start, end),
+            min_width: if step.is_finite() {
+                MIN_SAMPLES * step
+            } else {
+                0.0
+            },
+        }
+    }
+
+    pub fn width(&self) -> f32 {
+        self.end - self.start
+    }
+
+    pub fn center(&self) -> f32 {
+        (self.start + self.end) / 2.0
+    }
+
+    /// Zoom around time by factor, greater factor zooms in
+    pub fn zoom(&mut self, time: f32, factor: f32) {
+        let full = self.full.1 - self.full.0;
+        let width = (self.width() / factor).clamp(self.min_width.min(full), full);
+        let fraction = (time - self.start) / self.width();
+
+        self.start = time - fraction * width;
+        self.end = self.start + width;
+        self.pan(0.0);
+    }
+
+    /// Move window by time keeping it within traces
+    pub fn pan(&mut self, delta: f32) {
+        let width = self.width();
+        self.start = (self.start + delta).clamp(self.full.0, self.full.1 - width);
+        self.end = self.start + width;
+    }
+
+    /// Show all traces
+    pub fn reset(&mut self) {
+        self.start = self.full.0;
+        self.end = self.full.1;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{parse, Unit};
+    use std::fs::read;
+
+    #[test]
+    fn zoom_pan() {
+        let trace = Trace {
+            samples: vec![0.0; 1000],
+            sample_rate_hz: 1.0e3,
+            time_offset: -0.5,
+            unit: Unit::V,
+        };
+        let mut view = Viewport::of([&trace].iter().copied());
+        assert_eq!((view.start, view.end), (-0.5, 0.5));
+
+        view.zoom(0.0, 4.0);
+        assert_eq!((view.start, view.end), (-0.125, 0.125));
+        view.pan(1.0);
+        assert_eq!(view.end, 0.5);
+        view.zoom(0.0, 1.0e6);
+        assert!((view.width() - 0.01).abs() < 1.0e-6);
+        view.zoom(0.0, 1.0e-6);
+        assert_eq!((view.start, view.end), (-0.5, 0.5));
+    }
+
+    #[test]
+    fn ds1052e_2ch() {
+        let i = read("test/ds1052e_2ch.wfm").unwrap();
+        let r = parse(&i).unwrap();
+        let traces = r.screen_traces();
+
+        assert_eq!(traces.len(), 2);
+        assert_eq!(traces[1].channel, Channel::Ch2);
+        assert_eq!(traces[1].scale, 5.0);
+    }
+}
@@ -0,0 +1,113 @@
+/*!
+
+Bindings for JavaScript
+
+Module is built with `cargo rustc -p rigol-wfm --lib --features wasm
+--crate-type cdylib --target wasm32-unknown-unknown`.
+
+*/
+use crate::{export, parse, Channel, ChannelHeader, WaveformData};
+use core::convert::TryFrom;
+use wasm_bindgen::prelude::*;
+
+/// Parsed waveform file
+#[wasm_bindgen]
+pub struct Waveform(WaveformData);
+
+#[wasm_bindgen]
+impl Waveform {
+    /// Parse waveform file contents
+    #[wasm_bindgen(constructor)]
+    pub fn new(input: &[u8]) -> Result<Waveform, JsValue> {
+        parse(input)
+            .map(Waveform)
+            .map_err(|error| JsValue::from_str(&error.to_string()))
+    }
+
+    fn channel(&self, channel: u8) -> Result<(Channel, &ChannelHeader), JsValue> {
+        let channel = Channel::try_from(channel)
+            .map_err(|_| JsValue::from_str(&format!("Invalid channel {}", channel)))?;
+        let header = match channel {
+            Channel::Ch1 => &self.0.header.ch1,
+            Channel::Ch2 => &self.0.header.ch2,
+        };
+        Ok((channel, header))
+    }
+
+    /// Active channel
+    #[wasm_bindgen(getter, js_name = activeChannel)]
+    pub fn active_channel(&self) -> u8 {
+        self.0.header.active_channel
+    }
+
+    /// Sample rate in Hz
+    #[wasm_bindgen(getter, js_name = sampleRate)]
+    pub fn sample_rate(&self) -> f32 {
+        self.0.header.time.sample_rate_hz
+    }
+
+    /// Horizontal scale in seconds per division
+    #[wasm_bindgen(getter, js_name = timeScale)]
+    pub fn time_scale(&self) -> f32 {
+        self.0.header.time.scale()
+    }
+
+    /// Horizontal offset in seconds
+    #[wasm_bindgen(getter, js_name = timeOffset)]
+    pub fn time_offset(&self) -> f32 {
+        self.0.header.time.offset()
+    }
+
+    /// Channel is enabled (0 for CH1, 1 for CH2)
+    #[wasm_bindgen(js_name = channelEnabled)]
+    pub fn channel_enabled(&self, channel: u8) -> Result<bool, JsValue> {
+        Ok(self.channel(channel)?.1.enabled)
+    }
+
+    /// Vertical scale of channel in units per ADC step
+    #[wasm_bindgen(js_name = voltScale)]
+    pub fn volt_scale(&self, channel: u8) -> Result<f32, JsValue> {
+        Ok(self.channel(channel)?.1.volt_scale)
+    }
+
+    /// Vertical offset of channel in units
+    #[wasm_bindgen(js_name = voltOffset)]
+    pub fn volt_offset(&self, channel: u8) -> Result<f32, JsValue> {
+        Ok(self.channel(channel)?.1.volt_offset)
+    }
+
+    /// Header as JSON
+    #[wasm_bindgen(js_name = headerJson)]
+    pub fn header_json(&self) -> Result<String, JsValue> {
+        serde_json::to_string(&self.0.header).map_err(|error| JsValue::from_str(&error.to_string()))
+    }
+
+    /// Scaled samples of channel, empty when channel is disabled
+    pub fn samples(&self, channel: u8) -> Result<Vec<f32>, JsValue> {
+        let (channel, _) = self.channel(channel)?;
+        Ok(self
+            .0
+            .trace(channel)
+            .map(|trace| trace.samples)
+            .unwrap_or_default())
+    }
+
+    /// Time of first sample of channel in seconds relative to trigger
+    #[wasm_bindgen(js_name = startTime)]
+    pub fn start_time(&self, channel: u8) -> Result<Option<f32>, JsValue> {
+        let (channel, _) = self.channel(channel)?;
+        Ok(self.0.trace(channel).map(|trace| trace.time_offset))
+    }
+
+    /// Enabled channels as comma separated values
+    #[wasm_bindgen(js_name = toCsv)]
+    pub fn to_csv(&self) -> String {
+        export::to_csv(&self.0)
+    }
+
+    /// Header and scaled samples as JSON
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json(&self) -> String {
+        export::to_json(&self.0)
+    }
+}